@@ -0,0 +1,22 @@
+#![feature(test)]
+
+extern crate test;
+
+use ballast::{BumpHeap, BumpOptions, Handle};
+use test::Bencher;
+
+#[bench]
+fn churning_allocations(b: &mut Bencher) {
+    let mut bump = BumpHeap::new(
+        BumpOptions::new()
+            .young_heap_size(1024 * 64)
+            .old_heap_size(1024 * 64),
+    );
+
+    b.iter(|| {
+        for i in 0..1000 {
+            let handle: Handle<usize> = bump.alloc(i);
+            drop(handle);
+        }
+    });
+}
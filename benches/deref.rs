@@ -0,0 +1,22 @@
+#![feature(test)]
+
+extern crate test;
+
+use ballast::{BumpHeap, Rooted};
+use test::{black_box, Bencher};
+
+/// Compare with and without the `logging` and `trace-access` features
+#[bench]
+fn deref_in_loop(b: &mut Bencher) {
+    let mut bump = BumpHeap::default();
+    let rooted: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+
+    b.iter(|| {
+        let mut sum = 0;
+        for _ in 0..1000 {
+            sum += **black_box(&rooted);
+        }
+
+        sum
+    });
+}
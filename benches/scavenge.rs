@@ -0,0 +1,29 @@
+#![feature(test)]
+
+extern crate test;
+
+use ballast::{BumpHeap, BumpOptions, Handle};
+use test::Bencher;
+
+#[bench]
+fn scavenge_with_large_tenured_set(b: &mut Bencher) {
+    let mut bump = BumpHeap::new(
+        BumpOptions::new()
+            .young_heap_size(1024 * 128)
+            .old_heap_size(1024 * 1024),
+    );
+
+    let tenured: Vec<Handle<usize>> = (0..10_000).map(|i| bump.alloc(i)).collect();
+    bump.scavenge();
+
+    b.iter(|| {
+        for i in 0..100 {
+            let handle: Handle<usize> = bump.alloc(i);
+            drop(handle);
+        }
+
+        bump.scavenge();
+    });
+
+    drop(tenured);
+}
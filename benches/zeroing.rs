@@ -0,0 +1,39 @@
+#![feature(test)]
+
+extern crate test;
+
+use ballast::{BumpHeap, BumpOptions, Handle, YoungReclaim};
+use test::Bencher;
+
+const YOUNG: usize = 1024 * 1024 * 64;
+
+/// Compare with and without the `parallel-zeroing` feature
+#[bench]
+fn scavenge_full_64mib_young_gen(b: &mut Bencher) {
+    scavenge_full_young_gen(b, YoungReclaim::Memset);
+}
+
+/// Compare with `scavenge_full_64mib_young_gen`
+#[bench]
+fn scavenge_full_64mib_young_gen_madv_free(b: &mut Bencher) {
+    scavenge_full_young_gen(b, YoungReclaim::MadvFree);
+}
+
+fn scavenge_full_young_gen(b: &mut Bencher, reclaim: YoungReclaim) {
+    let mut bump = BumpHeap::new(
+        BumpOptions::new()
+            .young_heap_size(YOUNG)
+            .old_heap_size(1024 * 1024)
+            .allow_undersized_old_gen(true)
+            .young_reclaim(reclaim),
+    );
+
+    b.iter(|| {
+        for _ in 0..YOUNG / 4096 - 1 {
+            let handle: Handle<[u64; 511]> = bump.alloc([1; 511]);
+            drop(handle);
+        }
+
+        bump.scavenge();
+    });
+}
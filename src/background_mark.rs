@@ -0,0 +1,119 @@
+use crate::memory::AllocId;
+use alloc::vec::Vec;
+use std::{
+    collections::{HashMap, HashSet},
+    thread::{self, JoinHandle},
+};
+
+/// An object as it was when a background mark started
+#[derive(Debug)]
+pub(crate) struct SnapshotObject {
+    pub(crate) id: AllocId,
+    /// Young objects are always roots, since only scavenges reclaim them
+    pub(crate) young: bool,
+    pub(crate) rooted: bool,
+    pub(crate) edges: Vec<AllocId>,
+}
+
+/// A mark running on its own thread over a snapshot of the object graph, see
+/// [`BumpHeap::request_background_mark`](crate::BumpHeap::request_background_mark)
+#[derive(Debug)]
+pub(crate) struct BackgroundMark {
+    thread: JoinHandle<Marked>,
+    /// The tenured objects the write barrier caught being written to since the
+    /// mark started, as of the last scavenge
+    written: HashSet<AllocId>,
+}
+
+#[derive(Debug)]
+struct Marked {
+    edges: HashMap<AllocId, Vec<AllocId>>,
+    unreachable: HashSet<AllocId>,
+}
+
+impl BackgroundMark {
+    pub(crate) fn start(objects: Vec<SnapshotObject>) -> Self {
+        Self {
+            thread: thread::spawn(move || mark(objects)),
+            written: HashSet::new(),
+        }
+    }
+
+    /// Adds objects the write barrier remembered to the ones written to during the mark
+    pub(crate) fn remember(&mut self, written: HashSet<AllocId>) {
+        self.written.extend(written);
+    }
+
+    /// Waits for the mark to finish, returning the tenured objects that were
+    /// unreachable when it started
+    ///
+    /// Following snapshot-at-the-beginning, objects written to since then
+    /// (`written` and the ones already [remembered](BackgroundMark::remember))
+    /// may have had a handle taken out of them, so they're kept along with
+    /// everything they held in the snapshot
+    pub(crate) fn join(mut self, written: &HashSet<AllocId>) -> HashSet<AllocId> {
+        let Marked {
+            edges,
+            mut unreachable,
+        } = self.thread.join().expect("The background mark panicked");
+
+        self.written.extend(written);
+        let mut pending: Vec<AllocId> = self
+            .written
+            .into_iter()
+            .filter(|id| unreachable.contains(id))
+            .collect();
+        while let Some(id) = pending.pop() {
+            if unreachable.remove(&id) {
+                pending.extend(edges[&id].iter().copied());
+            }
+        }
+
+        unreachable
+    }
+}
+
+/// Finds the tenured objects that can't be reached from the roots, with the
+/// same rules as the heap's stop-the-world mark
+fn mark(objects: Vec<SnapshotObject>) -> Marked {
+    let held: HashSet<AllocId> = objects
+        .iter()
+        .flat_map(|object| object.edges.iter().copied())
+        .collect();
+    let mut pending: Vec<AllocId> = objects
+        .iter()
+        .filter(|object| object.young || (object.rooted && !held.contains(&object.id)))
+        .map(|object| object.id)
+        .collect();
+
+    let tenured: Vec<AllocId> = objects
+        .iter()
+        .filter(|object| !object.young)
+        .map(|object| object.id)
+        .collect();
+    let edges: HashMap<AllocId, Vec<AllocId>> = objects
+        .into_iter()
+        .map(|object| (object.id, object.edges))
+        .collect();
+
+    let mut reached = HashSet::new();
+    while let Some(id) = pending.pop() {
+        if reached.insert(id) {
+            // Edges can point into other heaps, which aren't this heap's to mark
+            pending.extend(
+                edges[&id]
+                    .iter()
+                    .filter(|child| edges.contains_key(child))
+                    .copied(),
+            );
+        }
+    }
+
+    Marked {
+        unreachable: tenured
+            .into_iter()
+            .filter(|id| !reached.contains(id))
+            .collect(),
+        edges,
+    }
+}
@@ -0,0 +1,83 @@
+use core::{marker::PhantomData, ops, slice};
+
+/// A growable run of bytes that lives in the heap, created with
+/// [`BumpHeap::alloc_buffer`](crate::BumpHeap::alloc_buffer) and grown with
+/// [`BumpHeap::extend_buffer`](crate::BumpHeap::extend_buffer)
+///
+/// The bytes are stored directly after the buffer's length
+#[derive(Debug)]
+#[repr(C)]
+pub struct HeapBuffer {
+    len: usize,
+    bytes: [u8; 0],
+}
+
+impl HeapBuffer {
+    pub(crate) const fn empty() -> Self {
+        Self { len: 0, bytes: [] }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        // Safety: The heap always allocates `len` bytes after the buffer's header
+        unsafe { slice::from_raw_parts(self.bytes.as_ptr(), self.len) }
+    }
+
+    pub(crate) fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+/// A fixed run of `T`s that lives in the heap, created with
+/// [`BumpHeap::collect_slice`](crate::BumpHeap::collect_slice)
+///
+/// Like [`HeapBuffer`] the items are stored directly after the slice's length
+#[derive(Debug)]
+#[repr(C)]
+pub struct HeapSlice<T: Copy> {
+    len: usize,
+    items: [T; 0],
+    __type: PhantomData<T>,
+}
+
+impl<T: Copy> HeapSlice<T> {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            len: 0,
+            items: [],
+            __type: PhantomData,
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // Safety: The heap always allocates `len` items after the slice's header
+        unsafe { slice::from_raw_parts(self.items.as_ptr(), self.len) }
+    }
+
+    pub(crate) fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+impl<T: Copy> ops::Deref for HeapSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
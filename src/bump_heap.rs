@@ -1,246 +1,5523 @@
 use crate::{
-    memory::{self, HeapPointer},
-    rooted::{ContainingHeap, HeapValue, Rooted, RootedInner},
-    sweep_heap::SweepHeap,
+    background_mark::{BackgroundMark, SnapshotObject},
+    buffer::{HeapBuffer, HeapSlice},
+    color::Color,
+    counters::{AllocSite, GcCounters, SiteStat},
+    dump::DumpWriter,
+    error::{AllocError, HeapCreateError, OptionsError},
+    events::{EventRing, PauseEvent, PauseKind, PromoteEvent, RelocateEvent},
+    free_list::{FreeBlock, PocketSizes, MAX_POCKETS},
+    fx_hash::FxHasher,
+    handle::Handle,
+    memory::{self, AllocId, GlobalSource, HeapPointer, IdTable, MemorySource},
+    native::NativeSize,
+    pressure::{Pressure, PressureThresholds},
+    promotion::{AgePolicy, PromotionPolicy, Survivor},
+    rooted::{self, ContainingHeap, Epoch, HeapValue, Location, Rooted, RootedInner, TraceFn},
+    sweep_heap::{CompactStats, SweepHeap},
+    trace::{Trace, Tracer},
 };
 
+#[cfg(feature = "type-profile")]
+use crate::counters::TypeStat;
+#[cfg(any(test, feature = "testing"))]
+use crate::workload::{WorkloadSpec, WorkloadStats};
 use alloc::{alloc::Layout, boxed::Box, vec::Vec};
 use core::{
-    any::Any,
-    mem::{self, ManuallyDrop},
+    any::{Any, TypeId},
+    hash::{Hash, Hasher},
+    iter, mem,
     pin::Pin,
-    ptr, raw,
+    ptr,
 };
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+/// The most generations a heap can have, counting the young generation
+pub const MAX_GENERATIONS: usize = 4;
+
+/// The most dead objects' metadata the heap keeps around for reuse
+const MAX_SPARE_ROOTS: usize = 1024;
+
+/// The capacity a root list is never trimmed below, so that small heaps don't
+/// reallocate their root lists over and over
+const MIN_ROOTS_CAPACITY: usize = 64;
 
 pub struct BumpHeap {
     young_start: HeapPointer,
     young_end: HeapPointer,
     young_current: HeapPointer,
     heap_size: usize,
-    intermediate: ManuallyDrop<SweepHeap>,
-    roots: Vec<Pin<Box<RootedInner>>>,
+    owns_region: bool,
+    /// The tenured generations, youngest first, so `generations[0]` holds generation 1
+    generations: Vec<SweepHeap>,
+    /// Roots of objects still in the young generation
+    young_roots: Vec<Pin<Box<RootedInner>>>,
+    /// Roots of the objects in each tenured generation, indexed like `generations`
+    roots: Vec<Vec<Pin<Box<RootedInner>>>>,
+    /// Metadata of dead young objects, reused by allocations instead of boxing new metadata
+    spare_roots: Vec<Pin<Box<RootedInner>>>,
+    counters: GcCounters,
+    /// The counters' allocated and reclaimed bytes from before they were last reset
+    #[cfg(feature = "debug-accounting")]
+    retired: (usize, usize),
+    ids: IdTable,
+    options: BumpOptions,
+    last_promoted: usize,
+    pressure: Pressure,
+    events: EventRing,
+    /// Roughly how much native memory live objects own, measured at the last
+    /// collection triggered by the native limit and added to by every allocation since
+    native_estimate: usize,
+    epoch: Epoch,
+    /// The mark [`BumpHeap::request_background_mark`] started, which the next major
+    /// collection waits for
+    background_mark: Option<BackgroundMark>,
+    /// The scavenge [`BumpHeap::scavenge_step`] is partway through
+    scavenge_cursor: Option<ScavengeCursor>,
+    /// Whether the heap has given its region back, see [`BumpHeap::hibernate`]
+    dormant: bool,
+    /// Whether the first tenured generation was handed to [`BumpHeap::with_intermediate`]
+    /// and lives outside of the heap's region
+    separate_intermediate: bool,
+    /// Whether any object has been [leaked](Rooted::leak), which keeps the heap
+    /// from ever freeing its memory
+    leaked: bool,
+}
+
+/// The progress of a scavenge that's been split into steps
+#[derive(Debug)]
+struct ScavengeCursor {
+    outcome: ScavengeOutcome,
+    promoted_before: usize,
+    reclaimed_before: usize,
+    /// How many of the young roots are survivors kept in the young generation
+    kept: usize,
 }
 
 impl BumpHeap {
-    pub fn new(options: BumpOptions) -> Self {
-        let layout = Layout::from_size_align(
-            options.young_heap_size + options.old_heap_size,
-            memory::page_size(),
+    /// Creates a heap that never collects, only bumping through a `size` byte
+    /// young generation and failing allocations once it's full
+    ///
+    /// Nothing is ever moved or freed, so handles are never invalidated. This is
+    /// meant for measuring how much of a workload's time is spent outside of the
+    /// collector, with the same call sites as a collecting heap
+    pub fn new_bump_only(size: usize) -> Self {
+        Self::new(
+            BumpOptions::new()
+                .young_heap_size(size)
+                .old_heap_size(0)
+                .allow_undersized_old_gen(true)
+                .collect(false),
         )
-        .unwrap();
+    }
 
-        let allocation = unsafe {
-            let allocation = HeapPointer::new(alloc::alloc::alloc_zeroed(layout) as usize);
-            assert!(!allocation.is_null());
-            allocation
-        };
+    /// # Panics
+    ///
+    /// Panics if the heap can't be created, see [`BumpHeap::try_new`]
+    pub fn new(options: BumpOptions) -> Self {
+        match Self::try_new(options) {
+            Ok(heap) => heap,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Creates a heap, failing if its options are invalid or its memory can't be
+    /// allocated
+    pub fn try_new(options: BumpOptions) -> Result<Self, HeapCreateError> {
+        options.validate()?;
+
+        let size = options.heap_size();
+        let allocation = Self::alloc_region(&options, size)?;
+        info!(
+            "Constructed bump allocator with {}kb young generation and {} tenured generations for a total of {}kb allocated",
+            options.young_heap_size / 1024,
+            options.tenured_sizes().len(),
+            size / 1024,
+        );
+
+        let mut heap = unsafe { Self::from_region(allocation, options) };
+        heap.owns_region = true;
+
+        Ok(heap)
+    }
 
-        let (young_start, young_current) = (allocation, allocation);
-        let young_end = allocation + options.young_heap_size;
+    /// Creates a heap whose first tenured generation is `intermediate`, which
+    /// keeps its own memory and pocket sizes instead of being carved out of the
+    /// heap's region
+    ///
+    /// The options' old generation size and pocket sizes are replaced with the
+    /// intermediate generation's, and any further tenured generations are still
+    /// laid out after the young generation. The intermediate generation is freed
+    /// along with the heap. Heaps with a separate intermediate generation can't
+    /// [hibernate](BumpHeap::hibernate)
+    pub fn with_intermediate(
+        options: BumpOptions,
+        intermediate: SweepHeap,
+    ) -> Result<Self, HeapCreateError> {
+        let options = options
+            .old_heap_size(intermediate.size())
+            .old_pocket_sizes(intermediate.pocket_sizes());
+        options.validate()?;
 
+        let size = Self::region_size(&options, true);
+        let allocation = Self::alloc_region(&options, size)?;
         info!(
-            "Constructed bump allocator with {}kb young generation and {}kb old generation for a total of {}kb allocated",
+            "Constructed bump allocator with {}kb young generation and a separate {}kb intermediate generation",
             options.young_heap_size / 1024,
-            options.old_heap_size / 1024,
-            layout.size() / 1024,
+            intermediate.size() / 1024,
+        );
+
+        let mut heap = unsafe { Self::assemble(allocation, options, Some(intermediate)) };
+        heap.owns_region = true;
+
+        Ok(heap)
+    }
+
+    /// Allocates a zeroed `size` byte region for a heap from its memory source
+    fn alloc_region(options: &BumpOptions, size: usize) -> Result<HeapPointer, HeapCreateError> {
+        let align = options.alignment();
+        let layout = Layout::from_size_align(size, align)
+            .map_err(|_| HeapCreateError::InvalidLayout { size, align })?;
+
+        let allocation = unsafe { options.memory_source.alloc_zeroed(layout) };
+        if allocation.is_null() {
+            return Err(HeapCreateError::AllocationFailed { size });
+        }
+        let allocation = HeapPointer::new(allocation as usize);
+        if options.madvise_hugepage {
+            memory::advise_hugepages(allocation, size);
+        }
+        if options.prefault {
+            unsafe { memory::prefault(allocation, size) };
+        }
+
+        Ok(allocation)
+    }
+
+    /// The size of the region a heap allocates, which leaves out a separate
+    /// intermediate generation
+    fn region_size(options: &BumpOptions, separate_intermediate: bool) -> usize {
+        if separate_intermediate {
+            options.heap_size() - options.tenured_sizes[0]
+        } else {
+            options.heap_size()
+        }
+    }
+
+    /// Lays the heap out over a caller-provided buffer at `base`, so that object
+    /// addresses are the same from run to run
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to [`BumpOptions::heap_size`] bytes of writable memory
+    /// that outlives the heap and doesn't move
+    #[cfg(any(test, feature = "testing"))]
+    pub unsafe fn new_at(options: BumpOptions, base: HeapPointer) -> Self {
+        debug_assert_eq!(options.validate(), Ok(()), "Invalid heap options");
+
+        base.as_mut_ptr::<u8>()
+            .write_bytes(0x00, options.heap_size());
+        Self::from_region(base, options)
+    }
+
+    /// Carves the young generation and then each tenured generation out of the
+    /// region at `start`, returning the end of the young generation and the
+    /// tenured generations
+    ///
+    /// A separate intermediate generation isn't part of the region, so only the
+    /// generations after it are returned
+    fn lay_out(
+        start: HeapPointer,
+        options: &BumpOptions,
+        separate_intermediate: bool,
+    ) -> (HeapPointer, Vec<SweepHeap>) {
+        let region_end = start
+            .checked_offset(Self::region_size(options, separate_intermediate))
+            .expect("The heap's region wraps around the address space");
+        let young_end = start + options.young_heap_size;
+
+        // Each tenured generation is carved from the region directly after the one before it
+        let mut generation_start = young_end;
+        let generations = options
+            .tenured_sizes()
+            .iter()
+            .skip(separate_intermediate as usize)
+            .map(|&size| {
+                debug_assert!(
+                    generation_start
+                        .checked_offset(size)
+                        .is_some_and(|end| end <= region_end),
+                    "A tenured generation runs past the end of the heap's region",
+                );
+                let generation =
+                    SweepHeap::from_region(generation_start, size, options.old_pocket_sizes);
+                generation_start += size;
+
+                generation
+            })
+            .collect();
+        debug_assert_eq!(
+            generation_start, region_end,
+            "The generations don't exactly fill the heap's region",
+        );
+
+        (young_end, generations)
+    }
+
+    /// Lays the heap out over the region at `start` again, replacing every
+    /// generation in the region with an empty one and returning the end of the
+    /// young generation. A separate intermediate generation is kept
+    fn lay_out_again(&mut self, start: HeapPointer) -> HeapPointer {
+        let (young_end, generations) =
+            Self::lay_out(start, &self.options, self.separate_intermediate);
+        self.generations
+            .truncate(self.separate_intermediate as usize);
+        self.generations.extend(generations);
+
+        young_end
+    }
+
+    /// Touches every page of the heap so the kernel commits them all now, instead
+    /// of faulting each one in the first time it's allocated into
+    ///
+    /// This trades a one-time cost for allocations that don't stall on page
+    /// faults, see [`BumpOptions::prefault`] to do it whenever the heap's memory
+    /// is allocated. Does nothing if the heap is hibernating
+    pub fn prefault(&mut self) {
+        if self.dormant {
+            return;
+        }
+
+        let (start, end) = self.bounds();
+        unsafe { memory::prefault(start, end.offset_from(start)) };
+        if self.separate_intermediate {
+            let (start, end) = self.generations[0].bounds();
+            unsafe { memory::prefault(start, end.offset_from(start)) };
+        }
+    }
+
+    /// Gives the heap's memory back to its [`MemorySource`] if there are no live
+    /// objects left, returning whether it did
+    ///
+    /// The heap is woken up again by the next allocation, which allocates a new
+    /// region. This is for heaps that sit idle for long stretches, so they don't
+    /// hold on to memory that other heaps could be using. Heaps laid out over
+    /// memory they don't own or with a [separate](BumpHeap::with_intermediate)
+    /// intermediate generation can't hibernate
+    pub fn hibernate(&mut self) -> bool {
+        if self.dormant {
+            return true;
+        }
+        if !self.owns_region || self.separate_intermediate || self.live_count() != 0 {
+            return false;
+        }
+        self.finish_scavenge();
+
+        info!("Hibernating a heap of {}kb", self.heap_size / 1024);
+
+        // The mark's objects are all being reclaimed anyway
+        self.background_mark = None;
+        for root in self
+            .young_roots
+            .drain(..)
+            .chain(self.roots.iter_mut().flat_map(|roots| roots.drain(..)))
+        {
+            self.counters.bytes_reclaimed += root.size();
+            self.ids.release(root.id);
+
+            // Weak handles may still point at the metadata
+            let _ = rooted::leave_to_handles(root);
+        }
+
+        let layout = Layout::from_size_align(self.heap_size, self.options.alignment()).unwrap();
+        unsafe {
+            self.options
+                .memory_source
+                .dealloc(self.young_start.as_mut_ptr(), layout)
+        };
+
+        // Empty generations over the old addresses, until the heap wakes up
+        self.lay_out_again(self.young_start);
+        self.young_current = self.young_start;
+        self.dormant = true;
+        self.update_pressure();
+
+        true
+    }
+
+    /// Whether the heap has [hibernated](BumpHeap::hibernate) and hasn't been
+    /// woken up by an allocation yet
+    pub const fn is_hibernating(&self) -> bool {
+        self.dormant
+    }
+
+    /// Allocates a new region for a hibernating heap
+    fn wake(&mut self) -> Result<(), AllocError> {
+        if !self.dormant {
+            return Ok(());
+        }
+
+        let layout = Layout::from_size_align(self.heap_size, self.options.alignment()).unwrap();
+        let start = unsafe { self.options.memory_source.alloc_zeroed(layout) };
+        if start.is_null() {
+            return Err(AllocError::OutOfMemory {
+                size: self.heap_size,
+            });
+        }
+        let start = HeapPointer::new(start as usize);
+        if self.options.madvise_hugepage {
+            memory::advise_hugepages(start, self.heap_size);
+        }
+        if self.options.prefault {
+            unsafe { memory::prefault(start, self.heap_size) };
+        }
+
+        info!("Waking up a heap of {}kb", self.heap_size / 1024);
+
+        self.young_start = start;
+        self.young_current = start;
+        self.young_end = self.lay_out_again(start);
+        self.dormant = false;
+
+        Ok(())
+    }
+
+    /// The number of live objects in the heap
+    pub fn live_count(&self) -> usize {
+        self.young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .filter(|root| root.is_rooted())
+            .count()
+    }
+
+    /// Grows the young generation to `size` bytes by moving the whole heap into a
+    /// new, larger region
+    ///
+    /// Handles stay valid since objects' metadata never moves, but every object
+    /// is moved, so this has the same restrictions on held references as a
+    /// collection. Heaps holding [pinned](BumpHeap::alloc_pinned_permanent)
+    /// objects can't be grown
+    pub fn grow_young_generation(&mut self, size: usize) -> Result<(), HeapCreateError> {
+        let pinned = self.roots.iter().flatten().any(|root| root.pinned);
+        if !self.owns_region || pinned {
+            return Err(HeapCreateError::FixedRegion);
+        }
+        if size <= self.options.young_heap_size {
+            return Ok(());
+        }
+        self.finish_scavenge();
+
+        let options = BumpOptions {
+            young_heap_size: size,
+            ..self.options
+        };
+        options.validate()?;
+
+        // There's no region to move, the heap will wake up with the new size
+        let heap_size = Self::region_size(&options, self.separate_intermediate);
+        if self.dormant {
+            self.heap_size = heap_size;
+            self.options = options;
+
+            return Ok(());
+        }
+
+        let start = Self::alloc_region(&options, heap_size)?;
+
+        info!(
+            "Growing the young generation from {}kb to {}kb",
+            self.options.young_heap_size / 1024,
+            size / 1024,
         );
 
+        unsafe {
+            let used = self.young_current.offset_from(self.young_start);
+            ptr::copy_nonoverlapping(
+                self.young_start.as_ptr::<u8>(),
+                start.as_mut_ptr::<u8>(),
+                used,
+            );
+            for root in self.young_roots.iter_mut() {
+                let offset = root.data_ptr().offset_from(self.young_start);
+                root.as_mut().get_unchecked_mut().relocate(start + offset);
+            }
+
+            // Laid out the same way as `from_region` does, a separate intermediate
+            // generation stays where it is
+            let mut generation_start = start + size;
+            for (generation, roots) in self
+                .generations
+                .iter_mut()
+                .zip(self.roots.iter_mut())
+                .skip(self.separate_intermediate as usize)
+            {
+                generation.move_to(generation_start, roots);
+                generation_start += generation.size();
+            }
+
+            let old_layout =
+                Layout::from_size_align(self.heap_size, self.options.alignment()).unwrap();
+            self.options
+                .memory_source
+                .dealloc(self.young_start.as_mut_ptr(), old_layout);
+
+            self.young_start = start;
+            self.young_current = start + used;
+            self.young_end = start + size;
+        }
+
+        self.heap_size = heap_size;
+        self.options = options;
+        self.update_pressure();
+
+        Ok(())
+    }
+
+    /// Creates a heap over an already-allocated, zeroed region which the heap
+    /// will not free when dropped
+    ///
+    /// # Safety
+    ///
+    /// `start` must point to `options.heap_size()` bytes of zeroed, writable
+    /// memory that outlives the heap and doesn't move
+    pub(crate) unsafe fn from_region(start: HeapPointer, options: BumpOptions) -> Self {
+        Self::assemble(start, options, None)
+    }
+
+    /// Lays the heap out over the region at `start`, with `intermediate` as its
+    /// first tenured generation if it has a separate one
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::from_region`], the region leaves out the intermediate generation
+    unsafe fn assemble(
+        start: HeapPointer,
+        options: BumpOptions,
+        intermediate: Option<SweepHeap>,
+    ) -> Self {
+        let separate_intermediate = intermediate.is_some();
+        let (young_start, young_current) = (start, start);
+        let (young_end, carved) = Self::lay_out(start, &options, separate_intermediate);
+        let generations: Vec<_> = intermediate.into_iter().chain(carved).collect();
+        let roots = generations.iter().map(|_| Vec::with_capacity(50)).collect();
+
         Self {
             young_start,
             young_current,
             young_end,
-            heap_size: layout.size(),
-            intermediate: ManuallyDrop::new(SweepHeap::from_region(
-                young_end.offset(1),
-                options.old_heap_size,
-            )),
-            roots: Vec::with_capacity(50),
+            heap_size: Self::region_size(&options, separate_intermediate),
+            owns_region: false,
+            generations,
+            young_roots: Vec::with_capacity(50),
+            roots,
+            spare_roots: Vec::new(),
+            counters: GcCounters::new(),
+            #[cfg(feature = "debug-accounting")]
+            retired: (0, 0),
+            ids: IdTable::new(),
+            options,
+            last_promoted: 0,
+            pressure: Pressure::Low,
+            events: EventRing::new(options.event_history),
+            native_estimate: 0,
+            epoch: Epoch(0),
+            background_mark: None,
+            scavenge_cursor: None,
+            dormant: false,
+            separate_intermediate,
+            leaked: false,
         }
     }
 
-    pub unsafe fn alloc<T: Sized + Any + 'static>(&mut self, value: T) -> Rooted<T> {
-        let allocation_size = mem::size_of::<HeapValue<T>>();
+    /// The number of bytes every `T` takes up in the heap beyond the `T` itself
+    pub const fn object_overhead<T: Sized + Any + 'static>() -> usize {
+        mem::size_of::<HeapValue<T>>() - mem::size_of::<T>()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub fn alloc<T: Sized + Any + 'static>(&mut self, value: T) -> Handle<T> {
+        match self.try_alloc(value) {
+            Ok(handle) => handle,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Allocates an object, returning a [`Handle`] that can only be dereferenced
+    /// while the heap is borrowed
+    ///
+    /// Allocating used to be unsafe because collections move objects, and the
+    /// references a [`Rooted`] hands out aren't tied to anything, so nothing
+    /// stopped one from being held across a collection that moved its object out
+    /// from under it. A handle's references borrow the heap, and everything that
+    /// can collect takes the heap mutably, so the borrow checker rules that out.
+    /// Handles outliving their heap and under-aligned objects were the other
+    /// hazards, and those are checked at runtime by every handle and by the heap.
+    /// Use [`BumpHeap::try_alloc_unchecked`] for a `Rooted` instead
+    pub fn try_alloc<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Handle<T>, AllocError> {
+        unsafe { self.try_alloc_unchecked(value) }.map(Handle::new)
+    }
+
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_unchecked<T: Sized + Any + 'static>(&mut self, value: T) -> Rooted<T> {
+        match self.try_alloc_unchecked(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Allocates an object the same as [`BumpHeap::try_alloc_unchecked`], but returns a
+    /// [`Rooted`] whose value can be borrowed without borrowing the heap
+    ///
+    /// # Safety
+    ///
+    /// Collections move objects, so a reference obtained by dereferencing the returned
+    /// `Rooted` must not be held across anything that can collect, which is anything
+    /// that takes the heap mutably (including allocation).
+    ///
+    /// Writing to the object through the `Rooted`, with methods like
+    /// [`Rooted::write`] or [`Rooted::as_pin_mut`], only takes the handle and not
+    /// the heap, so a reference to the value borrowed any other way (like through
+    /// a [`Weak`](crate::Weak)) must not be held across one either
+    ///
+    /// Handles may outlive the heap, but dereferencing one afterwards panics
+    pub unsafe fn try_alloc_unchecked<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        self.try_alloc_with(value, None)
+    }
+
+    /// Allocates an object that stays rooted when its handle is dropped, only being
+    /// unrooted by [`Rooted::unroot`]
+    ///
+    /// If the handle is dropped without being unrooted the object is leaked until
+    /// the heap is dropped
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_unmanaged<T: Sized + Any + 'static>(&mut self, value: T) -> Rooted<T> {
+        match self.try_alloc_unmanaged(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::alloc_unmanaged`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_unmanaged<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        let mut rooted = self.try_alloc_unchecked(value)?;
+        rooted.inner_mut().managed = false;
+
+        Ok(rooted)
+    }
+
+    /// Allocates an object tagged with the site it was allocated at, which
+    /// [`BumpHeap::leak_report`] groups it under
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_tagged<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+        tag: &'static str,
+    ) -> Rooted<T> {
+        match self.try_alloc_tagged(value, tag) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::alloc_tagged`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_tagged<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+        tag: &'static str,
+    ) -> Result<Rooted<T>, AllocError> {
+        let mut rooted = self.try_alloc_unchecked(value)?;
+        rooted.inner_mut().tag = Some(tag);
+
+        Ok(rooted)
+    }
+
+    /// Allocates an object whose children can be found through its [`Trace`] impl
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_traced`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_traced<T: Trace + Sized + Any + 'static>(&mut self, value: T) -> Rooted<T> {
+        match self.try_alloc_traced(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Allocates an object whose children can be found through its [`Trace`] impl
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]. On top of that, the value's [`Trace`]
+    /// impl must only report handles the value owns, since
+    /// [`BumpHeap::collect_mark_sweep_only`] and
+    /// [background marks](BumpHeap::request_background_mark) reclaim the children
+    /// of dead objects along with them. They drop them too, in no particular order,
+    /// so the value's destructor must not read the objects it holds handles to
+    pub unsafe fn try_alloc_traced<T: Trace + Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        let rooted = self.try_alloc_with(value, Some(rooted::trace_fn::<T>()))?;
+
+        // Lets the value's cells know which object they're in before they're written to
+        rooted.inner().trace_children(&mut Tracer::new());
+
+        Ok(rooted)
+    }
+
+    /// Allocates an object whose value is hashed through its [`Hash`] impl by
+    /// [`BumpHeap::live_fingerprint`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_hashed<T: Hash + Sized + Any + 'static>(&mut self, value: T) -> Rooted<T> {
+        match self.try_alloc_hashed(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::alloc_hashed`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_hashed<T: Hash + Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        let mut rooted = self.try_alloc_unchecked(value)?;
+        rooted.inner_mut().hash = Some(rooted::hash_fn::<T>());
+
+        Ok(rooted)
+    }
+
+    /// Allocates an object that owns memory outside of the heap, which is measured
+    /// through its [`NativeSize`] impl
+    ///
+    /// If the heap has a [native limit](BumpOptions::native_limit) and allocating
+    /// the object pushes the native memory past it, the heap collects
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_native<T: NativeSize + Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Rooted<T> {
+        match self.try_alloc_native(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::alloc_native`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_native<T: NativeSize + Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        let mut rooted = self.try_alloc_unchecked(value)?;
+        rooted.inner_mut().native = Some(rooted::native_size_fn::<T>());
+
+        if let Some(limit) = self.options.native_limit {
+            self.native_estimate += rooted.inner().native_bytes();
+
+            if self.native_estimate > limit && self.collects_on_alloc() {
+                trace!("Native memory passed its limit, starting a collection");
+                self.scavenge();
+                self.major();
+                self.native_estimate = self.total_native_bytes();
+            }
+        }
+
+        Ok(rooted)
+    }
+
+    unsafe fn try_alloc_with<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+        trace: Option<TraceFn>,
+    ) -> Result<Rooted<T>, AllocError> {
+        let ptr = self.reserve_young::<T>()?;
+        ptr.write(HeapValue::new(value), (self.young_start, self.young_end));
+
+        Ok(self.root_young::<T>(ptr, trace))
+    }
+
+    /// Bumps out room for a `T` in the young generation, scavenging if it's full
+    fn reserve_young<T: Sized + Any + 'static>(&mut self) -> Result<HeapPointer, AllocError> {
+        let align = mem::align_of::<HeapValue<T>>();
+
+        // Promoted objects are only aligned to `MAX_ALIGN`, unless they ask for more
+        // with `alloc_aligned`
+        if align > memory::MAX_ALIGN {
+            return Err(AllocError::UnsupportedAlignment(align));
+        }
+
+        self.reserve_young_aligned(mem::size_of::<HeapValue<T>>(), align)
+    }
+
+    /// Bumps out `allocation_size` bytes aligned to `align` in the young generation,
+    /// scavenging if it's full
+    fn reserve_young_aligned(
+        &mut self,
+        allocation_size: usize,
+        align: usize,
+    ) -> Result<HeapPointer, AllocError> {
         trace!("Allocating object of size {}", allocation_size);
 
+        self.finish_scavenge();
+        self.wake()?;
+
         // TODO: https://fitzgeraldnick.com/2019/11/01/always-bump-downwards.html
-        if self.young_current + allocation_size > self.young_end {
+        let mut ptr = self.aligned_young_current(align);
+        if ptr + allocation_size > self.young_end {
+            if !self.collects_on_alloc() {
+                return Err(AllocError::OutOfMemory {
+                    size: allocation_size,
+                });
+            }
+
             trace!("Young generation OOM, starting scavenge");
             self.scavenge();
 
-            if self.young_current + allocation_size > self.young_end {
-                panic!("Allocation too large for young generation");
+            ptr = self.aligned_young_current(align);
+            if ptr + allocation_size > self.young_end {
+                return Err(AllocError::TooLarge {
+                    size: allocation_size,
+                    capacity: self.young_end.offset_from(self.young_start),
+                });
             }
         }
 
-        let ptr = self.young_current;
-        self.young_current += allocation_size;
+        self.young_current = ptr + allocation_size;
+
+        self.counters.total_allocations += 1;
+        self.counters.bytes_allocated += allocation_size;
 
         debug_assert!(!ptr.is_null());
 
-        ptr.as_mut_ptr::<HeapValue<T>>()
-            .write(HeapValue::new(value));
+        Ok(ptr)
+    }
 
-        let inner: Pin<Box<RootedInner>> = Box::pin(RootedInner::new::<T>(
-            ptr.as_mut_ptr(),
-            ContainingHeap::Eden,
-        ));
-        let rooted_ptr = inner.as_ref().get_ref() as *const _ as *mut RootedInner;
+    /// Roots the `T` that was just written to `ptr` in the young generation
+    fn root_young<T: Sized + Any + 'static>(
+        &mut self,
+        ptr: HeapPointer,
+        trace: Option<TraceFn>,
+    ) -> Rooted<T> {
+        let id = self.ids.reserve();
+        let mut inner = RootedInner::new::<T>(ptr.as_mut_ptr(), ContainingHeap::Eden, id);
+        inner.trace = trace;
 
-        self.roots.push(inner);
+        let inner = self.bind_root(inner);
+        let rooted_ptr = inner.as_ref().get_ref() as *const _ as *mut RootedInner;
+        self.young_roots.push(inner);
 
         trace!("Allocated object successfully at {:p}", rooted_ptr);
 
         Rooted::new(rooted_ptr)
     }
 
-    pub fn scavenge(&mut self) {
-        info!("Starting Scavenge cycle");
+    /// Allocates an object whose address is always a multiple of `align`, as well
+    /// as of its type's alignment, such as a counter that needs a cache line to itself
+    ///
+    /// The object's size is rounded up to a multiple of the alignment, and every
+    /// collection that moves it keeps it aligned, so over-aligned objects can
+    /// leave gaps in the generations they're promoted into
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_aligned<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+        align: usize,
+    ) -> Rooted<T> {
+        match self.try_alloc_aligned(value, align) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
 
-        let mut roots = Vec::with_capacity(self.roots.len());
-        mem::swap(&mut self.roots, &mut roots);
+    /// See [`BumpHeap::alloc_aligned`]
+    ///
+    /// Alignments that aren't a power of two or are more than the heap's
+    /// [backing alignment](BumpOptions::backing_align) are rejected, since the
+    /// heap's region can be moved to any address with that alignment
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_aligned<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+        align: usize,
+    ) -> Result<Rooted<T>, AllocError> {
+        if !align.is_power_of_two() || align > self.options.alignment() {
+            return Err(AllocError::UnsupportedAlignment(align));
+        }
 
-        for mut root in roots {
-            assert!(!root.is_null());
-            if root.is_rooted() {
-                let size = root.size();
-                let ptr;
+        let align = align.max(mem::align_of::<HeapValue<T>>());
+        let size = mem::size_of::<HeapValue<T>>().next_multiple_of(align);
+        let ptr = self.reserve_young_aligned(size, align)?;
+        ptr.write(HeapValue::new(value), (self.young_start, self.young_end));
 
-                if let Some((_ptr, pocket_size)) = self.intermediate.alloc(size) {
-                    ptr = _ptr;
+        let mut rooted = self.root_young::<T>(ptr, None);
+        let inner = rooted.inner_mut();
+        inner.size = size;
+        inner.align = align;
 
-                    unsafe {
-                        ptr::copy(root.value_ptr() as *const u8, ptr.as_mut_ptr::<u8>(), size);
+        Ok(rooted)
+    }
 
-                        root.as_mut().get_unchecked_mut().heap =
-                            ContainingHeap::Intermediate(pocket_size);
-                    }
-                } else {
-                    self.major();
+    /// Allocates an array of zeroes directly in the heap, without building it on
+    /// the stack first like [`BumpHeap::alloc`] would
+    ///
+    /// # Safety
+    ///
+    /// All zeroes must be a valid `T`, and see [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the array can't be allocated
+    pub unsafe fn alloc_zeroed_array<T: Copy + Any, const N: usize>(&mut self) -> Rooted<[T; N]> {
+        match self.try_alloc_zeroed_array() {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
 
-                    if let Some((_ptr, pocket_size)) = self.intermediate.alloc(size) {
-                        ptr = _ptr;
+    /// See [`BumpHeap::alloc_zeroed_array`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::alloc_zeroed_array`]
+    pub unsafe fn try_alloc_zeroed_array<T: Copy + Any, const N: usize>(
+        &mut self,
+    ) -> Result<Rooted<[T; N]>, AllocError> {
+        let ptr = self.reserve_young::<[T; N]>()?;
 
-                        unsafe {
-                            root.as_mut().get_unchecked_mut().heap =
-                                ContainingHeap::Intermediate(pocket_size);
+        let size = mem::size_of::<HeapValue<[T; N]>>();
+        ptr.debug_check_bounds(size, (self.young_start, self.young_end));
+        memory::zero(ptr, size);
 
-                            ptr::copy(root.value_ptr() as *const u8, ptr.as_mut_ptr::<u8>(), size);
-                        }
-                    } else {
-                        panic!("Old Generation OOM");
-                    }
-                }
+        Ok(self.root_young::<[T; N]>(ptr, None))
+    }
 
-                let raw_root: raw::TraitObject = unsafe { mem::transmute(root.value_ptr()) };
-                unsafe {
-                    *root.as_mut().get_unchecked_mut() = RootedInner {
-                        value: mem::transmute(raw::TraitObject {
-                            data: ptr.as_mut_ptr(),
-                            vtable: raw_root.vtable,
-                        }),
-                        ..root.as_ref().get_ref().clone()
-                    };
-                }
+    /// Starts a new epoch, which every object allocated from here on is stamped
+    /// with until the next one is started
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap has run out of epochs
+    pub fn new_epoch(&mut self) -> Epoch {
+        let next = self.epoch.0.checked_add(1).expect("Ran out of epochs");
+        self.epoch = Epoch(next);
 
-                self.roots.push(root);
-            }
+        self.epoch
+    }
+
+    /// The epoch new objects are stamped with, which starts out as the heap's
+    /// first epoch
+    pub const fn current_epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    /// Reclaims every object allocated in `epoch` in one pass, whether or not
+    /// they're rooted, returning the number of objects freed
+    ///
+    /// This is meant for cohorts of objects that all die together, like the ones
+    /// allocated while handling a request, so that they don't have to be dropped
+    /// and collected one by one. The objects that are still rooted are dropped and
+    /// unrooted ones aren't, following the rule on [`Rooted`]
+    ///
+    /// Every destructor is run before any of the objects are freed, so objects
+    /// that point at each other (even in cycles) can still read each other from
+    /// their destructors, although the peer's own destructor may already have run.
+    /// Objects are freed even if a destructor stashes a handle to one of them
+    /// somewhere, that handle comes back cleared. [Leaked](Rooted::leak) objects
+    /// are never freed
+    ///
+    /// # Safety
+    ///
+    /// No object allocated in `epoch` may be used afterwards, dereferencing a
+    /// handle to one panics but anything else holding on to one (like a raw
+    /// pointer or a `HeapPointer`) is left dangling. Handles can still be dropped
+    pub unsafe fn free_region(&mut self, epoch: Epoch) -> usize {
+        self.finish_scavenge();
+
+        // Eden's memory is reclaimed wholesale by the next scavenge, so young
+        // objects don't have a generation to be freed from
+        let (young, young_roots): (Vec<_>, Vec<_>) = mem::take(&mut self.young_roots)
+            .into_iter()
+            .partition(|root| root.epoch == epoch && !root.leaked);
+        self.young_roots = young_roots;
+        let mut freed: Vec<(Option<usize>, Pin<Box<RootedInner>>)> =
+            young.into_iter().map(|root| (None, root)).collect();
+
+        for index in 0..self.generations.len() {
+            let (tenured, roots): (Vec<_>, Vec<_>) = mem::take(&mut self.roots[index])
+                .into_iter()
+                .partition(|root| root.epoch == epoch && !root.leaked);
+            self.roots[index] = roots;
+
+            freed.extend(tenured.into_iter().map(|root| (Some(index), root)));
         }
 
-        trace!("Finished processing roots");
+        Self::drop_rooted(freed.iter_mut().map(|(_, root)| root));
 
-        // Zero out the young heap
-        unsafe {
-            self.young_start
-                .as_mut_ptr::<u8>()
-                .write_bytes(0x00, *self.young_end - *self.young_start);
+        let count = freed.len();
+        for (index, root) in freed {
+            if let (Some(index), ContainingHeap::Generation(_, pocket_size)) = (index, root.heap) {
+                self.generations[index].free(root.data_ptr(), pocket_size);
+            }
+            self.release_object(root);
         }
-        self.young_current = self.young_start;
 
-        info!("Finished Scavenge cycle");
+        self.update_pressure();
+
+        count
     }
 
-    pub fn major(&mut self) {
-        info!("Starting a Major cleanup cycle");
+    /// Frees every object and empties every generation, keeping the heap's region
+    /// so that it can be reused without allocating a new one
+    ///
+    /// The destructors of every rooted object are run, all of them before any
+    /// object is freed as with [`BumpHeap::free_region`]. Unrooted objects are
+    /// freed without being dropped, following the rule on [`Rooted`]. Every handle
+    /// comes back cleared afterwards, [`Rooted::try_deref`] returns `None` for them
+    ///
+    /// # Safety
+    ///
+    /// No object may be used afterwards, dereferencing a handle to one panics but
+    /// anything else holding on to one (like a raw pointer or a `HeapPointer`) is
+    /// left dangling. Handles can still be dropped
+    ///
+    /// # Panics
+    ///
+    /// Panics if an object has been [leaked](Rooted::leak)
+    pub unsafe fn clear(&mut self) {
+        if self.dormant {
+            return;
+        }
+        assert!(!self.leaked, "Cleared a heap with leaked objects");
 
-        self.intermediate.collect(&mut self.roots);
+        info!("Clearing a heap of {}kb", self.heap_size / 1024);
 
-        info!("Finished a Major cleanup cycle");
-    }
-}
+        // Everything the paused scavenge and the background mark were working on is going away
+        self.scavenge_cursor = None;
+        self.background_mark = None;
 
-impl Drop for BumpHeap {
-    fn drop(&mut self) {
-        info!("Dropping Bump Heap");
+        let mut freed: Vec<_> = mem::take(&mut self.young_roots);
+        for roots in self.roots.iter_mut() {
+            freed.append(roots);
+        }
 
-        let layout = Layout::from_size_align(self.heap_size, memory::page_size()).unwrap();
+        Self::drop_rooted(freed.iter_mut());
+        for root in freed {
+            self.release_object(root);
+        }
 
-        unsafe { alloc::alloc::dealloc(self.young_start.as_mut_ptr(), layout) };
+        // Only the used part of the young generation is dirty, but the tenured
+        // generations have to be laid out again over zeroed memory
+        memory::zero(
+            self.young_start,
+            self.young_current.offset_from(self.young_start),
+        );
+        memory::zero(
+            self.young_end,
+            (self.young_start + self.heap_size).offset_from(self.young_end),
+        );
+        if self.separate_intermediate {
+            self.generations[0].clear();
+        }
+        self.lay_out_again(self.young_start);
+        self.young_current = self.young_start;
+        self.native_estimate = 0;
+        self.update_pressure();
     }
-}
 
-impl Default for BumpHeap {
-    fn default() -> Self {
-        Self::new(BumpOptions::default())
+    /// Drops the values of the rooted objects among the ones being freed
+    ///
+    /// Destructors can drop the handles of other objects being freed, so which
+    /// objects are rooted is settled before any destructor runs
+    ///
+    /// # Safety
+    ///
+    /// The objects must be freed right afterwards
+    unsafe fn drop_rooted<'a>(roots: impl Iterator<Item = &'a mut Pin<Box<RootedInner>>>) {
+        let rooted: Vec<_> = roots.filter(|root| root.is_rooted()).collect();
+        for root in rooted {
+            let inner = root.as_mut().get_unchecked_mut();
+            (inner.drop_value)(inner.data_ptr().as_mut_ptr());
+        }
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct BumpOptions {
-    young_heap_size: usize,
-    old_heap_size: usize,
-}
+    /// Reclaims every object held by a [`Soft`](crate::Soft) handle, leaving the
+    /// emptied metadata for the handles to free
+    ///
+    /// Young objects are left for the scavenge to handle when `scavenging`, since
+    /// the survivors it's kept so far are counted from the front of the young roots
+    fn clear_soft_handles(&mut self, scavenging: bool) {
+        let is_soft = |root: &Pin<Box<RootedInner>>| root.soft && root.is_rooted();
 
-impl Default for BumpOptions {
-    fn default() -> Self {
-        Self {
-            young_heap_size: 1024 * 4,
-            old_heap_size: 1024 * 2,
+        let mut cleared = Vec::new();
+        if !scavenging {
+            let (young, young_roots): (Vec<_>, Vec<_>) = mem::take(&mut self.young_roots)
+                .into_iter()
+                .partition(is_soft);
+            self.young_roots = young_roots;
+            cleared = young;
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for index in 0..self.generations.len() {
+            let (soft, roots): (Vec<_>, Vec<_>) = mem::take(&mut self.roots[index])
+                .into_iter()
+                .partition(is_soft);
+            self.roots[index] = roots;
+            cleared.extend(soft);
+        }
 
-    #[test]
-    fn new() {
-        let _bump = BumpHeap::default();
-    }
+        // The objects are still rooted by their soft handles, so they're dropped
+        // like any other rooted object that's reclaimed
+        unsafe { Self::drop_rooted(cleared.iter_mut()) };
 
-    #[test]
-    fn allocate_no_drop() {
-        let mut bump = BumpHeap::default();
+        // Eden's memory is reclaimed wholesale by the next scavenge
+        for root in cleared.iter() {
+            if let ContainingHeap::Generation(generation, pocket_size) = root.heap {
+                self.generations[usize::from(generation) - 1].free(root.data_ptr(), pocket_size);
+            }
+        }
+
+        trace!("Clearing {} soft handles", cleared.len());
+        for root in cleared {
+            self.counters.bytes_reclaimed += root.size();
+            self.ids.release(root.id);
 
-        let _one: ManuallyDrop<Rooted<usize>> = unsafe { ManuallyDrop::new(bump.alloc(1)) };
+            // Soft handles are still holding on to their metadata
+            let _ = rooted::leave_to_handles(root);
+        }
     }
 
-    #[test]
-    fn allocate_no_drop_deref() {
-        let mut bump = BumpHeap::default();
+    /// Releases the metadata of an object whose value has been dropped
+    fn release_object(&mut self, root: Pin<Box<RootedInner>>) {
+        self.counters.bytes_reclaimed += root.size();
+        self.ids.release(root.id);
 
-        let one: ManuallyDrop<Rooted<usize>> = unsafe { ManuallyDrop::new(bump.alloc(1)) };
-        assert_eq!(**one, 1usize);
+        // A handle may still point at the metadata, so it's left for the handle
+        // to free, the same as when the heap is dropped
+        if let Some(root) = rooted::leave_to_handles(root) {
+            if self.spare_roots.len() < MAX_SPARE_ROOTS {
+                self.spare_roots.push(root);
+            }
+        }
     }
 
-    #[test]
-    fn allocate() {
-        let mut bump = BumpHeap::default();
+    /// Boxes an object's metadata, reusing a dead object's if there is one, and
+    /// binds its id
+    fn bind_root(&mut self, mut inner: RootedInner) -> Pin<Box<RootedInner>> {
+        let id = inner.id;
+        inner.epoch = self.epoch;
+        #[cfg(feature = "capture-backtrace")]
+        {
+            inner.backtrace = Some(alloc::sync::Arc::new(
+                std::backtrace::Backtrace::force_capture(),
+            ));
+        }
+
+        let inner: Pin<Box<RootedInner>> = match self.spare_roots.pop() {
+            Some(mut spare) => {
+                spare.set(inner);
+                spare
+            }
+            None => Box::pin(inner),
+        };
+        self.ids
+            .bind(id, inner.as_ref().get_ref() as *const RootedInner);
 
-        let one_hundred: Rooted<usize> = unsafe { bump.alloc(100) };
-        assert_eq!(*one_hundred, 100usize);
+        inner
+    }
+
+    /// Tenures and pins the object behind `inner` and marks it as leaked, for
+    /// [`Rooted::leak`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object isn't in this heap, the heap doesn't own its region,
+    /// or the object doesn't fit in the first tenured generation
+    pub(crate) fn leak_object(&mut self, inner: &RootedInner) {
+        assert!(
+            self.ids.resolve(inner.id) == Some(inner as *const RootedInner),
+            "Leaked an object through a heap it isn't in",
+        );
+        assert!(
+            self.owns_region,
+            "Leaked an object from a heap that doesn't own its memory",
+        );
+        self.finish_scavenge();
+
+        let id = inner.id;
+        if inner.heap == ContainingHeap::Eden {
+            let index = self
+                .young_roots
+                .iter()
+                .position(|root| root.id == id)
+                .unwrap();
+            let mut root = self.young_roots.remove(index);
+
+            if !self.promote(&mut root, 0) {
+                self.counters.oom_events += 1;
+                self.collect_tenured(false);
+
+                assert!(
+                    self.promote(&mut root, 0),
+                    "Leaked an object that doesn't fit in the first tenured generation",
+                );
+            }
+            self.roots[0].push(root);
+        }
+
+        // The metadata is boxed and never moves, so this is the same `inner`
+        let inner = self.ids.resolve(id).unwrap() as *mut RootedInner;
+        unsafe {
+            (*inner).pinned = true;
+            (*inner).leaked = true;
+        }
+        self.leaked = true;
+    }
+
+    /// Allocates an object that's never moved for as long as it's alive, for
+    /// objects that point into themselves or whose address is used as their identity
+    ///
+    /// Pinned objects are allocated directly in the first tenured generation and
+    /// stay there, compaction works around them instead of moving them. They're
+    /// still reclaimed once they're unrooted. Their values can only be mutated
+    /// through [`Rooted::as_pin_mut`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`], although the object itself never moves
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc_pinned_permanent<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Rooted<T> {
+        match self.try_alloc_pinned_permanent(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::alloc_pinned_permanent`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc_pinned_permanent<T: Sized + Any + 'static>(
+        &mut self,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        let allocation_size = mem::size_of::<HeapValue<T>>();
+        let align = mem::align_of::<HeapValue<T>>();
+        if align > memory::MAX_ALIGN {
+            return Err(AllocError::UnsupportedAlignment(align));
+        }
+
+        self.wake()?;
+
+        let (ptr, pocket_size) = match self.generations[0].alloc_typed::<T>() {
+            Some(allocation) => allocation,
+            None => {
+                self.counters.oom_events += 1;
+                if self.collects_on_alloc() {
+                    self.major();
+                }
+
+                self.generations[0]
+                    .alloc_typed::<T>()
+                    .ok_or(AllocError::GenerationFull {
+                        size: allocation_size,
+                        generation: 1,
+                    })?
+            }
+        };
+
+        self.counters.total_allocations += 1;
+        self.counters.bytes_allocated += allocation_size;
+
+        ptr.write(HeapValue::new(value), self.generations[0].bounds());
+
+        let id = self.ids.reserve();
+        let mut inner = RootedInner::new::<T>(
+            ptr.as_mut_ptr(),
+            ContainingHeap::Generation(1, pocket_size),
+            id,
+        );
+        inner.pinned = true;
+
+        let inner = self.bind_root(inner);
+        let rooted_ptr = inner.as_ref().get_ref() as *const _ as *mut RootedInner;
+        self.roots[0].push(inner);
+
+        Ok(Rooted::new(rooted_ptr))
+    }
+
+    /// Allocates a buffer holding a copy of `bytes`
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn alloc_buffer(&mut self, bytes: &[u8]) -> Result<Rooted<HeapBuffer>, AllocError> {
+        let mut buffer = self.try_alloc_unchecked(HeapBuffer::empty())?;
+        self.extend_buffer(&mut buffer, bytes)?;
+
+        Ok(buffer)
+    }
+
+    /// Allocates a buffer holding a copy of the `len` bytes at `bytes`, which can
+    /// include uninitialized padding
+    pub(crate) unsafe fn alloc_raw_buffer(
+        &mut self,
+        bytes: *const u8,
+        len: usize,
+    ) -> Result<Rooted<HeapBuffer>, AllocError> {
+        let mut buffer = self.try_alloc_unchecked(HeapBuffer::empty())?;
+        self.append_raw(&mut buffer, bytes, len)?;
+
+        buffer
+            .inner_mut()
+            .downcast_mut::<HeapBuffer>()
+            .unwrap()
+            .set_len(len);
+
+        Ok(buffer)
+    }
+
+    /// Appends `bytes` to a buffer, growing it in place when there's room directly
+    /// after it and moving it otherwise
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must have been allocated by this heap, see [`BumpHeap::try_alloc_unchecked`]
+    /// for the rest
+    pub unsafe fn extend_buffer(
+        &mut self,
+        buffer: &mut Rooted<HeapBuffer>,
+        bytes: &[u8],
+    ) -> Result<(), AllocError> {
+        self.append_bytes(buffer, bytes)?;
+
+        let header = buffer
+            .inner_mut()
+            .downcast_mut::<HeapBuffer>()
+            .expect("Extended something that isn't a buffer");
+        header.set_len(header.len() + bytes.len());
+
+        Ok(())
+    }
+
+    /// Collects `items` into a single heap allocated slice
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice can't be allocated
+    pub unsafe fn collect_slice<T, I>(&mut self, items: I) -> Rooted<HeapSlice<T>>
+    where
+        T: Copy + Any,
+        I: IntoIterator<Item = T>,
+    {
+        match self.try_collect_slice(items) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// See [`BumpHeap::collect_slice`]
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_collect_slice<T, I>(
+        &mut self,
+        items: I,
+    ) -> Result<Rooted<HeapSlice<T>>, AllocError>
+    where
+        T: Copy + Any,
+        I: IntoIterator<Item = T>,
+    {
+        // Iterators can't be trusted to report their length, so the items are
+        // gathered up first and the slice is allocated once
+        let items: Vec<T> = items.into_iter().collect();
+
+        // Copied without being read as bytes, since `T` can have padding
+        let mut slice = self.try_alloc_unchecked(HeapSlice::<T>::empty())?;
+        self.append_raw(
+            &mut slice,
+            items.as_ptr() as *const u8,
+            items.len() * mem::size_of::<T>(),
+        )?;
+
+        slice
+            .inner_mut()
+            .downcast_mut::<HeapSlice<T>>()
+            .unwrap()
+            .set_len(items.len());
+
+        Ok(slice)
+    }
+
+    /// Appends `bytes` to the end of an object, growing it in place when there's
+    /// room directly after it and moving it otherwise
+    unsafe fn append_bytes<T: Any>(
+        &mut self,
+        object: &mut Rooted<T>,
+        bytes: &[u8],
+    ) -> Result<(), AllocError> {
+        self.append_raw(object, bytes.as_ptr(), bytes.len())
+    }
+
+    /// Appends the `len` bytes at `bytes` to the end of an object, see
+    /// [`BumpHeap::append_bytes`]
+    unsafe fn append_raw<T: Any>(
+        &mut self,
+        object: &mut Rooted<T>,
+        bytes: *const u8,
+        len: usize,
+    ) -> Result<(), AllocError> {
+        let to = self.grow_object(object, len)?;
+        ptr::copy_nonoverlapping(bytes, to.as_mut_ptr::<u8>(), len);
+
+        Ok(())
+    }
+
+    /// Grows an object by `len` uninitialized bytes, in place when there's room
+    /// directly after it and moving it otherwise, returning where the new bytes start
+    pub(crate) unsafe fn grow_object<T: Any>(
+        &mut self,
+        object: &mut Rooted<T>,
+        len: usize,
+    ) -> Result<HeapPointer, AllocError> {
+        self.finish_scavenge();
+
+        let old_size = object.inner().size();
+        let new_size = old_size + len;
+        let mut collected = false;
+
+        let ptr = loop {
+            let from = object.inner().data_ptr();
+
+            match object.inner().containing_heap() {
+                ContainingHeap::Eden => {
+                    if from + old_size == self.young_current && from + new_size <= self.young_end {
+                        self.young_current = from + new_size;
+                        break from;
+                    }
+
+                    let to = self.aligned_young_current(mem::align_of::<HeapValue<T>>());
+                    if to + new_size <= self.young_end {
+                        ptr::copy_nonoverlapping(
+                            from.as_ptr::<u8>(),
+                            to.as_mut_ptr::<u8>(),
+                            old_size,
+                        );
+                        self.young_current = to + new_size;
+                        break to;
+                    }
+
+                    // The promotion policy can keep the object young, in which case the
+                    // scavenge only compacts the young generation, so it's only tried once
+                    if collected || !self.collects_on_alloc() {
+                        return Err(AllocError::OutOfMemory { size: new_size });
+                    }
+
+                    // Promotes the object unless the policy keeps it, so it's grown in its
+                    // new generation instead
+                    self.scavenge();
+                    collected = true;
+                }
+
+                ContainingHeap::Generation(generation, _) => {
+                    let index = usize::from(generation) - 1;
+                    if let Some(ptr) = self.generations[index].realloc(from, old_size, new_size) {
+                        break ptr;
+                    }
+
+                    if collected || !self.collects_on_alloc() {
+                        return Err(AllocError::GenerationFull {
+                            size: new_size,
+                            generation,
+                        });
+                    }
+
+                    self.counters.oom_events += 1;
+                    self.major();
+                    collected = true;
+                }
+            }
+        };
+
+        let inner = object.inner_mut();
+        inner.relocate(ptr);
+        inner.size = new_size;
+        if let ContainingHeap::Generation(generation, _) = inner.heap {
+            let block_size = self.generations[usize::from(generation) - 1].block_size(new_size);
+            inner.heap = ContainingHeap::Generation(generation, block_size);
+        }
+
+        self.counters.bytes_allocated += len;
+
+        Ok(ptr + old_size)
+    }
+
+    fn aligned_young_current(&self, align: usize) -> HeapPointer {
+        self.young_current + memory::padding_for(self.young_current.as_usize(), align)
+    }
+
+    /// Promotes live young objects into the first tenured generation and
+    /// empties the young generation
+    ///
+    /// The [promotion policy](BumpOptions::promotion_policy) can keep survivors
+    /// in the young generation instead, which are compacted to its start. If the
+    /// tenured generation fills up a major collection is run to make room, which
+    /// is reported in the outcome. Finishes the scavenge started by
+    /// [`BumpHeap::scavenge_step`] if there is one
+    ///
+    /// # Panics
+    ///
+    /// Panics if a live object can't be promoted even after a major collection
+    pub fn scavenge(&mut self) -> ScavengeOutcome {
+        match self.scavenge_step(usize::MAX) {
+            Some(outcome) => outcome,
+            None => unreachable!("An unbounded scavenge step didn't finish"),
+        }
+    }
+
+    /// Does part of a scavenge, promoting at most `budget` live young objects
+    /// before returning so that long scavenges can be spread out. A budget of
+    /// zero is treated as one, so that every step makes progress
+    ///
+    /// Returns the outcome once every young object has been processed, and `None`
+    /// while the scavenge is still in progress. Nothing can be allocated in the
+    /// young generation until the scavenge is finished, so any allocation made in
+    /// the meantime finishes it first
+    ///
+    /// # Panics
+    ///
+    /// Panics if a live object can't be promoted even after a major collection
+    pub fn scavenge_step(&mut self, budget: usize) -> Option<ScavengeOutcome> {
+        if !self.options.collect {
+            return Some(ScavengeOutcome::default());
+        }
+        let budget = budget.max(1);
+
+        let mut cursor = match self.scavenge_cursor.take() {
+            Some(cursor) => cursor,
+            None => {
+                info!("Starting Scavenge cycle");
+                self.counters.scavenge_count += 1;
+
+                ScavengeCursor {
+                    outcome: ScavengeOutcome::default(),
+                    promoted_before: self.counters.bytes_promoted,
+                    reclaimed_before: self.counters.bytes_reclaimed,
+                    kept: 0,
+                }
+            }
+        };
+
+        // The batch ends right before the live object that would go over the budget,
+        // skipping the survivors already kept in the young generation
+        let mut survivors = 0;
+        let batch_len = self.young_roots[cursor.kept..]
+            .iter()
+            .position(|root| {
+                survivors += root.is_rooted() as usize;
+                survivors > budget
+            })
+            .unwrap_or(self.young_roots.len() - cursor.kept);
+
+        // Only young objects can be in the young generation, so tenured roots are never touched here
+        let batch: Vec<_> = self
+            .young_roots
+            .drain(cursor.kept..cursor.kept + batch_len)
+            .collect();
+        let mut kept = Vec::new();
+        for mut root in batch {
+            debug_assert_eq!(root.containing_heap(), ContainingHeap::Eden);
+            assert!(!root.is_null());
+            if root.is_rooted() {
+                let survivals = root.age.saturating_add(1);
+                let survivor = Survivor {
+                    id: root.id,
+                    size: root.size(),
+                    type_id: root.type_id,
+                    tag: root.tag,
+                };
+
+                if !self
+                    .options
+                    .promotion_policy
+                    .should_promote(&survivor, survivals)
+                {
+                    unsafe { root.as_mut().get_unchecked_mut().age = survivals };
+
+                    kept.push(root);
+                    cursor.outcome.kept += 1;
+
+                    continue;
+                }
+
+                if !self.promote(&mut root, 0) {
+                    self.counters.oom_events += 1;
+                    self.collect_tenured(true);
+                    cursor.outcome.triggered_major = true;
+
+                    if !self.promote(&mut root, 0) {
+                        panic!(
+                            "Old Generation OOM: promoting {} bytes but the largest free block is {} bytes",
+                            root.size(),
+                            self.generations[0].largest_free_block(),
+                        );
+                    }
+                }
+
+                self.roots[0].push(root);
+                cursor.outcome.survived += 1;
+            } else {
+                self.counters.bytes_reclaimed += root.size();
+                cursor.outcome.young_freed_bytes += root.size();
+                self.ids.release(root.id);
+
+                // The object's handle was dropped, so only weak handles can still
+                // point at its metadata
+                if let Some(root) = rooted::leave_to_handles(root) {
+                    if self.spare_roots.len() < MAX_SPARE_ROOTS {
+                        self.spare_roots.push(root);
+                    }
+                }
+            }
+        }
+
+        // Kept survivors stay at the front of the young roots, and are only moved
+        // once every root has been processed
+        cursor.kept += kept.len();
+        let end = cursor.kept - kept.len();
+        drop(self.young_roots.splice(end..end, kept));
+
+        if self.young_roots.len() > cursor.kept {
+            trace!(
+                "Paused Scavenge cycle with {} roots left",
+                self.young_roots.len() - cursor.kept
+            );
+            self.scavenge_cursor = Some(cursor);
+
+            return None;
+        }
+
+        trace!("Finished processing roots");
+
+        // Growing an object can move it past ones allocated after it, so the kept
+        // survivors are put in address order before being compacted
+        let kept = &mut self.young_roots[..cursor.kept];
+        kept.sort_by_key(|root| root.data_ptr());
+        let frontier = kept.iter_mut().fold(self.young_start, |frontier, root| {
+            Self::keep_young(root, frontier)
+        });
+
+        // Zero out the young heap past the kept survivors, everything past the bump
+        // pointer is still zeroed (or lazily freed) from the last scavenge
+        let used = self.young_current.offset_from(frontier);
+        match self.options.young_reclaim {
+            YoungReclaim::Memset => unsafe { memory::zero(frontier, used) },
+            YoungReclaim::MadvFree => unsafe { memory::free_lazily(frontier, used) },
+        }
+        self.young_current = frontier;
+
+        // Whatever the written objects were given has been promoted or is reclaimed,
+        // but a background mark still needs to know they were written to
+        let written = self.ids.take_remembered();
+        if let Some(mark) = &mut self.background_mark {
+            mark.remember(written);
+        }
+
+        self.last_promoted = self.counters.bytes_promoted - cursor.promoted_before;
+        cursor.outcome.promoted = self.last_promoted;
+        self.update_pressure();
+        #[cfg(all(feature = "trace-audit", debug_assertions))]
+        self.audit_traces();
+        #[cfg(feature = "debug-accounting")]
+        self.verify_accounting();
+
+        self.events.record(
+            PauseKind::Scavenge,
+            self.counters.bytes_reclaimed - cursor.reclaimed_before,
+        );
+
+        info!("Finished Scavenge cycle");
+
+        Some(cursor.outcome)
+    }
+
+    /// Finishes the scavenge started by [`BumpHeap::scavenge_step`], if there is
+    /// one, so that the young generation can be allocated into
+    fn finish_scavenge(&mut self) {
+        if self.scavenge_cursor.is_some() {
+            self.scavenge();
+        }
+    }
+
+    /// Moves a young survivor that isn't being promoted down to `frontier`, the
+    /// end of the survivors kept so far, returning the new end
+    ///
+    /// Survivors are moved in address order, so the survivor never moves up
+    /// and can't overwrite one that hasn't been moved yet
+    fn keep_young(root: &mut Pin<Box<RootedInner>>, frontier: HeapPointer) -> HeapPointer {
+        let (size, align) = (root.size(), root.align);
+        let from = root.data_ptr();
+        let to = frontier + memory::padding_for(frontier.as_usize(), align);
+        debug_assert!(to <= from);
+
+        unsafe {
+            ptr::copy(from.as_ptr::<u8>(), to.as_mut_ptr::<u8>(), size);
+            root.as_mut().get_unchecked_mut().relocate(to);
+        }
+
+        to + size
+    }
+
+    /// Whether allocations that run out of room are allowed to collect
+    fn collects_on_alloc(&self) -> bool {
+        self.options.collect && self.options.collection_policy == CollectionPolicy::Automatic
+    }
+
+    /// Collects if the heap's [collection policy](BumpOptions::collection_policy)
+    /// says it should, for interpreters that only let objects move between operations
+    ///
+    /// Finishes the scavenge started by [`BumpHeap::scavenge_step`] if there is
+    /// one. Under [`CollectionPolicy::Manual`] this also scavenges once less than
+    /// the policy's headroom is left free in the young generation, which is the
+    /// only way the heap collects on its own
+    pub fn safepoint(&mut self) {
+        self.finish_scavenge();
+
+        if let CollectionPolicy::Manual { headroom } = self.options.collection_policy {
+            if self.young_end.offset_from(self.young_current) < headroom {
+                trace!("Young generation passed its headroom, starting scavenge at safepoint");
+                self.scavenge();
+            }
+        }
+    }
+
+    /// Copies an object into the tenured generation at `index`, returning `false`
+    /// if the generation can't fit it
+    fn promote(&mut self, root: &mut Pin<Box<RootedInner>>, index: usize) -> bool {
+        let size = root.size();
+        let (ptr, pocket_size) = match self.generations[index].alloc_aligned(size, root.align) {
+            Some(allocation) => allocation,
+            None => return false,
+        };
+        let generation = index as u8 + 1;
+
+        ptr.debug_check_bounds(size, self.generations[index].bounds());
+        unsafe {
+            ptr::copy(root.value_ptr() as *const u8, ptr.as_mut_ptr::<u8>(), size);
+
+            let inner = root.as_mut().get_unchecked_mut();
+            inner.heap = ContainingHeap::Generation(generation, pocket_size);
+            inner.age = 0;
+        }
+
+        if let Some(on_promote) = self.options.on_promote {
+            on_promote(PromoteEvent {
+                id: root.id,
+                size,
+                pocket_size,
+                generation,
+            });
+        }
+
+        unsafe { root.as_mut().get_unchecked_mut().relocate(ptr) };
+
+        self.counters.bytes_promoted += size;
+        true
+    }
+
+    /// Ages every live object in the tenured generation at `index`, moving the ones
+    /// that have reached the tenure age up into the next generation
+    fn tenure(&mut self, index: usize) {
+        let roots = mem::take(&mut self.roots[index]);
+        let mut remaining = Vec::with_capacity(roots.len());
+
+        for mut root in roots {
+            if root.is_rooted() && !root.pinned {
+                let age = root.age.saturating_add(1);
+                unsafe { root.as_mut().get_unchecked_mut().age = age };
+
+                if let ContainingHeap::Generation(_, pocket_size) = root.heap {
+                    let from = root.data_ptr();
+
+                    // Objects that don't fit in the next generation stay put until they do
+                    if age >= self.options.tenure_age && self.promote(&mut root, index + 1) {
+                        self.generations[index].free(from, pocket_size);
+                        self.roots[index + 1].push(root);
+
+                        continue;
+                    }
+                }
+            }
+
+            remaining.push(root);
+        }
+
+        self.roots[index] = remaining;
+    }
+
+    /// Collects every tenured generation, oldest first, so that an object moves
+    /// up at most one generation per major collection
+    ///
+    /// If the heap is under `High` or `Critical` memory pressure, every
+    /// [`Soft`](crate::Soft) handle is cleared first. If a
+    /// [background mark](BumpHeap::request_background_mark) was started, this
+    /// waits for it and sweeps the objects it found unreachable before anything else
+    ///
+    /// Young objects live in their own root list, which a major collection never
+    /// looks at, and it doesn't scavenge first. Dead young objects are only
+    /// reclaimed by the next scavenge, so run one first to collect the whole heap
+    pub fn major(&mut self) {
+        self.finish_scavenge();
+        self.collect_tenured(false);
+        #[cfg(feature = "debug-accounting")]
+        self.verify_accounting();
+    }
+
+    /// A [major collection](BumpHeap::major), for scavenges that need one while
+    /// some of their survivors are out of every root list and can't be accounted for,
+    /// which is what `scavenging` says
+    fn collect_tenured(&mut self, scavenging: bool) {
+        if !self.options.collect {
+            return;
+        }
+
+        info!("Starting a Major cleanup cycle");
+        self.counters.major_count += 1;
+        let reclaimed_before = self.counters.bytes_reclaimed;
+
+        if let Some(mark) = self.background_mark.take() {
+            let unreachable = mark.join(&self.ids.remembered());
+            trace!(
+                "Sweeping {} objects marked in the background",
+                unreachable.len()
+            );
+
+            self.sweep_unreachable(|root| unreachable.contains(&root.id));
+        }
+
+        if self.memory_pressure() >= Pressure::High {
+            self.clear_soft_handles(scavenging);
+        }
+
+        for index in (0..self.generations.len()).rev() {
+            if index + 1 < self.generations.len() {
+                self.tenure(index);
+            }
+
+            let ids = &mut self.ids;
+            let on_relocate = self.options.on_relocate;
+            self.generations[index].collect(
+                &mut self.roots[index],
+                &mut self.counters,
+                |root| ids.release(root.id),
+                |root, from| relocated(on_relocate, root, from),
+            );
+        }
+
+        self.trim_metadata();
+        self.update_pressure();
+        #[cfg(all(feature = "trace-audit", debug_assertions))]
+        self.audit_traces();
+
+        self.events.record(
+            PauseKind::Major,
+            self.counters.bytes_reclaimed - reclaimed_before,
+        );
+
+        info!("Finished a Major cleanup cycle");
+    }
+
+    /// Gives back the memory of root lists that are much larger than the number
+    /// of objects left in them, which every major collection does after sweeping
+    ///
+    /// A list is trimmed once its capacity is more than the
+    /// [shrink factor](BumpOptions::roots_shrink_factor) times its length, so a
+    /// burst of objects doesn't hold on to its metadata forever once it's collected
+    pub fn trim_metadata(&mut self) {
+        self.finish_scavenge();
+
+        let factor = match self.options.roots_shrink_factor {
+            0 => return,
+            factor => factor,
+        };
+
+        for roots in iter::once(&mut self.young_roots).chain(self.roots.iter_mut()) {
+            let len = roots.len().max(MIN_ROOTS_CAPACITY);
+            if roots.capacity() > len.saturating_mul(factor) {
+                trace!(
+                    "Trimming a root list from {} to {} slots",
+                    roots.capacity(),
+                    len,
+                );
+                roots.shrink_to(len);
+            }
+        }
+    }
+
+    /// Starts marking the heap on a background thread, which the next major
+    /// collection (including one started by an allocation) waits for, sweeping the
+    /// tenured objects it found unreachable before collecting as usual
+    ///
+    /// Marking follows the same rules as [`BumpHeap::collect_mark_sweep_only`]. Values
+    /// can't be traced from another thread while they may be written to, so the
+    /// edges of every object are recorded here on the calling thread, and only
+    /// following them from the roots happens in the background. The mark works
+    /// from that snapshot of the object graph, so objects that become unreachable
+    /// after it survive the sweep and objects allocated after it are never part
+    /// of it. Taking a handle out of an unreachable object's [`GcCell`](crate::GcCell)
+    /// would make what it points to reachable again behind the mark's back, so
+    /// the objects the write barrier catches being written to in the meantime are
+    /// kept, along with everything they held when the mark started. Only the writes
+    /// [`BumpHeap::is_remembered`] lists are caught
+    ///
+    /// Does nothing if a mark is already running, or if the heap was created while
+    /// 255 others were alive on its thread. Such a heap doesn't get a number of its
+    /// own, so the write barrier can't tell which heap the objects written to are
+    /// in and loses every write
+    pub fn request_background_mark(&mut self) {
+        self.finish_scavenge();
+        if !self.options.collect || self.background_mark.is_some() {
+            return;
+        }
+        if !self.ids.is_numbered() {
+            warn!("Not marking in the background, since the write barrier can't see this heap");
+            return;
+        }
+
+        // Dead objects still hold their children's handles, so they're traced too
+        let mut tracer = Tracer::new();
+        let objects: Vec<SnapshotObject> = self
+            .young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .map(|root| {
+                tracer.clear();
+                root.trace_children(&mut tracer);
+
+                SnapshotObject {
+                    id: root.id,
+                    young: root.heap == ContainingHeap::Eden,
+                    rooted: root.is_rooted(),
+                    edges: tracer.edges().to_vec(),
+                }
+            })
+            .collect();
+
+        trace!("Marking {} objects in the background", objects.len());
+        self.background_mark = Some(BackgroundMark::start(objects));
+    }
+
+    /// Runs a major collection that always compacts the tenured generations,
+    /// regardless of how fragmented they are
+    ///
+    /// Objects aren't moved between generations
+    pub fn compact_now(&mut self) -> CompactStats {
+        self.finish_scavenge();
+        if !self.options.collect {
+            return CompactStats::default();
+        }
+
+        info!("Starting a compacting Major cleanup cycle");
+        self.counters.major_count += 1;
+        self.counters.compaction_count += 1;
+        let reclaimed_before = self.counters.bytes_reclaimed;
+
+        let mut stats = CompactStats::default();
+        for (generation, roots) in self.generations.iter_mut().zip(self.roots.iter_mut()) {
+            let ids = &mut self.ids;
+            self.counters.bytes_reclaimed += generation.sweep(roots, |root| ids.release(root.id));
+
+            let on_relocate = self.options.on_relocate;
+            let compacted =
+                generation.compact(roots, |root, from| relocated(on_relocate, root, from));
+            stats.objects_moved += compacted.objects_moved;
+            stats.bytes_reclaimed += compacted.bytes_reclaimed;
+        }
+
+        self.update_pressure();
+        #[cfg(feature = "debug-accounting")]
+        self.verify_accounting();
+        self.events.record(
+            PauseKind::Compaction,
+            self.counters.bytes_reclaimed - reclaimed_before,
+        );
+
+        info!("Finished a compacting Major cleanup cycle");
+
+        stats
+    }
+
+    /// Runs a major collection that never moves anything, marking which objects
+    /// are reachable from the heap's external roots through their [`Trace`] impls
+    /// and sweeping the rest of the tenured generations in place
+    ///
+    /// An external root is a rooted object whose handle isn't held by any object
+    /// in the heap, one that no object's [`Trace`] impl reports as an edge. Objects
+    /// whose handles are held by other objects only live as long as something
+    /// external reaches them, so garbage cycles and the children of dead objects
+    /// are reclaimed even though their handles are never dropped. A [`Trace`] impl
+    /// that leaves out a child makes that child an external root, which keeps it
+    /// alive after its parent dies
+    ///
+    /// Survivors are never compacted or promoted, so this separates marking and
+    /// sweeping from relocation when debugging the collector, and suits heaps
+    /// full of pinned objects. Dead blocks go back to their generation's free
+    /// list. Like [`BumpHeap::major`] it leaves the young generation alone, whose
+    /// objects can only be reclaimed by a scavenge
+    ///
+    /// Unlike other collections this can reclaim rooted objects, whose values it
+    /// drops following the rule on [`Rooted`]. The values of unrooted objects are
+    /// never dropped, so the children they hold handles to are unrooted instead
+    pub fn collect_mark_sweep_only(&mut self) {
+        self.finish_scavenge();
+        if !self.options.collect {
+            return;
+        }
+
+        info!("Starting a non-moving Major cleanup cycle");
+        self.counters.major_count += 1;
+        let reclaimed_before = self.counters.bytes_reclaimed;
+
+        self.mark();
+        self.sweep_unreachable(|root| root.color() == Color::White);
+
+        self.update_pressure();
+        #[cfg(all(feature = "trace-audit", debug_assertions))]
+        self.audit_traces();
+        #[cfg(feature = "debug-accounting")]
+        self.verify_accounting();
+
+        self.events.record(
+            PauseKind::Major,
+            self.counters.bytes_reclaimed - reclaimed_before,
+        );
+
+        info!("Finished a non-moving Major cleanup cycle");
+    }
+
+    /// Frees the tenured objects that a mark found unreachable, which `is_unreachable`
+    /// picks out, dropping the ones that are still rooted like every reclamation does
+    ///
+    /// Unrooted objects are never dropped, so the handles they hold never will be
+    /// either. Those are the only handles to their children, so the children are
+    /// unrooted once they've been dropped, which lets their metadata be freed
+    fn sweep_unreachable(&mut self, is_unreachable: impl Fn(&RootedInner) -> bool) {
+        let unreachable: Vec<_> = self
+            .roots
+            .iter_mut()
+            .flatten()
+            .filter(|root| {
+                matches!(root.heap, ContainingHeap::Generation(..)) && is_unreachable(root)
+            })
+            .collect();
+
+        let mut orphaned = Tracer::new();
+        for root in unreachable.iter().filter(|root| !root.is_rooted()) {
+            root.trace_children(&mut orphaned);
+        }
+        unsafe { Self::drop_rooted(unreachable.into_iter()) };
+
+        let orphaned: HashSet<AllocId> = orphaned.edges().iter().copied().collect();
+        let roots = self
+            .young_roots
+            .iter_mut()
+            .chain(self.roots.iter_mut().flatten());
+        for root in roots.filter(|root| orphaned.contains(&root.id)) {
+            unsafe { root.as_mut().get_unchecked_mut().rooted = false };
+        }
+
+        for (generation, roots) in self.generations.iter_mut().zip(self.roots.iter_mut()) {
+            let ids = &mut self.ids;
+            self.counters.bytes_reclaimed +=
+                generation.sweep_where(roots, &is_unreachable, |root| ids.release(root.id));
+        }
+    }
+
+    /// Colors every object reachable from the heap's external roots black and every
+    /// other object white, following the edges their [`Trace`] impls report
+    ///
+    /// Every object has exactly one handle and [`Trace`] impls only report the
+    /// handles their values own, so a rooted object whose handle lives inside of
+    /// another object is only held by that object and is reached through its edge.
+    /// Edges of unreachable objects count too, otherwise garbage cycles would keep
+    /// each other alive. The external roots are the rooted objects no object in the heap
+    /// reports as an edge, whose handles are held outside of it. Young objects are
+    /// roots as well, since only scavenges reclaim them, and their children must
+    /// outlive them. Objects are claimed with [`RootedInner::try_mark`] so that
+    /// each one is only traced once, and the colors are left in place for the sweep
+    /// to read
+    fn mark(&self) {
+        let objects: HashMap<AllocId, &RootedInner> = self
+            .young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .map(|root| (root.id, root.as_ref().get_ref()))
+            .collect();
+        for root in objects.values() {
+            root.color.store(Color::White);
+        }
+
+        // Dead objects still hold their children's handles until they're swept, so
+        // they're traced too. Values that were moved out of aren't traced at all
+        let mut tracer = Tracer::new();
+        let mut held = HashSet::new();
+        for root in objects.values() {
+            tracer.clear();
+            root.trace_children(&mut tracer);
+            held.extend(tracer.edges().iter().copied());
+        }
+
+        let young = self.young_roots.iter().map(|root| root.as_ref().get_ref());
+        let external = objects
+            .values()
+            .copied()
+            .filter(|root| root.is_rooted() && !held.contains(&root.id));
+        let mut pending: Vec<&RootedInner> = young
+            .chain(external)
+            .filter(|root| root.try_mark())
+            .collect();
+
+        while let Some(root) = pending.pop() {
+            tracer.clear();
+            root.trace_children(&mut tracer);
+
+            for child in tracer.edges() {
+                // Edges can point into other heaps, which aren't this heap's to mark
+                if let Some(&child) = objects.get(child) {
+                    if child.try_mark() {
+                        pending.push(child);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The fraction of the tenured generations' used span that's free, but
+    /// fragmented into pockets
+    pub fn fragmentation(&self) -> f32 {
+        let span: usize = self
+            .generations
+            .iter()
+            .map(|generation| generation.span())
+            .sum();
+        if span == 0 {
+            return 0.0;
+        }
+
+        1.0 - (self.tenured_used() as f32 / span as f32)
+    }
+
+    /// How close the tenured generations are to filling up, judged by their current
+    /// occupancy plus the amount promoted by the last scavenge
+    pub fn memory_pressure(&self) -> Pressure {
+        let size: usize = self
+            .generations
+            .iter()
+            .map(|generation| generation.size())
+            .sum();
+        let projected = self.tenured_used() + self.last_promoted;
+        let percent_used = projected * 100 / size.max(1);
+
+        self.options.pressure_thresholds.level(percent_used)
+    }
+
+    fn tenured_used(&self) -> usize {
+        self.generations
+            .iter()
+            .map(|generation| generation.used())
+            .sum()
+    }
+
+    fn update_pressure(&mut self) {
+        let pressure = self.memory_pressure();
+
+        if pressure > self.pressure {
+            trace!("Memory pressure rose to {:?}", pressure);
+
+            if let Some(on_pressure) = self.options.on_pressure {
+                on_pressure(pressure);
+            }
+        }
+
+        self.pressure = pressure;
+    }
+
+    /// Calls `f` on every live object of type `T`, except for
+    /// [pinned](BumpHeap::alloc_pinned_permanent) ones since they could be moved
+    /// out of
+    pub fn for_each_mut<T: Any>(&mut self, mut f: impl FnMut(&mut T)) {
+        self.finish_scavenge();
+
+        let roots = self
+            .young_roots
+            .iter_mut()
+            .chain(self.roots.iter_mut().flatten());
+
+        for root in roots.filter(|root| root.is_rooted() && !root.pinned) {
+            if let Some(value) = unsafe { root.as_mut().get_unchecked_mut().downcast_mut::<T>() } {
+                f(value);
+            }
+        }
+    }
+
+    /// The memory live objects own outside of the heap, as reported by the
+    /// [`NativeSize`] impls of the objects allocated with [`BumpHeap::alloc_native`]
+    ///
+    /// Unrooted objects are never dropped, see [`Rooted`], so collecting an object
+    /// usually doesn't free its native memory, it only stops being counted here
+    pub fn total_native_bytes(&self) -> usize {
+        self.young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .filter(|root| root.is_rooted())
+            .map(|root| root.native_bytes())
+            .sum()
+    }
+
+    /// Every free block of the tenured generations, sorted by address
+    pub fn free_blocks(&self) -> impl Iterator<Item = FreeBlock> + '_ {
+        // Generations are laid out one after another, so their blocks stay sorted
+        self.generations
+            .iter()
+            .flat_map(|generation| generation.free_blocks())
+    }
+
+    /// Every live object, young objects first and then each tenured generation's
+    /// in the order they were added to it
+    pub fn iter_live(&self) -> impl Iterator<Item = LiveObject> + '_ {
+        self.young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .filter(|root| root.is_rooted())
+            .map(|root| LiveObject {
+                id: root.id,
+                address: root.data_ptr(),
+                size: root.size(),
+                type_id: root.type_id,
+                location: match root.containing_heap() {
+                    ContainingHeap::Eden => Location::Eden,
+                    ContainingHeap::Generation(generation, _) => Location::Tenured(generation),
+                },
+            })
+    }
+
+    /// Every live object sorted by its current address, for looking at how the
+    /// heap is laid out
+    ///
+    /// This is the order compaction slides objects in, which keeps them in the
+    /// same order relative to each other. The objects are collected and sorted up
+    /// front, so this allocates
+    pub fn iter_by_address(&self) -> impl Iterator<Item = LiveObject> {
+        let mut objects: Vec<_> = self.iter_live().collect();
+        objects.sort_unstable_by_key(|object| object.address);
+
+        objects.into_iter()
+    }
+
+    /// Whether the object is in the remembered set, which holds the tenured objects
+    /// that have been written to through [`Rooted::replace`], [`Rooted::write`],
+    /// [`Rooted::map_in_place`] or a [`GcCell`](crate::GcCell) since the last scavenge
+    ///
+    /// Writes through plain mutable references, like the ones [`Handle::get_mut`]
+    /// and [`Rooted::as_pin_mut`] hand out, aren't seen by the write barrier.
+    /// Objects are kept alive by their handles, so the set isn't needed to find
+    /// young objects' parents, but it does tell which tenured objects may hold
+    /// young ones. The set is always empty for heaps the barrier can't see, see
+    /// [`BumpHeap::request_background_mark`]
+    pub fn is_remembered(&self, id: AllocId) -> bool {
+        self.ids.is_remembered(id)
+    }
+
+    /// Resolves an id to the current address of its object, or `None` if the
+    /// object has been collected
+    pub fn locate(&self, id: AllocId) -> Option<HeapPointer> {
+        self.ids
+            .resolve(id)
+            .map(|inner| unsafe { (*inner).data_ptr() })
+    }
+
+    /// Writes every live object to `w` in the format read by [`read_dump`](crate::read_dump)
+    pub fn dump<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let live = || {
+            self.young_roots
+                .iter()
+                .chain(self.roots.iter().flatten())
+                .filter(|root| root.is_rooted())
+        };
+        let tenured_bounds: Vec<_> = self
+            .generations
+            .iter()
+            .map(|generation| generation.bounds())
+            .collect();
+
+        let mut writer = DumpWriter::new(w);
+        writer.header(
+            (self.young_start, self.young_end),
+            &tenured_bounds,
+            self.options.tenure_age,
+            live().count(),
+        )?;
+        for root in live() {
+            writer.object(root)?;
+        }
+
+        Ok(())
+    }
+
+    /// A hash of every live object's id, type, size and value, for telling
+    /// whether the live heap has changed
+    ///
+    /// Objects are hashed in order of their ids, so collections that only move
+    /// objects keep the fingerprint the same, and two heaps that allocated the
+    /// same objects in the same order fingerprint the same. Values are hashed
+    /// through their [`Hash`] impls, so every live object has to have been
+    /// allocated with [`BumpHeap::alloc_hashed`]. Returns `None` if one wasn't,
+    /// since a change to its value couldn't be seen
+    pub fn live_fingerprint(&self) -> Option<u64> {
+        let mut live: Vec<&RootedInner> = self
+            .young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .filter(|root| root.is_rooted())
+            .map(|root| root.as_ref().get_ref())
+            .collect();
+        live.sort_unstable_by_key(|root| root.id);
+
+        let mut hasher = FxHasher::default();
+        for root in live {
+            root.id.index().hash(&mut hasher);
+            root.id.generation().hash(&mut hasher);
+            root.type_id.hash(&mut hasher);
+            root.size().hash(&mut hasher);
+            if !root.hash_value(&mut hasher) {
+                return None;
+            }
+        }
+
+        Some(hasher.finish())
+    }
+
+    /// Prints a human-readable summary of the heap to stdout, see
+    /// [`BumpHeap::write_report`]
+    pub fn print_report(&self) {
+        let stdout = io::stdout();
+        self.write_report(&mut stdout.lock())
+            .expect("Failed to print the heap report");
+    }
+
+    /// Runs the allocation pattern described by `spec` against the heap, for
+    /// comparing heap options without writing a benchmark loop for each one
+    ///
+    /// The returned stats only cover the workload, not anything the heap did before it
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spec` has no object sizes or if the heap runs out of memory
+    #[cfg(any(test, feature = "testing"))]
+    pub fn run_workload(&mut self, spec: WorkloadSpec) -> WorkloadStats {
+        crate::workload::run(self, spec)
+    }
+
+    /// Writes a human-readable summary of the heap's generations, pockets and
+    /// counters to `w`, for debugging
+    pub fn write_report<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let live =
+            |roots: &[Pin<Box<RootedInner>>]| roots.iter().filter(|root| root.is_rooted()).count();
+
+        writeln!(
+            w,
+            "Heap of {} bytes, {:?} pressure, {:.1}% fragmented",
+            self.heap_size,
+            self.memory_pressure(),
+            self.fragmentation() * 100.0,
+        )?;
+        writeln!(
+            w,
+            "  Eden: {} of {} bytes used, {} live objects",
+            self.young_current.offset_from(self.young_start),
+            self.young_end.offset_from(self.young_start),
+            live(&self.young_roots),
+        )?;
+
+        for (index, (generation, roots)) in self.generations.iter().zip(&self.roots).enumerate() {
+            let pocket_sizes = generation.pocket_sizes();
+            writeln!(
+                w,
+                "  Generation {}: {} of {} bytes used, {} live objects",
+                index + 1,
+                generation.used(),
+                generation.size(),
+                live(roots),
+            )?;
+
+            let mut free = [0; MAX_POCKETS];
+            let mut large = 0;
+            for block in generation.free_blocks() {
+                let pocket = block.pocket.and_then(|size| {
+                    pocket_sizes
+                        .as_slice()
+                        .iter()
+                        .position(|&pocket| pocket == size)
+                });
+
+                match pocket {
+                    Some(pocket) => free[pocket] += 1,
+                    None => large += 1,
+                }
+            }
+
+            write!(w, "    Free blocks:")?;
+            for (size, free) in pocket_sizes.as_slice().iter().zip(&free) {
+                write!(w, " {}B: {},", size, free)?;
+            }
+            writeln!(w, " large: {}", large)?;
+        }
+
+        let counters = &self.counters;
+        writeln!(
+            w,
+            "  {} allocations of {} bytes, {} promoted, {} reclaimed",
+            counters.total_allocations,
+            counters.bytes_allocated,
+            counters.bytes_promoted,
+            counters.bytes_reclaimed,
+        )?;
+        writeln!(
+            w,
+            "  {} scavenges, {} majors, {} compactions, {} old generation OOMs",
+            counters.scavenge_count,
+            counters.major_count,
+            counters.compaction_count,
+            counters.oom_events,
+        )
+    }
+
+    /// The number of live objects and bytes allocated at each site, for finding
+    /// out what's keeping memory alive
+    ///
+    /// Objects are grouped by the tag they were [allocated](BumpHeap::alloc_tagged)
+    /// with, or by their allocation's backtrace with the `capture-backtrace` feature
+    pub fn leak_report(&self) -> HashMap<AllocSite, SiteStat> {
+        let mut report: HashMap<AllocSite, SiteStat> = HashMap::new();
+
+        let roots = self.young_roots.iter().chain(self.roots.iter().flatten());
+        for root in roots.filter(|root| root.is_rooted()) {
+            let site = match (root.tag, root.backtrace_site()) {
+                (Some(tag), _) => AllocSite::Tagged(tag),
+                (None, Some(site)) => site,
+                (None, None) => AllocSite::Unknown,
+            };
+
+            let stat = report.entry(site).or_default();
+            stat.count += 1;
+            stat.bytes += root.size();
+        }
+
+        report
+    }
+
+    /// The live objects with the given mark color
+    #[cfg(feature = "research")]
+    pub fn objects_with_color(&self, color: crate::Color) -> impl Iterator<Item = AllocId> + '_ {
+        self.young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .filter(move |root| root.is_rooted() && root.color() == color)
+            .map(|root| root.id)
+    }
+
+    /// The number of live objects and bytes of each type in the heap
+    #[cfg(feature = "type-profile")]
+    pub fn type_profile(&self) -> HashMap<TypeId, TypeStat> {
+        let mut profile: HashMap<TypeId, TypeStat> = HashMap::new();
+
+        let roots = self.young_roots.iter().chain(self.roots.iter().flatten());
+        for root in roots.filter(|root| root.is_rooted()) {
+            let stat = profile.entry(root.type_id).or_insert(TypeStat {
+                name: root.type_name,
+                count: 0,
+                bytes: 0,
+            });
+
+            stat.count += 1;
+            stat.bytes += root.size();
+        }
+
+        profile
+    }
+
+    /// Checks that every traced object reports all of its children, only reports
+    /// children that are live objects of this heap, and reports the same ones every
+    /// time it's traced, panicking otherwise
+    ///
+    /// Children that weren't reported are found by scanning every word of the
+    /// object for the address a handle holds, that of a live object's metadata.
+    /// This is conservative, an integer that happens to equal such an address is
+    /// reported as a missing edge
+    #[cfg(all(feature = "trace-audit", debug_assertions))]
+    fn audit_traces(&self) {
+        let live = || {
+            self.young_roots
+                .iter()
+                .chain(self.roots.iter().flatten())
+                .filter(|root| root.is_rooted())
+        };
+
+        let metadata: HashMap<usize, AllocId> = live()
+            .map(|root| {
+                (
+                    root.as_ref().get_ref() as *const RootedInner as usize,
+                    root.id,
+                )
+            })
+            .collect();
+
+        let (mut tracer, mut again) = (Tracer::new(), Tracer::new());
+        for root in live() {
+            tracer.clear();
+            if !root.trace_children(&mut tracer) {
+                continue;
+            }
+
+            for &child in tracer.edges() {
+                assert!(
+                    matches!(self.ids.resolve(child), Some(inner) if unsafe { (*inner).is_rooted() }),
+                    "Trace audit: {} reports {} which isn't a live object of this heap",
+                    root.id,
+                    child,
+                );
+            }
+
+            again.clear();
+            root.trace_children(&mut again);
+            assert_eq!(
+                tracer.edges(),
+                again.edges(),
+                "Trace audit: {} reports different children each time it's traced",
+                root.id,
+            );
+
+            let start = root.data_ptr();
+            let words = root.size() / mem::size_of::<usize>();
+            for word in 0..words {
+                // Safety: Live objects occupy `size` bytes at their address
+                let value =
+                    unsafe { memory::read_word((start + word * mem::size_of::<usize>()).as_ptr()) };
+
+                if let Some(&child) = metadata.get(&value) {
+                    assert!(
+                        tracer.edges().contains(&child),
+                        "Trace audit: {} holds a handle to {} that its Trace impl didn't report",
+                        root.id,
+                        child,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The `[start, end)` address range of the young generation
+    ///
+    /// The bounds change when the young generation is
+    /// [grown](BumpHeap::grow_young_generation), so they have to be queried again
+    /// afterwards
+    pub fn young_bounds(&self) -> (HeapPointer, HeapPointer) {
+        (self.young_start, self.young_end)
+    }
+
+    /// The `[start, end)` address range of a tenured generation, numbered from 1
+    /// like [`Location::Tenured`](crate::Location::Tenured), or `None` if the heap
+    /// doesn't have that generation
+    ///
+    /// See [`BumpHeap::young_bounds`] for when the bounds change
+    pub fn tenured_bounds(&self, generation: u8) -> Option<(HeapPointer, HeapPointer)> {
+        let index = usize::from(generation).checked_sub(1)?;
+        self.generations
+            .get(index)
+            .map(|generation| generation.bounds())
+    }
+
+    /// The `[start, end)` address range of the heap's region, which every
+    /// generation lies within apart from a [separate](BumpHeap::with_intermediate)
+    /// intermediate generation
+    ///
+    /// See [`BumpHeap::young_bounds`] for when the bounds change
+    pub fn bounds(&self) -> (HeapPointer, HeapPointer) {
+        (self.young_start, self.young_start + self.heap_size)
+    }
+
+    /// Whether `ptr` is in one of the heap's generations
+    pub(crate) fn contains(&self, ptr: HeapPointer) -> bool {
+        let (start, end) = self.bounds();
+        (start..end).contains(&ptr)
+            || self.generations.iter().any(|generation| {
+                let (start, end) = generation.bounds();
+                (start..end).contains(&ptr)
+            })
+    }
+
+    /// The `[start, end)` address range of a generation
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn bounds_of(&self, heap: ContainingHeap) -> (HeapPointer, HeapPointer) {
+        match heap {
+            ContainingHeap::Eden => self.young_bounds(),
+            ContainingHeap::Generation(generation, _) => self.tenured_bounds(generation).unwrap(),
+        }
+    }
+
+    /// The live object at `ptr`, if there is one
+    pub(crate) fn object_at(&self, ptr: HeapPointer) -> Option<&RootedInner> {
+        self.young_roots
+            .iter()
+            .chain(self.roots.iter().flatten())
+            .find(|root| root.is_rooted() && root.data_ptr() == ptr)
+            .map(|root| root.as_ref().get_ref())
+    }
+
+    /// The most recent collections, oldest first, up to the number set by
+    /// [`BumpOptions::event_history`]
+    pub fn recent_events(&self) -> &[PauseEvent] {
+        self.events.events()
+    }
+
+    pub fn counters(&self) -> &GcCounters {
+        &self.counters
+    }
+
+    pub fn reset_counters(&mut self) {
+        #[cfg(feature = "debug-accounting")]
+        {
+            self.retired.0 += self.counters.bytes_allocated;
+            self.retired.1 += self.counters.bytes_reclaimed;
+        }
+
+        self.counters.reset();
+    }
+
+    /// Every byte the heap has allocated and reclaimed since it was created
+    #[cfg(feature = "debug-accounting")]
+    pub fn accounting(&self) -> crate::Accounting {
+        crate::Accounting {
+            total_allocated: self.retired.0 + self.counters.bytes_allocated,
+            total_reclaimed: self.retired.1 + self.counters.bytes_reclaimed,
+            live_bytes: self
+                .young_roots
+                .iter()
+                .chain(self.roots.iter().flatten())
+                .map(|root| root.size())
+                .sum(),
+        }
+    }
+
+    /// Checks that every byte the heap allocated was either reclaimed or is still
+    /// held, which is run after every collection and when the heap is dropped
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes don't add up, which means the collector leaked an
+    /// object or reclaimed one twice
+    #[cfg(feature = "debug-accounting")]
+    pub fn verify_accounting(&self) {
+        let accounting = self.accounting();
+        assert!(
+            accounting.is_balanced(),
+            "Heap accounting mismatch: {} bytes allocated but {} reclaimed and {} live",
+            accounting.total_allocated,
+            accounting.total_reclaimed,
+            accounting.live_bytes,
+        );
+    }
+}
+
+/// Called before an object's value is written to, adding the object to its heap's
+/// remembered set if it's tenured, see [`BumpHeap::is_remembered`]
+#[inline]
+pub(crate) fn write_barrier(object: AllocId) {
+    memory::remember(object);
+}
+
+fn relocated(on_relocate: Option<fn(RelocateEvent)>, root: &RootedInner, from: HeapPointer) {
+    if let Some(on_relocate) = on_relocate {
+        on_relocate(RelocateEvent {
+            id: root.id,
+            from,
+            to: root.data_ptr(),
+        });
+    }
+}
+
+impl Drop for BumpHeap {
+    fn drop(&mut self) {
+        info!("Dropping Bump Heap");
+
+        // Panicking again while unwinding would abort
+        #[cfg(feature = "debug-accounting")]
+        if !std::thread::panicking() {
+            self.verify_accounting();
+        }
+
+        // Any handles still alive get to keep their (now empty) metadata, which they free when dropped
+        for root in self
+            .young_roots
+            .drain(..)
+            .chain(self.roots.drain(..).flatten())
+        {
+            let _ = rooted::leave_to_handles(root);
+        }
+
+        // Leaked objects live in the generations' memory for as long as the program runs
+        if self.leaked {
+            mem::forget(mem::take(&mut self.generations));
+
+            return;
+        }
+
+        if self.owns_region && !self.dormant {
+            let layout = Layout::from_size_align(self.heap_size, self.options.alignment()).unwrap();
+
+            unsafe {
+                self.options
+                    .memory_source
+                    .dealloc(self.young_start.as_mut_ptr(), layout)
+            };
+        }
+    }
+}
+
+impl Default for BumpHeap {
+    fn default() -> Self {
+        Self::new(BumpOptions::default())
+    }
+}
+
+/// What a [`BumpHeap::scavenge`] did
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ScavengeOutcome {
+    /// The number of objects that were alive and promoted
+    pub survived: usize,
+    /// The number of objects that were alive but kept in the young generation
+    /// by the [promotion policy](BumpOptions::promotion_policy)
+    pub kept: usize,
+    /// The number of bytes promoted
+    pub promoted: usize,
+    /// The number of bytes of dead objects reclaimed from the young generation
+    pub young_freed_bytes: usize,
+    /// Whether the first tenured generation filled up and a major collection
+    /// was run to make room, a sign that the heap is close to running out of memory
+    pub triggered_major: bool,
+}
+
+/// A live object, from [`BumpHeap::iter_live`] and [`BumpHeap::iter_by_address`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LiveObject {
+    pub id: AllocId,
+    /// Where the object currently is, which changes whenever it's moved
+    pub address: HeapPointer,
+    /// The size of the object
+    pub size: usize,
+    pub type_id: TypeId,
+    pub location: Location,
+}
+
+/// When a heap is allowed to collect, see [`BumpOptions::collection_policy`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CollectionPolicy {
+    /// Collect whenever an allocation runs out of room
+    Automatic,
+    /// Only collect when asked to or at a [`BumpHeap::safepoint`] that finds
+    /// less than `headroom` bytes free in the young generation, so allocations
+    /// that run out of room fail instead of moving objects
+    Manual { headroom: usize },
+}
+
+/// How a scavenge clears the young generation's used span, see
+/// [`BumpOptions::young_reclaim`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum YoungReclaim {
+    /// Zero the span with a memset
+    Memset,
+    /// Hand the span's whole pages back to the kernel with `MADV_FREE`, which
+    /// only zeroes them if it reclaims them, and otherwise leaves their old bytes
+    /// in place. Allocation always initializes objects, so the young generation
+    /// doesn't need to be zeroed. Only does anything on Linux
+    MadvFree,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BumpOptions {
+    pub(crate) young_heap_size: usize,
+    /// The sizes of the tenured generations, youngest first, of which only the
+    /// first `generations - 1` are used
+    pub(crate) tenured_sizes: [usize; MAX_GENERATIONS - 1],
+    pub(crate) generations: usize,
+    pub(crate) tenure_age: u8,
+    pub(crate) collect: bool,
+    pub(crate) collection_policy: CollectionPolicy,
+    pub(crate) memory_source: &'static dyn MemorySource,
+    pub(crate) event_history: usize,
+    pub(crate) old_pocket_sizes: PocketSizes,
+    pub(crate) native_limit: Option<usize>,
+    pub(crate) allow_undersized_old_gen: bool,
+    pub(crate) pressure_thresholds: PressureThresholds,
+    pub(crate) on_pressure: Option<fn(Pressure)>,
+    pub(crate) backing_align: Option<usize>,
+    pub(crate) on_promote: Option<fn(PromoteEvent)>,
+    pub(crate) on_relocate: Option<fn(RelocateEvent)>,
+    pub(crate) promotion_policy: &'static dyn PromotionPolicy,
+    pub(crate) roots_shrink_factor: usize,
+    pub(crate) madvise_hugepage: bool,
+    pub(crate) prefault: bool,
+    pub(crate) young_reclaim: YoungReclaim,
+}
+
+impl BumpOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn young_heap_size(mut self, size: usize) -> Self {
+        self.young_heap_size = size;
+        self
+    }
+
+    /// Sets the size of the first tenured generation, which every object that
+    /// survives a scavenge is promoted into
+    pub fn old_heap_size(mut self, size: usize) -> Self {
+        self.tenured_sizes[0] = size;
+        self
+    }
+
+    /// Sets the sizes of the tenured generations, youngest first, giving the heap
+    /// `sizes.len() + 1` generations in total
+    pub fn tenured_generations(mut self, sizes: &[usize]) -> Self {
+        let count = sizes.len().min(MAX_GENERATIONS - 1);
+        self.tenured_sizes[..count].copy_from_slice(&sizes[..count]);
+        self.generations = sizes.len() + 1;
+        self
+    }
+
+    /// Sets how many major collections an object has to survive in a tenured
+    /// generation before it's moved up into the next one
+    pub fn tenure_age(mut self, age: u8) -> Self {
+        self.tenure_age = age;
+        self
+    }
+
+    /// Allows an old generation smaller than the young generation
+    ///
+    /// Every object that survives a scavenge is promoted into the old generation,
+    /// so if the old generation is smaller than the young one then a scavenge where
+    /// most of the young generation survives will run the old generation out of memory
+    pub fn allow_undersized_old_gen(mut self, allow: bool) -> Self {
+        self.allow_undersized_old_gen = allow;
+        self
+    }
+
+    pub fn pressure_thresholds(mut self, thresholds: PressureThresholds) -> Self {
+        self.pressure_thresholds = thresholds;
+        self
+    }
+
+    /// Whether the heap collects at all, when it doesn't allocations only bump through
+    /// the young generation and collections do nothing, see [`BumpHeap::new_bump_only`]
+    pub fn collect(mut self, collect: bool) -> Self {
+        self.collect = collect;
+        self
+    }
+
+    /// Sets when the heap is allowed to collect, which defaults to
+    /// [`CollectionPolicy::Automatic`]
+    pub fn collection_policy(mut self, policy: CollectionPolicy) -> Self {
+        self.collection_policy = policy;
+        self
+    }
+
+    /// Sets a callback that's called whenever a collection leaves the heap
+    /// under more pressure than before
+    pub fn on_pressure(mut self, on_pressure: fn(Pressure)) -> Self {
+        self.on_pressure = Some(on_pressure);
+        self
+    }
+
+    /// Sets a callback that's called for every object a scavenge promotes into
+    /// the intermediate heap
+    pub fn on_promote(mut self, on_promote: fn(PromoteEvent)) -> Self {
+        self.on_promote = Some(on_promote);
+        self
+    }
+
+    /// Sets the policy that decides which young survivors a scavenge promotes,
+    /// which defaults to promoting every survivor
+    pub fn promotion_policy(mut self, policy: &'static dyn PromotionPolicy) -> Self {
+        self.promotion_policy = policy;
+        self
+    }
+
+    /// Sets a callback that's fired whenever compaction moves an object, for
+    /// patching raw addresses held outside of the heap
+    pub fn on_relocate(mut self, on_relocate: fn(RelocateEvent)) -> Self {
+        self.on_relocate = Some(on_relocate);
+        self
+    }
+
+    /// Whether to ask the kernel to back the heap with transparent huge pages,
+    /// which only does anything on Linux
+    ///
+    /// The kernel only uses huge pages for the parts of the heap that cover whole,
+    /// aligned huge pages, so this works best for large heaps with a
+    /// [backing alignment](BumpOptions::backing_align) of [`HUGE_PAGE_SIZE`](crate::HUGE_PAGE_SIZE)
+    pub fn madvise_hugepage(mut self, advise: bool) -> Self {
+        self.madvise_hugepage = advise;
+        self
+    }
+
+    /// Whether to [prefault](BumpHeap::prefault) the heap's memory whenever it's
+    /// allocated, which is when the heap is created, woken from hibernation or
+    /// has its young generation grown
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// Sets how scavenges clear the young generation, which defaults to
+    /// [`YoungReclaim::Memset`]
+    ///
+    /// [`YoungReclaim::MadvFree`] is usually cheaper for large young generations,
+    /// since the kernel only zeroes the pages it actually takes back
+    pub fn young_reclaim(mut self, reclaim: YoungReclaim) -> Self {
+        self.young_reclaim = reclaim;
+        self
+    }
+
+    /// Sets the alignment of the heap's backing allocation, which defaults to the page size
+    ///
+    /// The alignment must be a power of two and at least 16 bytes, the alignment
+    /// the tenured generations guarantee their objects
+    pub fn backing_align(mut self, align: usize) -> Self {
+        self.backing_align = Some(align);
+        self
+    }
+
+    pub(crate) fn tenured_sizes(&self) -> &[usize] {
+        &self.tenured_sizes[..self.generations - 1]
+    }
+
+    /// The total size of every generation
+    pub fn heap_size(&self) -> usize {
+        self.young_heap_size + self.tenured_sizes().iter().sum::<usize>()
+    }
+
+    /// Sets where the heap allocates its backing memory from, which defaults to
+    /// the global allocator
+    pub fn memory_source(mut self, source: &'static dyn MemorySource) -> Self {
+        self.memory_source = source;
+        self
+    }
+
+    /// Sets how many of the most recent collections are kept for
+    /// [`BumpHeap::recent_events`], which can be zero to keep none
+    pub fn event_history(mut self, events: usize) -> Self {
+        self.event_history = events;
+        self
+    }
+
+    /// Sets the pocket sizes the tenured generations sort objects into, which
+    /// can be tuned to the sizes of long-lived objects independently of anything
+    /// else
+    pub fn old_pocket_sizes(mut self, sizes: PocketSizes) -> Self {
+        self.old_pocket_sizes = sizes;
+        self
+    }
+
+    /// Sets how many times larger than its length a root list's capacity has to
+    /// be before [`BumpHeap::trim_metadata`] shrinks it, which defaults to 4
+    ///
+    /// Larger factors reallocate less often for heaps whose object count swings
+    /// back and forth, and zero never trims
+    pub fn roots_shrink_factor(mut self, factor: usize) -> Self {
+        self.roots_shrink_factor = factor;
+        self
+    }
+
+    /// Collects whenever objects allocated with [`BumpHeap::alloc_native`] have
+    /// allocated more than `bytes` of native memory since the last time it was measured
+    pub fn native_limit(mut self, bytes: usize) -> Self {
+        self.native_limit = Some(bytes);
+        self
+    }
+
+    pub(crate) fn alignment(&self) -> usize {
+        self.backing_align.unwrap_or_else(memory::page_size)
+    }
+
+    pub fn build(self) -> Result<Self, OptionsError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), OptionsError> {
+        if self.generations < 2 || self.generations > MAX_GENERATIONS {
+            return Err(OptionsError::InvalidGenerationCount(self.generations));
+        }
+
+        let heap_size = self
+            .tenured_sizes()
+            .iter()
+            .try_fold(self.young_heap_size, |total, &size| total.checked_add(size));
+        if self.young_heap_size == 0 || heap_size.is_none() {
+            return Err(OptionsError::InvalidHeapSize);
+        }
+
+        let old_heap_size = self.tenured_sizes[0];
+        if old_heap_size < self.young_heap_size && !self.allow_undersized_old_gen {
+            return Err(OptionsError::UndersizedOldGeneration {
+                young: self.young_heap_size,
+                old: old_heap_size,
+            });
+        }
+
+        if let Some(align) = self.backing_align {
+            if !align.is_power_of_two() || align < memory::MAX_ALIGN {
+                return Err(OptionsError::InvalidBackingAlign(align));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for BumpOptions {
+    fn default() -> Self {
+        Self {
+            young_heap_size: 1024 * 4,
+            tenured_sizes: [1024 * 4; MAX_GENERATIONS - 1],
+            generations: 2,
+            tenure_age: 1,
+            collect: true,
+            collection_policy: CollectionPolicy::Automatic,
+            memory_source: &GlobalSource,
+            event_history: 16,
+            old_pocket_sizes: PocketSizes::default(),
+            native_limit: None,
+            allow_undersized_old_gen: false,
+            pressure_thresholds: PressureThresholds::default(),
+            on_pressure: None,
+            backing_align: None,
+            on_promote: None,
+            on_relocate: None,
+            promotion_policy: &AgePolicy { age: 1 },
+            roots_shrink_factor: 4,
+            madvise_hugepage: false,
+            prefault: false,
+            young_reclaim: YoungReclaim::Memset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, SizeThresholdPolicy, Weak};
+    use core::{
+        mem::ManuallyDrop,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[test]
+    fn new() {
+        let _bump = BumpHeap::default();
+    }
+
+    #[test]
+    fn undersized_old_gen() {
+        let options = BumpOptions::new()
+            .young_heap_size(1024 * 4)
+            .old_heap_size(1024 * 2);
+
+        assert_eq!(
+            options.build().err(),
+            Some(OptionsError::UndersizedOldGeneration {
+                young: 1024 * 4,
+                old: 1024 * 2,
+            })
+        );
+        assert!(options.allow_undersized_old_gen(true).build().is_ok());
+        assert!(BumpOptions::default().build().is_ok());
+    }
+
+    #[test]
+    fn backing_align() {
+        for &align in &[4096, 64] {
+            let bump = BumpHeap::new(BumpOptions::new().backing_align(align));
+            assert_eq!(bump.young_start.as_usize() % align, 0);
+        }
+
+        assert_eq!(
+            BumpOptions::new().backing_align(48).build().err(),
+            Some(OptionsError::InvalidBackingAlign(48))
+        );
+        assert_eq!(
+            BumpOptions::new().backing_align(1).build().err(),
+            Some(OptionsError::InvalidBackingAlign(1))
+        );
+        assert_eq!(
+            BumpOptions::new().backing_align(8).build().err(),
+            Some(OptionsError::InvalidBackingAlign(8))
+        );
+        assert!(BumpOptions::new().backing_align(16).build().is_ok());
+    }
+
+    #[test]
+    fn try_new_failures() {
+        #[derive(Debug)]
+        struct Exhausted;
+
+        unsafe impl MemorySource for Exhausted {
+            unsafe fn alloc_zeroed(&self, _layout: Layout) -> *mut u8 {
+                ptr::null_mut()
+            }
+
+            unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+                unreachable!("Nothing was ever allocated")
+            }
+        }
+
+        let options = BumpOptions::new().memory_source(&Exhausted);
+        assert_eq!(
+            BumpHeap::try_new(options).err(),
+            Some(HeapCreateError::AllocationFailed {
+                size: options.heap_size()
+            })
+        );
+
+        let size = isize::MAX as usize;
+        let options = BumpOptions::new()
+            .young_heap_size(size)
+            .old_heap_size(0)
+            .allow_undersized_old_gen(true);
+        assert_eq!(
+            BumpHeap::try_new(options).err(),
+            Some(HeapCreateError::InvalidLayout {
+                size,
+                align: memory::page_size(),
+            })
+        );
+
+        // Generations that add up past the address space are rejected rather
+        // than wrapping around into a tiny region
+        let options = BumpOptions::new()
+            .young_heap_size(usize::MAX - 1024)
+            .tenured_generations(&[1024 * 4, usize::MAX / 2])
+            .allow_undersized_old_gen(true);
+        assert_eq!(
+            BumpHeap::try_new(options).err(),
+            Some(HeapCreateError::InvalidOptions(
+                OptionsError::InvalidHeapSize
+            ))
+        );
+        assert_eq!(
+            BumpHeap::try_new(BumpOptions::new().young_heap_size(0)).err(),
+            Some(HeapCreateError::InvalidOptions(
+                OptionsError::InvalidHeapSize
+            ))
+        );
+
+        assert_eq!(
+            BumpHeap::try_new(BumpOptions::new().old_heap_size(16)).err(),
+            Some(HeapCreateError::InvalidOptions(
+                OptionsError::UndersizedOldGeneration {
+                    young: 1024 * 4,
+                    old: 16
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn grow_young_generation() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let tenured: Vec<Rooted<usize>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        let garbage: Vec<Rooted<usize>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        bump.scavenge();
+        drop(garbage);
+        let young: Vec<Rooted<String>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked(i.to_string()) })
+            .collect();
+        let old_young_start = bump.young_start;
+        let old_tenured_bounds = bump.generations[0].bounds();
+
+        bump.grow_young_generation(1024 * 8).unwrap();
+        assert_ne!(bump.young_start, old_young_start);
+        assert_eq!(*bump.young_end - *bump.young_start, 1024 * 8);
+        assert_ne!(bump.generations[0].bounds(), old_tenured_bounds);
+
+        for (i, (tenured, young)) in tenured.iter().zip(young.iter()).enumerate() {
+            assert_eq!(**tenured, i);
+            assert_eq!(**young, i.to_string());
+            assert_eq!(
+                bump.locate(young.id()),
+                Some(unsafe { young.inner().data_ptr() })
+            );
+        }
+
+        // Both generations keep working in their new places
+        let more: Vec<Rooted<u64>> = (0..200)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        bump.scavenge();
+        bump.major();
+        assert!(more.iter().enumerate().all(|(i, more)| **more == i as u64));
+        assert!(tenured
+            .iter()
+            .enumerate()
+            .all(|(i, tenured)| **tenured == i));
+        assert_eq!(*young[19], "19");
+
+        let mut backing = vec![0u8; BumpOptions::new().heap_size()];
+        let mut fixed = unsafe {
+            BumpHeap::new_at(BumpOptions::new(), HeapPointer::from(backing.as_mut_ptr()))
+        };
+        assert_eq!(
+            fixed.grow_young_generation(1024 * 8),
+            Err(HeapCreateError::FixedRegion)
+        );
+    }
+
+    #[test]
+    fn allocate_no_drop() {
+        let mut bump = BumpHeap::default();
+
+        let _one: ManuallyDrop<Rooted<usize>> =
+            unsafe { ManuallyDrop::new(bump.alloc_unchecked(1)) };
+    }
+
+    #[test]
+    fn allocate_no_drop_deref() {
+        let mut bump = BumpHeap::default();
+
+        let one: ManuallyDrop<Rooted<usize>> =
+            unsafe { ManuallyDrop::new(bump.alloc_unchecked(1)) };
+        assert_eq!(**one, 1usize);
+    }
+
+    #[test]
+    #[cfg(all(feature = "trace-audit", debug_assertions))]
+    #[should_panic(expected = "Trace audit")]
+    fn trace_audit_catches_freed_edges() {
+        use crate::trace::Tracer;
+
+        struct Pair {
+            left: Rooted<usize>,
+            right: Rooted<usize>,
+        }
+
+        impl Trace for Pair {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.left.trace(tracer);
+                self.right.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let epoch = bump.new_epoch();
+        let left = unsafe { bump.alloc_unchecked(1) };
+        bump.new_epoch();
+        let right = unsafe { bump.alloc_unchecked(2) };
+        let pair = unsafe { bump.alloc_traced(Pair { left, right }) };
+
+        // `left` is freed out from under the pair that still reports it
+        unsafe { bump.free_region(epoch) };
+        bump.scavenge();
+        assert_eq!(*pair.right, 2);
+    }
+
+    #[test]
+    #[cfg(all(feature = "trace-audit", debug_assertions))]
+    #[should_panic(expected = "that its Trace impl didn't report")]
+    fn trace_audit_catches_missing_edges() {
+        use crate::trace::Tracer;
+
+        struct Pair {
+            left: Rooted<usize>,
+            right: Rooted<usize>,
+        }
+
+        impl Trace for Pair {
+            fn trace(&self, tracer: &mut Tracer) {
+                // Forgets about `right`
+                self.left.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let (left, right) = unsafe { (bump.alloc_unchecked(1), bump.alloc_unchecked(2)) };
+        let pair = unsafe { bump.alloc_traced(Pair { left, right }) };
+
+        bump.scavenge();
+        assert_eq!(*pair.right, 2);
+    }
+
+    #[test]
+    #[cfg(all(feature = "trace-audit", debug_assertions))]
+    fn trace_audit_accepts_complete_traces() {
+        use crate::trace::Tracer;
+
+        struct Pair {
+            left: Rooted<usize>,
+            right: Rooted<usize>,
+        }
+
+        impl Trace for Pair {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.left.trace(tracer);
+                self.right.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let (left, right) = unsafe { (bump.alloc_unchecked(1), bump.alloc_unchecked(2)) };
+        let pair = unsafe { bump.alloc_traced(Pair { left, right }) };
+
+        bump.scavenge();
+        bump.major();
+        assert_eq!((*pair.left, *pair.right), (1, 2));
+    }
+
+    #[test]
+    fn old_pocket_sizes() {
+        let mut bump =
+            BumpHeap::new(BumpOptions::new().old_pocket_sizes(PocketSizes::new(&[16, 48, 256])));
+        let pocket_of = |rooted: &Rooted<[u64; 5]>| match unsafe { rooted.inner().heap } {
+            ContainingHeap::Generation(_, pocket) => pocket,
+            ContainingHeap::Eden => unreachable!(),
+        };
+
+        let small: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let medium: Rooted<[u64; 5]> = unsafe { bump.alloc_unchecked([2; 5]) };
+        bump.scavenge();
+
+        // The default table would have put both of these into 32 byte pockets
+        assert_eq!(
+            unsafe { small.inner().heap },
+            ContainingHeap::Generation(1, 16)
+        );
+        assert_eq!(pocket_of(&medium), 48);
+        assert_eq!((*small, *medium), (1, [2; 5]));
+    }
+
+    #[test]
+    fn native_bytes() {
+        let mut bump = BumpHeap::default();
+
+        let buffers: Vec<Rooted<Vec<u8>>> = [0, 10, 1000, 4096]
+            .iter()
+            .map(|&len| unsafe { bump.alloc_native(Vec::with_capacity(len)) })
+            .collect();
+        let strings: Rooted<Vec<String>> = unsafe { bump.alloc_native(Vec::with_capacity(3)) };
+        // Objects without a `NativeSize` bound aren't counted
+        let _untracked: Handle<Vec<u8>> = bump.alloc(Vec::with_capacity(100));
+
+        let expected: usize = buffers
+            .iter()
+            .map(|buffer| buffer.capacity())
+            .sum::<usize>()
+            + strings.capacity() * mem::size_of::<String>();
+        assert_eq!(bump.total_native_bytes(), expected);
+
+        bump.for_each_mut(|buffer: &mut Vec<u8>| buffer.reserve(512));
+        bump.scavenge();
+        let grown: usize = buffers.iter().map(|buffer| buffer.capacity()).sum();
+        assert!(grown >= 4 * 512);
+        assert_eq!(
+            bump.total_native_bytes(),
+            grown + strings.capacity() * mem::size_of::<String>()
+        );
+
+        drop(buffers);
+        bump.scavenge();
+        bump.major();
+        assert_eq!(
+            bump.total_native_bytes(),
+            strings.capacity() * mem::size_of::<String>()
+        );
+    }
+
+    #[test]
+    fn native_limit() {
+        let mut bump = BumpHeap::new(BumpOptions::new().native_limit(4096));
+
+        for _ in 0..7 {
+            let _garbage: Rooted<Vec<u8>> = unsafe { bump.alloc_native(vec![0; 1024]) };
+        }
+        assert_eq!(bump.counters().scavenge_count, 1);
+        assert_eq!(bump.counters().major_count, 1);
+        assert_eq!(bump.total_native_bytes(), 0);
+
+        // Live native memory is still counted against the limit after a collection
+        let kept: Rooted<Vec<u8>> = unsafe { bump.alloc_native(vec![0; 4096]) };
+        assert_eq!(bump.counters().scavenge_count, 2);
+        let small: Rooted<Vec<u8>> = unsafe { bump.alloc_native(vec![0; 1]) };
+        assert_eq!(bump.counters().scavenge_count, 3);
+        assert_eq!(
+            bump.total_native_bytes(),
+            kept.capacity() + small.capacity()
+        );
+    }
+
+    #[test]
+    fn compaction_never_aliases() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let mut objects: Vec<Option<Rooted<[u64; 3]>>> = (0..60)
+            .map(|i| Some(unsafe { bump.alloc_unchecked([i; 3]) }))
+            .collect();
+        bump.scavenge();
+        for object in objects.iter_mut().step_by(2) {
+            *object = None;
+        }
+        bump.compact_now();
+
+        let mut live: Vec<Rooted<[u64; 3]>> = objects.into_iter().flatten().collect();
+        live.extend((60..90).map(|i| unsafe { bump.alloc_unchecked([i; 3]) }));
+        bump.scavenge();
+
+        let mut ranges: Vec<(HeapPointer, usize)> = live
+            .iter()
+            .map(|object| (bump.locate(object.id()).unwrap(), object.allocated_size()))
+            .collect();
+        ranges.sort();
+        for pair in ranges.windows(2) {
+            assert!(pair[0].0 + pair[0].1 <= pair[1].0, "{:?} overlaps", pair);
+        }
+        let expected: Vec<u64> = (1..60).step_by(2).chain(60..90).collect();
+        let values: Vec<[u64; 3]> = live.iter().map(|object| **object).collect();
+        assert_eq!(values, expected.iter().map(|&i| [i; 3]).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn alloc_zeroed_array() {
+        const LEN: usize = 1024 * 512;
+
+        let options = BumpOptions::new()
+            .young_heap_size(1024 * 1024)
+            .old_heap_size(1024 * 1024);
+        let mut bump = BumpHeap::new(options);
+
+        let _dirty: Handle<[u8; 64]> = bump.alloc([0xFF; 64]);
+        let zeroes: Rooted<[u8; LEN]> = unsafe { bump.alloc_zeroed_array() };
+        assert_eq!(
+            zeroes.allocated_size(),
+            mem::size_of::<HeapValue<[u8; LEN]>>()
+        );
+        assert!(zeroes.iter().all(|&byte| byte == 0));
+
+        bump.scavenge();
+        assert_eq!(zeroes.location(), Location::Tenured(1));
+        assert!(zeroes.iter().all(|&byte| byte == 0));
+
+        let words: Rooted<[u64; 16]> = unsafe { bump.alloc_zeroed_array() };
+        assert_eq!(*words, [0; 16]);
+    }
+
+    #[test]
+    fn hibernate() {
+        static RELEASED: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct CountingSource;
+
+        unsafe impl MemorySource for CountingSource {
+            unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+                GlobalSource.alloc_zeroed(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                RELEASED.fetch_add(1, Ordering::Relaxed);
+                GlobalSource.dealloc(ptr, layout)
+            }
+        }
+
+        let mut bump = BumpHeap::new(BumpOptions::new().memory_source(&CountingSource));
+
+        let live: Rooted<u64> = unsafe { bump.alloc_unchecked(1) };
+        let dead: Rooted<u64> = unsafe { bump.alloc_unchecked(2) };
+        drop(dead);
+        assert!(!bump.hibernate());
+
+        bump.scavenge();
+        let weak = Weak::new(&live);
+        drop(live);
+        assert!(bump.hibernate());
+        assert!(bump.is_hibernating());
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 1);
+        assert!(weak.is_cleared());
+        assert_eq!(
+            bump.free_blocks().map(|block| block.size).sum::<usize>(),
+            1024 * 4
+        );
+
+        // Collecting a hibernating heap has nothing to do
+        bump.scavenge();
+        bump.major();
+
+        let woken: Rooted<u64> = unsafe { bump.alloc_unchecked(3) };
+        assert!(!bump.is_hibernating());
+        bump.scavenge();
+        bump.major();
+        assert_eq!(*woken, 3);
+        assert_eq!(bump.live_count(), 1);
+
+        drop(woken);
+        drop(bump);
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn madvise_hugepage() {
+        let options = BumpOptions::new()
+            .young_heap_size(memory::HUGE_PAGE_SIZE * 2)
+            .old_heap_size(memory::HUGE_PAGE_SIZE * 2)
+            .backing_align(memory::HUGE_PAGE_SIZE)
+            .madvise_hugepage(true);
+        let mut bump = BumpHeap::new(options);
+        let (start, _) = bump.bounds();
+        assert_eq!(start.as_usize() % memory::HUGE_PAGE_SIZE, 0);
+
+        let objects: Vec<Rooted<[u64; 16]>> = (0..1000)
+            .map(|i| unsafe { bump.alloc_unchecked([i; 16]) })
+            .collect();
+        bump.scavenge();
+        bump.major();
+        assert!(objects
+            .iter()
+            .enumerate()
+            .all(|(i, object)| object[0] == i as u64));
+
+        // Whether the kernel actually used huge pages depends on how it's configured,
+        // so this only checks that the heap's mapping is where it should be
+        if let Ok(smaps) = std::fs::read_to_string("/proc/self/smaps") {
+            let mapped = smaps.lines().any(|line| {
+                let range = line.split(' ').next().unwrap_or_default();
+                match range.split_once('-').map(|(low, high)| {
+                    (
+                        usize::from_str_radix(low, 16),
+                        usize::from_str_radix(high, 16),
+                    )
+                }) {
+                    Some((Ok(low), Ok(high))) => (low..high).contains(&start.as_usize()),
+                    _ => false,
+                }
+            });
+            assert!(mapped);
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn madv_free_scavenges() {
+        let options = BumpOptions::new()
+            .young_heap_size(1024 * 256)
+            .old_heap_size(1024 * 256)
+            .young_reclaim(YoungReclaim::MadvFree);
+        let mut bump = BumpHeap::new(options);
+
+        for round in 0..4u64 {
+            let objects: Vec<Rooted<[u64; 16]>> = (0..100)
+                .map(|i| unsafe { bump.alloc_unchecked([round * 100 + i; 16]) })
+                .collect();
+            let zeroes: Rooted<[u64; 64]> = unsafe { bump.alloc_zeroed_array() };
+
+            assert!(objects
+                .iter()
+                .enumerate()
+                .all(|(i, object)| object.iter().all(|&word| word == round * 100 + i as u64)));
+            assert!(zeroes.iter().all(|&word| word == 0));
+
+            drop((objects, zeroes));
+            bump.scavenge();
+            assert_eq!(bump.counters().bytes_promoted, 0);
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn prefault() {
+        // The global allocator zeroes aligned allocations by hand, which already
+        // commits them, so this maps its memory lazily instead
+        #[derive(Debug)]
+        struct Mapped;
+
+        unsafe impl MemorySource for Mapped {
+            unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+                assert!(layout.align() <= memory::page_size());
+                let ptr = libc::mmap(
+                    ptr::null_mut(),
+                    layout.size(),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                );
+                assert_ne!(ptr, libc::MAP_FAILED);
+
+                ptr as *mut u8
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                libc::munmap(ptr as *mut libc::c_void, layout.size());
+            }
+        }
+
+        fn minor_faults() -> i64 {
+            let mut usage = mem::MaybeUninit::<libc::rusage>::uninit();
+            assert_eq!(
+                unsafe { libc::getrusage(libc::RUSAGE_THREAD, usage.as_mut_ptr()) },
+                0
+            );
+
+            unsafe { usage.assume_init() }.ru_minflt
+        }
+
+        const YOUNG: usize = 1024 * 1024;
+        let options = BumpOptions::new()
+            .young_heap_size(YOUNG)
+            .old_heap_size(YOUNG * 4)
+            .memory_source(&Mapped);
+
+        let burst = |bump: &mut BumpHeap| {
+            let mut objects: Vec<Rooted<[u64; 511]>> = Vec::with_capacity(YOUNG / 4096);
+            let before = minor_faults();
+            for i in 0..YOUNG / 4096 - 1 {
+                objects.push(unsafe { bump.alloc_unchecked([i as u64; 511]) });
+            }
+            let faults = minor_faults() - before;
+
+            bump.scavenge();
+            assert!(objects
+                .iter()
+                .enumerate()
+                .all(|(i, object)| object.iter().all(|&word| word == i as u64)));
+
+            faults
+        };
+
+        // Page faults are up to the kernel, so this only checks that most of the
+        // young generation's pages were already there
+        let pages = (YOUNG / memory::page_size()) as i64;
+        let mut bump = BumpHeap::new(options);
+        bump.prefault();
+        let faults = burst(&mut bump);
+        assert!(faults < pages / 2, "{} faults over {} pages", faults, pages);
+
+        let faults = burst(&mut BumpHeap::new(options.prefault(true)));
+        assert!(faults < pages / 2, "{} faults over {} pages", faults, pages);
+    }
+
+    #[test]
+    fn collect_mark_sweep_only() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let mut objects: Vec<Option<Rooted<u64>>> = (0..20)
+            .map(|i| Some(unsafe { bump.alloc_unchecked(i) }))
+            .collect();
+        let children: Vec<Rooted<u64>> =
+            (0..3).map(|i| unsafe { bump.alloc_unchecked(i) }).collect();
+        let parent = unsafe { bump.alloc_traced(children) };
+        bump.scavenge();
+
+        let ids: Vec<_> = objects.iter().flatten().map(Rooted::id).collect();
+        let addresses: Vec<_> = ids.iter().map(|&id| bump.locate(id)).collect();
+        let child_addresses: Vec<_> = parent.iter().map(|child| bump.locate(child.id())).collect();
+        for object in objects.iter_mut().step_by(2) {
+            *object = None;
+        }
+
+        let before = bump.counters().bytes_reclaimed;
+        bump.collect_mark_sweep_only();
+        assert_eq!(
+            bump.counters().bytes_reclaimed - before,
+            10 * mem::size_of::<HeapValue<u64>>()
+        );
+        assert_eq!(bump.counters().compaction_count, 0);
+
+        for (i, (&id, &address)) in ids.iter().zip(&addresses).enumerate() {
+            if i % 2 == 0 {
+                assert_eq!(bump.locate(id), None);
+            } else {
+                assert_eq!(bump.locate(id), address);
+                assert_eq!(objects[i].as_deref(), Some(&(i as u64)));
+            }
+        }
+        assert_eq!(
+            parent
+                .iter()
+                .map(|child| bump.locate(child.id()))
+                .collect::<Vec<_>>(),
+            child_addresses
+        );
+    }
+
+    #[test]
+    // The trace audit catches `Forgetful`, which is what it's for
+    #[cfg(not(all(feature = "trace-audit", debug_assertions)))]
+    fn collect_mark_sweep_only_follows_edges() {
+        struct Parent(Rooted<u64>);
+
+        impl Trace for Parent {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.0.trace(tracer);
+            }
+        }
+
+        // Leaves its child out, so the child looks like it's held from outside
+        struct Forgetful(Rooted<u64>);
+
+        impl Trace for Forgetful {
+            fn trace(&self, _tracer: &mut Tracer) {}
+        }
+
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let child = unsafe { bump.alloc_unchecked(1) };
+        let parent = unsafe { bump.alloc_traced(Parent(child)) };
+        let forgotten = unsafe { bump.alloc_unchecked(2) };
+        let forgetful = unsafe { bump.alloc_traced(Forgetful(forgotten)) };
+        bump.scavenge();
+
+        let child = parent.0.id();
+        let forgotten = forgetful.0.id();
+        bump.collect_mark_sweep_only();
+        assert!(bump.locate(child).is_some());
+        assert!(bump.locate(forgotten).is_some());
+
+        let ids = [parent.id(), forgetful.id()];
+        drop(parent);
+        drop(forgetful);
+
+        let before = bump.counters().bytes_reclaimed;
+        bump.collect_mark_sweep_only();
+        assert!(ids.iter().all(|&id| bump.locate(id).is_none()));
+        assert_eq!(bump.locate(child), None);
+        assert!(bump.locate(forgotten).is_some());
+        assert_eq!(
+            bump.counters().bytes_reclaimed - before,
+            mem::size_of::<HeapValue<u64>>()
+                + mem::size_of::<HeapValue<Parent>>()
+                + mem::size_of::<HeapValue<Forgetful>>()
+        );
+    }
+
+    #[test]
+    fn collect_mark_sweep_only_drops_dead_children() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        struct Parent(Rooted<Counted>, Counted);
+
+        impl Trace for Parent {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.0.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+        let drops = Rc::new(Cell::new(0));
+
+        let child = unsafe { bump.alloc_unchecked(Counted(Rc::clone(&drops))) };
+        let parent = unsafe { bump.alloc_traced(Parent(child, Counted(Rc::new(Cell::new(0))))) };
+        let parent_drops = Rc::clone(&(parent.1).0);
+        bump.scavenge();
+
+        bump.collect_mark_sweep_only();
+        assert_eq!(drops.get(), 0);
+
+        // The child is still rooted by its handle in the parent, the parent isn't
+        let child = parent.0.id();
+        drop(parent);
+        bump.collect_mark_sweep_only();
+        assert_eq!(drops.get(), 1);
+        assert_eq!(parent_drops.get(), 0);
+        assert_eq!(bump.locate(child), None);
+        assert_eq!(bump.live_count(), 0);
+    }
+
+    #[test]
+    fn collect_mark_sweep_only_unroots_orphaned_young_children() {
+        struct Parent(Rooted<u64>);
+
+        impl Trace for Parent {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.0.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let first = unsafe { bump.alloc_unchecked(0) };
+        let mut parent = unsafe { bump.alloc_traced(Parent(first)) };
+        bump.scavenge();
+
+        // The young child's handle is left in the dead parent, which is never dropped
+        let young = unsafe { bump.alloc_unchecked(1) };
+        let child = young.id();
+        parent.map_in_place(|_| Parent(young));
+        drop(parent);
+
+        bump.collect_mark_sweep_only();
+        assert!(bump.locate(child).is_some());
+        bump.scavenge();
+        assert_eq!(bump.locate(child), None);
+    }
+
+    #[test]
+    fn major_skips_young_objects() {
+        let mut bump = BumpHeap::default();
+
+        let live: Vec<Handle<u64>> = (0..10).map(|i| bump.alloc(i)).collect();
+        let dead: Vec<Handle<u64>> = (0..10).map(|i| bump.alloc(i)).collect();
+        let addresses: Vec<_> = live.iter().map(|object| bump.locate(object.id())).collect();
+        drop(dead);
+
+        bump.major();
+        assert_eq!(bump.counters().major_count, 1);
+        assert_eq!(bump.counters().bytes_reclaimed, 0);
+        assert_eq!(bump.young_roots.len(), 20);
+        assert!(bump.roots.iter().all(Vec::is_empty));
+        assert_eq!(
+            live.iter()
+                .map(|object| bump.locate(object.id()))
+                .collect::<Vec<_>>(),
+            addresses
+        );
+        assert!(live
+            .iter()
+            .all(|object| object.location() == Location::Eden));
+    }
+
+    #[test]
+    fn trim_metadata() {
+        let options = BumpOptions::new().old_heap_size(1024 * 1024);
+        let mut bump = BumpHeap::new(options);
+
+        let burst: Vec<Handle<u64>> = (0..5000).map(|i| bump.alloc(i)).collect();
+        bump.scavenge();
+        assert!(bump.roots[0].capacity() >= 5000);
+
+        drop(burst);
+        let survivor: Handle<u64> = bump.alloc(1);
+        bump.scavenge();
+        bump.major();
+        assert_eq!(bump.roots[0].len(), 1);
+        assert_eq!(bump.roots[0].capacity(), MIN_ROOTS_CAPACITY);
+        assert!(bump.young_roots.capacity() <= MIN_ROOTS_CAPACITY);
+        assert_eq!(*survivor.get(&bump), 1);
+
+        // Root lists within the factor of their length are left alone
+        let lenient = options.roots_shrink_factor(usize::MAX);
+        let mut bump = BumpHeap::new(lenient);
+        let burst: Vec<Handle<u64>> = (0..5000).map(|i| bump.alloc(i)).collect();
+        bump.scavenge();
+        drop(burst);
+        bump.major();
+        bump.major();
+        assert!(bump.young_roots.capacity() > MIN_ROOTS_CAPACITY);
+    }
+
+    #[test]
+    fn clear() {
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let bounds = bump.bounds();
+        let first: Rooted<Counted> = unsafe { bump.alloc_unchecked(Counted) };
+        let first_address = bump.locate(first.id()).unwrap();
+
+        let tenured: Rooted<Counted> = unsafe { bump.alloc_unchecked(Counted) };
+        bump.scavenge();
+        let young: Rooted<u64> = unsafe { bump.alloc_unchecked(1) };
+        let dead: Rooted<Counted> = unsafe { bump.alloc_unchecked(Counted) };
+        drop(dead);
+
+        unsafe { bump.clear() };
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+        assert!(first.try_deref().is_none());
+        assert!(tenured.try_deref().is_none());
+        assert!(young.try_deref().is_none());
+        assert_eq!(bump.live_count(), 0);
+        assert_eq!(bump.memory_pressure(), Pressure::Low);
+        assert_eq!(
+            bump.free_blocks().map(|block| block.size).sum::<usize>(),
+            1024 * 4
+        );
+
+        // The heap starts over in the same region
+        assert_eq!(bump.bounds(), bounds);
+        let again: Rooted<u64> = unsafe { bump.alloc_unchecked(2) };
+        assert_eq!(bump.locate(again.id()), Some(first_address));
+        assert!(bump.locate(first.id()).is_none());
+        bump.scavenge();
+        bump.major();
+        assert_eq!(*again, 2);
+    }
+
+    #[test]
+    fn bounds() {
+        let mut bump = BumpHeap::new(BumpOptions::new().tenured_generations(&[1024 * 4, 1024 * 8]));
+        let (young_start, young_end) = bump.young_bounds();
+        assert_eq!(*young_end - *young_start, 1024 * 4);
+
+        let handle: Handle<u64> = bump.alloc(1);
+        let ptr = bump.locate(handle.id()).unwrap();
+        assert_eq!(ptr, young_start);
+        assert!(ptr + mem::size_of::<HeapValue<u64>>() <= young_end);
+
+        let (first_start, first_end) = bump.tenured_bounds(1).unwrap();
+        let (second_start, second_end) = bump.tenured_bounds(2).unwrap();
+        assert!(young_end <= first_start && first_end <= second_start);
+        assert_eq!(*second_end - *second_start, 1024 * 8);
+        assert_eq!(bump.tenured_bounds(0), None);
+        assert_eq!(bump.tenured_bounds(3), None);
+        assert_eq!(bump.bounds(), (young_start, second_end));
+
+        bump.scavenge();
+        let ptr = bump.locate(handle.id()).unwrap();
+        assert!(first_start <= ptr && ptr < first_end);
+    }
+
+    #[test]
+    fn scavenge_steps() {
+        let run = |budget: Option<usize>| {
+            let mut bump = BumpHeap::default();
+
+            let mut objects: Vec<Option<Rooted<u64>>> = (0..30)
+                .map(|i| Some(unsafe { bump.alloc_unchecked(i) }))
+                .collect();
+            for object in objects.iter_mut().step_by(4) {
+                *object = None;
+            }
+
+            let (outcome, steps) = match budget {
+                Some(budget) => {
+                    let mut steps = 1;
+                    loop {
+                        if let Some(outcome) = bump.scavenge_step(budget) {
+                            break (outcome, steps);
+                        }
+                        steps += 1;
+                    }
+                }
+                None => (bump.scavenge(), 1),
+            };
+
+            // Each run has its own region, so only the offsets into it can match
+            let (start, _) = bump.bounds();
+            let addresses: Vec<_> = objects
+                .iter()
+                .flatten()
+                .map(|object| *bump.locate(object.id()).unwrap() - *start)
+                .collect();
+            assert_eq!(bump.counters().scavenge_count, 1);
+
+            (outcome, steps, addresses, objects)
+        };
+
+        let (full, _, full_addresses, _objects) = run(None);
+        let (stepped, steps, stepped_addresses, objects) = run(Some(5));
+        assert_eq!(stepped, full);
+        assert_eq!(steps, 5);
+        assert_eq!(stepped_addresses, full_addresses);
+        assert!(objects
+            .iter()
+            .flatten()
+            .all(|object| object.location() == Location::Tenured(1)));
+    }
+
+    #[test]
+    fn allocating_finishes_scavenge_steps() {
+        let mut bump = BumpHeap::default();
+
+        let objects: Vec<Handle<u64>> = (0..4).map(|i| bump.alloc(i)).collect();
+        assert_eq!(bump.scavenge_step(2), None);
+        assert_eq!(objects[1].location(), Location::Tenured(1));
+        assert_eq!(objects[2].location(), Location::Eden);
+
+        let young: Handle<u64> = bump.alloc(4);
+        assert_eq!(young.location(), Location::Eden);
+        assert!(objects
+            .iter()
+            .all(|object| object.location() == Location::Tenured(1)));
+        assert_eq!(bump.counters().scavenge_count, 1);
+
+        bump.scavenge();
+        assert_eq!(*young.get(&bump), 4);
+        assert_eq!(bump.counters().scavenge_count, 2);
+    }
+
+    #[test]
+    fn zero_budget_scavenge_steps() {
+        let mut bump = BumpHeap::default();
+
+        let objects: Vec<Handle<u64>> = (0..3).map(|i| bump.alloc(i)).collect();
+        assert_eq!(bump.scavenge_step(0), None);
+        assert_eq!(bump.scavenge_step(0), None);
+        assert_eq!(
+            bump.scavenge_step(0).map(|outcome| outcome.survived),
+            Some(3)
+        );
+        assert!(objects
+            .iter()
+            .all(|object| object.location() == Location::Tenured(1)));
+    }
+
+    #[test]
+    fn free_region_finishes_scavenge_steps() {
+        static POLICY: AgePolicy = AgePolicy { age: u8::MAX };
+        let mut bump = BumpHeap::new(BumpOptions::new().promotion_policy(&POLICY));
+
+        let kept: Rooted<u64> = unsafe { bump.alloc_unchecked(0) };
+        let epoch = bump.new_epoch();
+        let freed: Vec<Rooted<u64>> = (1..4).map(|i| unsafe { bump.alloc_unchecked(i) }).collect();
+
+        // Keeps `kept` and the first freed object before pausing
+        assert_eq!(bump.scavenge_step(2), None);
+        assert_eq!(unsafe { bump.free_region(epoch) }, 3);
+        assert_eq!(bump.counters().scavenge_count, 1);
+        assert!(freed.iter().all(|freed| freed.try_deref().is_none()));
+
+        let outcome = bump.scavenge();
+        assert_eq!(outcome.kept, 1);
+        assert_eq!(*kept, 0);
+    }
+
+    #[test]
+    fn scavenge_outcome() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 4));
+        let size = mem::size_of::<HeapValue<usize>>();
+
+        let survivors: Vec<Rooted<usize>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        let outcome = bump.scavenge();
+        assert_eq!(
+            outcome,
+            ScavengeOutcome {
+                survived: survivors.len(),
+                kept: 0,
+                promoted: survivors.len() * size,
+                young_freed_bytes: 0,
+                triggered_major: false,
+            }
+        );
+
+        drop(survivors);
+        for _ in 0..10 {
+            drop(unsafe { bump.alloc_unchecked(0usize) });
+        }
+        let outcome = bump.scavenge();
+        assert_eq!(outcome.survived, 0);
+        assert_eq!(outcome.young_freed_bytes, 10 * size);
+        assert!(!outcome.triggered_major);
+
+        // The tenured generation fills up with the dead survivors, so making room
+        // for these takes a major collection
+        let mut survivors: Vec<Rooted<usize>> = Vec::new();
+        let outcome = loop {
+            survivors.extend((0..20).map(|i| unsafe { bump.alloc_unchecked(i) }));
+            let outcome = bump.scavenge();
+            if outcome.triggered_major {
+                break outcome;
+            }
+
+            survivors.clear();
+        };
+        assert_eq!(outcome.survived, 20);
+        assert!(survivors
+            .iter()
+            .enumerate()
+            .all(|(i, survivor)| **survivor == i));
+    }
+
+    #[test]
+    fn free_region() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted(usize);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let first_epoch = bump.current_epoch();
+
+        let tenured: Vec<Rooted<Counted>> = (0..5)
+            .map(|i| unsafe { bump.alloc_unchecked(Counted(i)) })
+            .collect();
+        bump.scavenge();
+        let young: Vec<Rooted<Counted>> = (5..10)
+            .map(|i| unsafe { bump.alloc_unchecked(Counted(i)) })
+            .collect();
+
+        let second_epoch = bump.new_epoch();
+        assert_ne!(first_epoch, second_epoch);
+        let kept: Vec<Rooted<Counted>> = (0..10)
+            .map(|i| unsafe { bump.alloc_unchecked(Counted(i)) })
+            .collect();
+        bump.scavenge();
+        let used = bump.tenured_used();
+
+        assert_eq!(unsafe { bump.free_region(first_epoch) }, 10);
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 10);
+        assert!(bump.tenured_used() < used);
+        for rooted in tenured.iter().chain(&young) {
+            assert_eq!(bump.locate(rooted.id()), None);
+        }
+
+        // The second epoch is untouched and keeps working through collections
+        bump.major();
+        bump.scavenge();
+        assert!(kept.iter().enumerate().all(|(i, kept)| kept.0 == i));
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 10);
+
+        // Handles to freed objects can still be dropped
+        drop(tenured);
+        drop(young);
+    }
+
+    #[test]
+    fn free_region_skips_unrooted_destructors() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let drops = Rc::new(Cell::new(0));
+        let epoch = bump.new_epoch();
+
+        let tenured = bump.alloc(Counted(Rc::clone(&drops)));
+        bump.scavenge();
+        drop(tenured);
+        drop(bump.alloc(Counted(Rc::clone(&drops))));
+        let rooted = bump.alloc(Counted(Rc::clone(&drops)));
+
+        // Both unrooted objects are freed, but only the rooted one is dropped
+        assert_eq!(unsafe { bump.free_region(epoch) }, 3);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(bump.live_count(), 0);
+        drop(rooted);
+    }
+
+    #[test]
+    fn free_region_cycles() {
+        use crate::GcCell;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+        struct Node {
+            value: usize,
+            peer: GcCell<Option<Rooted<Node>>>,
+        }
+
+        impl Drop for Node {
+            fn drop(&mut self) {
+                if let Some(peer) = &*self.peer.borrow() {
+                    SEEN.fetch_add(peer.value, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let epoch = bump.new_epoch();
+
+        let first = unsafe {
+            bump.alloc_unchecked(Node {
+                value: 1,
+                peer: GcCell::new(None),
+            })
+        };
+        let second = unsafe {
+            bump.alloc_unchecked(Node {
+                value: 2,
+                peer: GcCell::new(Some(first)),
+            })
+        };
+
+        // Close the cycle, handing the only handle to each node to its peer
+        let first_peer: *const GcCell<Option<Rooted<Node>>> =
+            &second.peer.borrow().as_ref().unwrap().peer;
+        unsafe { *(*first_peer).borrow_mut() = Some(second) };
+
+        assert_eq!(unsafe { bump.free_region(epoch) }, 2);
+        assert_eq!(SEEN.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn recent_events() {
+        let mut bump = BumpHeap::new(BumpOptions::new().event_history(3));
+        let size = mem::size_of::<HeapValue<usize>>();
+
+        for dead in 0..5 {
+            for i in 0..dead {
+                drop(bump.alloc::<usize>(i));
+            }
+            bump.scavenge();
+        }
+        bump.major();
+
+        let events = bump.recent_events();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.kind, event.bytes_reclaimed))
+                .collect::<Vec<_>>(),
+            vec![
+                (PauseKind::Scavenge, 3 * size),
+                (PauseKind::Scavenge, 4 * size),
+                (PauseKind::Major, 0),
+            ],
+        );
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        let bump = BumpHeap::new(BumpOptions::new().event_history(0));
+        assert!(bump.recent_events().is_empty());
+    }
+
+    #[test]
+    fn unmanaged_handles() {
+        let mut bump = BumpHeap::default();
+
+        let unmanaged: Rooted<usize> = unsafe { bump.alloc_unmanaged(1) };
+        let id = unmanaged.id();
+        drop(unmanaged);
+
+        bump.scavenge();
+        assert!(bump.locate(id).is_some());
+
+        let unmanaged: Rooted<usize> = unsafe { bump.alloc_unmanaged(2) };
+        let id = unmanaged.id();
+        unmanaged.unroot();
+
+        bump.scavenge();
+        assert!(bump.locate(id).is_none());
+    }
+
+    #[test]
+    fn allocate() {
+        let mut bump = BumpHeap::default();
+
+        let one_hundred: Handle<usize> = bump.alloc(100);
+        assert_eq!(*one_hundred.get(&bump), 100usize);
+    }
+
+    #[test]
+    fn trigger_scavenge() {
+        let mut bump = BumpHeap::default();
+
+        let i: usize = 1000;
+        let handle: Handle<usize> = bump.alloc(i);
+        assert_eq!(*handle.get(&bump), i);
+
+        bump.scavenge();
+        assert_eq!(*handle.get(&bump), i);
+    }
+
+    #[test]
+    fn counters() {
+        let mut bump = BumpHeap::default();
+
+        let kept: Handle<usize> = bump.alloc(1);
+        let dropped: Handle<usize> = bump.alloc(2);
+        drop(dropped);
+
+        assert_eq!(bump.counters().total_allocations, 2);
+        assert_eq!(
+            bump.counters().bytes_allocated,
+            mem::size_of::<HeapValue<usize>>() * 2
+        );
+
+        bump.scavenge();
+        assert_eq!(bump.counters().scavenge_count, 1);
+        assert_eq!(
+            bump.counters().bytes_promoted,
+            mem::size_of::<HeapValue<usize>>()
+        );
+        assert_eq!(
+            bump.counters().bytes_reclaimed,
+            mem::size_of::<HeapValue<usize>>()
+        );
+        assert_eq!(*kept.get(&bump), 1);
+
+        let snapshot = *bump.counters();
+        bump.reset_counters();
+        assert_eq!(*bump.counters(), GcCounters::default());
+        assert_ne!(snapshot, *bump.counters());
+    }
+
+    #[test]
+    fn id_is_stable_across_scavenge() {
+        let mut bump = BumpHeap::default();
+
+        let handle: Handle<usize> = bump.alloc(10);
+        let id = handle.id();
+        let before = bump.locate(id).unwrap();
+
+        bump.scavenge();
+
+        assert_eq!(handle.id(), id);
+        assert_ne!(bump.locate(id).unwrap(), before);
+        assert_eq!(*handle.get(&bump), 10);
+    }
+
+    #[test]
+    fn reused_ids_are_generation_tagged() {
+        let mut bump = BumpHeap::default();
+
+        let dead: Handle<usize> = bump.alloc(1);
+        let dead_id = dead.id();
+        drop(dead);
+        bump.scavenge();
+
+        assert_eq!(bump.locate(dead_id), None);
+
+        let live: Handle<usize> = bump.alloc(2);
+        assert_eq!(live.id().index(), dead_id.index());
+        assert_ne!(live.id(), dead_id);
+        assert_eq!(bump.locate(dead_id), None);
+        assert!(bump.locate(live.id()).is_some());
+    }
+
+    #[test]
+    fn for_each_mut() {
+        let mut bump = BumpHeap::default();
+
+        let numbers: Vec<Handle<u32>> = (0..10).map(|i| bump.alloc(i)).collect();
+        let other: Handle<u64> = bump.alloc(100);
+        let dead: Handle<u32> = bump.alloc(1000);
+        drop(dead);
+
+        let mut visited = 0;
+        bump.for_each_mut::<u32>(|number| {
+            *number += 1;
+            visited += 1;
+        });
+
+        assert_eq!(visited, 10);
+        for (i, number) in numbers.iter().enumerate() {
+            assert_eq!(*number.get(&bump), i as u32 + 1);
+        }
+        assert_eq!(*other.get(&bump), 100);
+    }
+
+    #[test]
+    fn pressure_escalates() {
+        use std::sync::Mutex;
+
+        static LEVELS: Mutex<Vec<Pressure>> = Mutex::new(Vec::new());
+
+        let mut bump = BumpHeap::new(BumpOptions::new().on_pressure(|pressure| {
+            LEVELS.lock().unwrap().push(pressure);
+        }));
+        assert_eq!(bump.memory_pressure(), Pressure::Low);
+
+        let mut survivors = Vec::new();
+        while bump.memory_pressure() != Pressure::Critical {
+            survivors.push(bump.alloc::<usize>(survivors.len()));
+            bump.scavenge();
+        }
+
+        let levels = LEVELS.lock().unwrap();
+        assert!(levels.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(levels.last(), Some(&Pressure::Critical));
+        assert!(levels.contains(&Pressure::High));
+    }
+
+    #[test]
+    fn scavenge_skips_tenured_roots() {
+        let mut bump = BumpHeap::default();
+
+        let tenured: Handle<usize> = bump.alloc(1);
+        bump.scavenge();
+        let tenured_at = bump.locate(tenured.id()).unwrap();
+        let used = bump.generations[0].used();
+
+        bump.scavenge();
+        assert_eq!(bump.locate(tenured.id()), Some(tenured_at));
+        assert_eq!(bump.generations[0].used(), used);
+        assert_eq!(*tenured.get(&bump), 1);
+    }
+
+    #[test]
+    fn promote_events() {
+        use std::sync::Mutex;
+
+        static EVENTS: Mutex<Vec<PromoteEvent>> = Mutex::new(Vec::new());
+
+        let mut bump = BumpHeap::new(BumpOptions::new().on_promote(|event| {
+            EVENTS.lock().unwrap().push(event);
+        }));
+
+        let survivors: Vec<Handle<usize>> = (0..3).map(|i| bump.alloc(i)).collect();
+        let dead: Handle<usize> = bump.alloc(3);
+        drop(dead);
+
+        bump.scavenge();
+
+        let events = EVENTS.lock().unwrap();
+        let size = mem::size_of::<HeapValue<usize>>();
+        assert_eq!(events.len(), survivors.len());
+        for (event, survivor) in events.iter().zip(&survivors) {
+            assert_eq!(event.id, survivor.id());
+            assert_eq!(event.size, size);
+            assert_eq!(event.pocket_size, PocketSizes::default().as_slice()[1]);
+            assert_eq!(event.generation, 1);
+        }
+    }
+
+    #[test]
+    fn objects_are_aligned() {
+        let mut bump = BumpHeap::default();
+
+        let byte: Handle<u8> = bump.alloc(1);
+        let long: Handle<u64> = bump.alloc(2);
+        let wide: Handle<u128> = bump.alloc(3);
+
+        for _ in 0..2 {
+            assert_eq!(bump.locate(long.id()).unwrap().as_usize() % 8, 0);
+            assert_eq!(bump.locate(wide.id()).unwrap().as_usize() % 16, 0);
+            assert_eq!(
+                (*byte.get(&bump), *long.get(&bump), *wide.get(&bump)),
+                (1, 2, 3)
+            );
+
+            bump.scavenge();
+        }
+    }
+
+    #[test]
+    fn handle_outlives_heap() {
+        let mut bump = BumpHeap::default();
+
+        let handle: Handle<usize> = bump.alloc(1);
+        drop(bump);
+        drop(handle);
+    }
+
+    #[test]
+    #[should_panic(expected = "heap was dropped")]
+    fn deref_after_heap_drop() {
+        let mut bump = BumpHeap::default();
+
+        let rooted: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        drop(bump);
+        let _ = *rooted;
+    }
+
+    #[test]
+    fn compact_now() {
+        let mut bump = BumpHeap::default();
+
+        let mut objects: Vec<(Rooted<usize>, usize)> = (0..50)
+            .map(|i| (unsafe { bump.alloc_unchecked(i) }, i))
+            .collect();
+        bump.scavenge();
+
+        // Free every third object to fragment the intermediate heap without
+        // crossing the automatic compaction threshold
+        let mut i = 0;
+        objects.retain(|_| {
+            i += 1;
+            i % 3 != 0
+        });
+        bump.major();
+        assert!(bump.fragmentation() > 0.25);
+
+        let stats = bump.compact_now();
+        assert!(stats.objects_moved > 0);
+        assert!(stats.bytes_reclaimed > 0);
+        assert!(bump.fragmentation() < 0.01);
+
+        for (rooted, value) in &objects {
+            assert_eq!(**rooted, *value);
+        }
+    }
+
+    #[test]
+    fn relocate_events() {
+        use std::{collections::HashMap, sync::Mutex};
+
+        static MOVES: Mutex<Vec<RelocateEvent>> = Mutex::new(Vec::new());
+
+        let mut bump = BumpHeap::new(BumpOptions::new().on_relocate(|event| {
+            MOVES.lock().unwrap().push(event);
+        }));
+
+        let mut objects: Vec<Handle<usize>> = (0..30).map(|i| bump.alloc(i)).collect();
+        bump.scavenge();
+        objects.retain(|object| *object.get(&bump) % 2 == 0);
+
+        // Something outside of the heap that caches raw addresses
+        let mut cache: HashMap<HeapPointer, usize> = objects
+            .iter()
+            .map(|object| (bump.locate(object.id()).unwrap(), *object.get(&bump)))
+            .collect();
+
+        let stats = bump.compact_now();
+        let moves = MOVES.lock().unwrap();
+        assert_eq!(moves.len(), stats.objects_moved);
+        for event in moves.iter() {
+            assert!(event.to < event.from);
+            let value = cache.remove(&event.from).unwrap();
+            cache.insert(event.to, value);
+        }
+
+        for object in &objects {
+            let address = bump.locate(object.id()).unwrap();
+            assert_eq!(cache[&address], *object.get(&bump));
+        }
+    }
+
+    #[test]
+    fn pinned_objects_never_move() {
+        struct SelfReferential {
+            value: usize,
+            me: *const SelfReferential,
+        }
+
+        let mut bump = BumpHeap::default();
+
+        let before: Vec<Handle<usize>> = (0..10).map(|i| bump.alloc(i)).collect();
+        bump.scavenge();
+
+        let mut pinned = unsafe {
+            bump.alloc_pinned_permanent(SelfReferential {
+                value: 7,
+                me: ptr::null(),
+            })
+        };
+        let address = &*pinned as *const SelfReferential;
+        pinned.as_pin_mut().get_mut().me = address;
+        let mut after: Vec<Handle<usize>> = (0..10).map(|i| bump.alloc(i)).collect();
+        bump.scavenge();
+
+        // Everything in front of the pinned object dies, as does every other
+        // object behind it, so compaction has to work around it
+        drop(before);
+        after.retain(|after| *after.get(&bump) % 2 == 1);
+        for _ in 0..3 {
+            bump.scavenge();
+        }
+        let stats = bump.compact_now();
+        assert_eq!(stats.objects_moved, after.len());
+        let gap = bump.free_blocks().next().unwrap();
+        assert_eq!(gap.ptr.as_usize() + gap.size, address as usize);
+        bump.major();
+
+        assert_eq!(&*pinned as *const SelfReferential, address);
+        assert_eq!(pinned.me, address);
+        assert_eq!(pinned.value, 7);
+        assert_eq!(
+            unsafe { pinned.inner().heap },
+            ContainingHeap::Generation(1, 32)
+        );
+        assert!(after
+            .iter()
+            .enumerate()
+            .all(|(i, after)| *after.get(&bump) == i * 2 + 1));
+        assert_eq!(
+            bump.grow_young_generation(1024 * 8),
+            Err(HeapCreateError::FixedRegion)
+        );
+
+        // Unrooted pinned objects are still reclaimed
+        let id = pinned.id();
+        drop(pinned);
+        bump.major();
+        assert_eq!(bump.locate(id), None);
+    }
+
+    #[test]
+    fn memory_source() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Debug)]
+        struct Counting(AtomicUsize, AtomicUsize);
+
+        unsafe impl MemorySource for Counting {
+            unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                GlobalSource.alloc_zeroed(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                self.1.fetch_add(1, Ordering::Relaxed);
+                GlobalSource.dealloc(ptr, layout)
+            }
+        }
+
+        static SOURCE: Counting = Counting(AtomicUsize::new(0), AtomicUsize::new(0));
+
+        let mut bump = BumpHeap::new(BumpOptions::new().memory_source(&SOURCE));
+        let handle: Handle<usize> = bump.alloc(1);
+        bump.scavenge();
+        assert_eq!(*handle.get(&bump), 1);
+        assert_eq!(SOURCE.0.load(Ordering::Relaxed), 1);
+
+        drop(bump);
+        assert_eq!(SOURCE.1.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "mimalloc")]
+    fn mimalloc_source() {
+        let mut bump = BumpHeap::new(BumpOptions::new().memory_source(&memory::MiMallocSource));
+
+        let handle: Handle<usize> = bump.alloc(1);
+        bump.scavenge();
+        assert_eq!(*handle.get(&bump), 1);
+    }
+
+    /// The index and generation of each live id, which unlike the ids themselves
+    /// can be compared between heaps
+    fn live_ids(bump: &BumpHeap) -> Vec<(usize, usize)> {
+        let mut ids: Vec<(usize, usize)> = bump
+            .roots
+            .iter()
+            .flatten()
+            .map(|root| (root.id.index(), root.id.generation()))
+            .collect();
+        ids.sort();
+
+        ids
+    }
+
+    /// Builds the same object graph in `bump` every time, returning the handles
+    /// held from outside of the heap
+    fn build_graph(bump: &mut BumpHeap) -> Vec<Rooted<Vec<Rooted<u64>>>> {
+        let mut parents: Vec<Rooted<Vec<Rooted<u64>>>> = (0..10)
+            .map(|i| {
+                let children: Vec<Rooted<u64>> = (0..3)
+                    .map(|j| unsafe { bump.alloc_unchecked(i * 3 + j) })
+                    .collect();
+                unsafe { bump.alloc_traced(children) }
+            })
+            .collect();
+        bump.scavenge();
+
+        // Dead parents leave their children unreachable, but still rooted
+        for i in (0..parents.len()).rev().step_by(3) {
+            parents.remove(i);
+        }
+        for parent in parents.iter_mut().step_by(2) {
+            parent.map_in_place(|mut children| {
+                children.pop();
+                children
+            });
+        }
+
+        // Young objects are always kept by the mark
+        let young = unsafe { bump.alloc_unchecked(100) };
+        parents.push(unsafe { bump.alloc_traced(vec![young]) });
+
+        parents
     }
 
     #[test]
-    fn trigger_scavenge() {
+    fn background_mark_matches_stop_the_world() {
         let mut bump = BumpHeap::default();
+        let _parents = build_graph(&mut bump);
 
-        let i: usize = 1000;
-        let rooted: Rooted<usize> = unsafe { bump.alloc(i) };
-        assert_eq!(*rooted, i);
+        bump.request_background_mark();
+        bump.mark();
+        let white: HashSet<AllocId> = bump
+            .roots
+            .iter()
+            .flatten()
+            .filter(|root| root.color() == Color::White)
+            .map(|root| root.id)
+            .collect();
+
+        let mark = bump.background_mark.take().unwrap();
+        let unreachable = mark.join(&bump.ids.remembered());
+        assert_eq!(unreachable, white);
+        assert_eq!(unreachable.len(), 4 * 4 + 3);
+
+        let run = |background: bool| {
+            let mut bump = BumpHeap::default();
+            let parents = build_graph(&mut bump);
+
+            if background {
+                bump.request_background_mark();
+            } else {
+                bump.collect_mark_sweep_only();
+            }
+            bump.major();
+
+            (live_ids(&bump), bump.tenured_used(), parents)
+        };
+
+        let (stop_the_world, stop_the_world_used, _parents) = run(false);
+        let (background, background_used, _parents) = run(true);
+        assert_eq!(background, stop_the_world);
+        assert_eq!(background_used, stop_the_world_used);
+    }
+
+    #[test]
+    fn background_mark_keeps_written_objects() {
+        use crate::GcCell;
+
+        struct Parent {
+            child: GcCell<Option<Rooted<u64>>>,
+        }
+
+        impl Trace for Parent {
+            fn trace(&self, tracer: &mut Tracer) {
+                self.child.trace(tracer);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let child = unsafe { bump.alloc_unchecked(7) };
+        let child_id = child.id();
+        let parent = unsafe {
+            bump.alloc_traced(Parent {
+                child: GcCell::new(Some(child)),
+            })
+        };
+        let weak = Weak::new(&parent);
+        bump.scavenge();
+
+        // Both are unreachable when the mark starts
+        drop(parent);
+        bump.request_background_mark();
+
+        // Taking the child out fires the barrier for the parent
+        let parent = unsafe { weak.get(&bump) }.unwrap();
+        let child = parent.child.replace(None).unwrap();
+        bump.scavenge();
+
+        let promoted: Handle<u64> = bump.alloc(3);
+        bump.scavenge();
+        bump.major();
+
+        // The parent was unrooted, which the rest of the collection still reclaims
+        assert!(weak.is_cleared());
+        assert_eq!(bump.locate(child_id), bump.locate(child.id()));
+        assert_eq!(*child, 7);
+        assert_eq!(*promoted.get(&bump), 3);
+        assert_eq!(bump.counters().major_count, 1);
+    }
+
+    #[test]
+    fn background_mark_needs_the_write_barrier() {
+        // Takes every heap number
+        let _tables: Vec<IdTable> = (0..255).map(|_| IdTable::new()).collect();
+
+        let mut bump = BumpHeap::default();
+        let _parents = build_graph(&mut bump);
+        bump.request_background_mark();
+        assert!(bump.background_mark.is_none());
+    }
+
+    #[test]
+    fn ids_stay_unique_across_collections() {
+        let mut bump = BumpHeap::default();
+
+        let live: Vec<Rooted<u64>> = (0..10)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        let dead: Rooted<u64> = unsafe { bump.alloc_unchecked(10) };
+        let dead_id = dead.id();
+        drop(dead);
+        bump.scavenge();
+        bump.major();
+
+        // The dead object's slot is reused, but its old id doesn't resolve to the new object
+        let reused: Rooted<u64> = unsafe { bump.alloc_unchecked(11) };
+        assert_eq!(reused.id().index(), dead_id.index());
+        assert_ne!(reused.id(), dead_id);
+        assert!(bump.locate(dead_id).is_none());
+        assert!(bump.locate(reused.id()).is_some());
+
+        let mut ids: Vec<AllocId> = live.iter().chain(Some(&reused)).map(Rooted::id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), live.len() + 1);
+    }
+
+    #[test]
+    fn write_report() {
+        let report = |bump: &BumpHeap| {
+            let mut report = Vec::new();
+            bump.write_report(&mut report).unwrap();
+            String::from_utf8(report).unwrap()
+        };
+
+        let empty = BumpHeap::default();
+        empty.print_report();
+        assert!(report(&empty).contains("Eden: 0 of 4096 bytes used, 0 live objects"));
+
+        let mut busy = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+        let mut objects: Vec<Option<Rooted<[u64; 4]>>> = (0..200)
+            .map(|i| Some(unsafe { busy.alloc_unchecked([i; 4]) }))
+            .collect();
+        for object in objects.iter_mut().step_by(3) {
+            *object = None;
+        }
+        busy.scavenge();
+        busy.major();
+        busy.print_report();
+
+        let report = report(&busy);
+        assert!(report.contains("Generation 1:"), "{}", report);
+        assert!(report.contains("32B: "), "{}", report);
+        assert!(report.contains("200 allocations"), "{}", report);
+    }
+
+    #[test]
+    fn leak_report() {
+        let mut bump = BumpHeap::default();
+
+        let requests: Vec<Rooted<u64>> = (0..3)
+            .map(|i| unsafe { bump.alloc_tagged(i, "request") })
+            .collect();
+        let sessions: Vec<Rooted<u8>> = (0..2)
+            .map(|i| unsafe { bump.alloc_tagged(i, "session") })
+            .collect();
+        let dead: Rooted<u64> = unsafe { bump.alloc_tagged(3, "request") };
+        drop(dead);
+        bump.scavenge();
+
+        let report = bump.leak_report();
+        assert_eq!(
+            report[&AllocSite::Tagged("request")],
+            SiteStat {
+                count: requests.len(),
+                bytes: 3 * mem::size_of::<HeapValue<u64>>(),
+            },
+        );
+        assert_eq!(
+            report[&AllocSite::Tagged("session")],
+            SiteStat {
+                count: sessions.len(),
+                bytes: 2 * mem::size_of::<HeapValue<u8>>(),
+            },
+        );
+        assert_eq!(report.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "research")]
+    fn objects_with_color() {
+        use crate::Color;
+
+        let mut bump = BumpHeap::default();
+
+        let tenured: Rooted<u64> = unsafe { bump.alloc_unchecked(1) };
+        bump.scavenge();
+        let mut young: Rooted<u64> = unsafe { bump.alloc_unchecked(2) };
+        assert_eq!(young.color(), Color::White);
+
+        young.set_color(Color::Grey);
+        assert_eq!(young.color(), Color::Grey);
+        assert_eq!(
+            bump.objects_with_color(Color::Grey).collect::<Vec<_>>(),
+            vec![young.id()],
+        );
+        assert_eq!(
+            bump.objects_with_color(Color::White).collect::<Vec<_>>(),
+            vec![tenured.id()],
+        );
+
+        // Colors are kept when objects are promoted
+        bump.scavenge();
+        assert_eq!(young.color(), Color::Grey);
+        assert_eq!(bump.objects_with_color(Color::Black).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "type-profile")]
+    fn type_profile() {
+        use core::any::TypeId;
+
+        let mut bump = BumpHeap::default();
+
+        let bytes: Vec<Handle<u8>> = (0..3).map(|i| bump.alloc(i)).collect();
+        let longs: Vec<Handle<u64>> = (0..5).map(|i| bump.alloc(i)).collect();
+        let dead: Handle<u64> = bump.alloc(5);
+        drop(dead);
+        bump.scavenge();
+
+        let profile = bump.type_profile();
+        assert_eq!(profile.len(), 2);
+
+        let byte_stat = profile[&TypeId::of::<u8>()];
+        assert_eq!(byte_stat.name, "u8");
+        assert_eq!(byte_stat.count, bytes.len());
+        assert_eq!(byte_stat.bytes, 3 * mem::size_of::<HeapValue<u8>>());
+
+        let long_stat = profile[&TypeId::of::<u64>()];
+        assert_eq!(long_stat.name, "u64");
+        assert_eq!(long_stat.count, longs.len());
+        assert_eq!(long_stat.bytes, 5 * mem::size_of::<HeapValue<u64>>());
+    }
+
+    #[test]
+    fn manual_collection() {
+        let options = BumpOptions::new()
+            .old_heap_size(1024 * 16)
+            .collection_policy(CollectionPolicy::Manual { headroom: 1024 });
+        let mut bump = BumpHeap::new(options);
+        let size = mem::size_of::<HeapValue<[u8; 120]>>();
+
+        let mut objects = Vec::new();
+        let error = loop {
+            match bump.try_alloc([0u8; 120]) {
+                Ok(object) => objects.push(object),
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(error, AllocError::OutOfMemory { size });
+        assert_eq!(bump.counters().scavenge_count, 0);
+
+        // Plenty of headroom is left after the scavenge, so safepoints do nothing
+        bump.safepoint();
+        assert_eq!(bump.counters().scavenge_count, 1);
+        bump.safepoint();
+        assert_eq!(bump.counters().scavenge_count, 1);
+        assert!(objects
+            .iter()
+            .all(|object| object.location() == Location::Tenured(1)));
+
+        drop(objects);
+        let _objects: Vec<Rooted<[u8; 120]>> = (0..28)
+            .map(|_| unsafe { bump.alloc_unchecked([0u8; 120]) })
+            .collect();
+        assert_eq!(bump.counters().scavenge_count, 1);
+        bump.safepoint();
+        assert_eq!(bump.counters().scavenge_count, 2);
+    }
+
+    #[test]
+    fn size_threshold_promotion() {
+        static POLICY: SizeThresholdPolicy = SizeThresholdPolicy {
+            threshold: 64,
+            age: 2,
+        };
+        let mut bump = BumpHeap::new(BumpOptions::new().promotion_policy(&POLICY));
+
+        let garbage: Handle<usize> = bump.alloc(0);
+        let small: Handle<usize> = bump.alloc(1);
+        let large: Handle<[u64; 16]> = bump.alloc([2; 16]);
+        drop(garbage);
+
+        let outcome = bump.scavenge();
+        assert_eq!((outcome.survived, outcome.kept), (1, 1));
+        assert_eq!(large.location(), Location::Tenured(1));
+        assert_eq!(small.location(), Location::Eden);
+        assert_eq!(*small.get(&bump), 1);
+        assert_eq!(*large.get(&bump), [2; 16]);
+
+        // The small survivor was compacted down over the garbage
+        let (young_start, _) = bump.young_bounds();
+        let address = small.get(&bump) as *const usize as usize;
+        assert!(address < young_start.as_usize() + mem::size_of::<HeapValue<usize>>());
+        let later: Handle<usize> = bump.alloc(3);
+        assert_eq!(*small.get(&bump), 1);
+        assert_eq!(*later.get(&bump), 3);
+
+        // Its second survival reaches the policy's age
+        let outcome = bump.scavenge();
+        assert_eq!((outcome.survived, outcome.kept), (1, 1));
+        assert_eq!(small.location(), Location::Tenured(1));
+        assert_eq!(later.location(), Location::Eden);
+        assert_eq!(*small.get(&bump), 1);
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let is_aligned = |bump: &BumpHeap, object: &Rooted<u8>| {
+            bump.locate(object.id())
+                .unwrap()
+                .as_usize()
+                .is_multiple_of(64)
+        };
+        let mut bump = BumpHeap::new(BumpOptions::new().promotion_policy(&AgePolicy { age: 2 }));
+
+        let _padding: Handle<u8> = bump.alloc(0);
+        let aligned = unsafe { bump.alloc_aligned(7u8, 64) };
+        assert!(is_aligned(&bump, &aligned));
+        assert_eq!(aligned.allocated_size(), 64);
+
+        // Kept in the young generation, then promoted
+        let _more_padding: Handle<u8> = bump.alloc(0);
+        bump.scavenge();
+        assert_eq!(aligned.location(), Location::Eden);
+        assert!(is_aligned(&bump, &aligned));
+        bump.scavenge();
+        assert_eq!(aligned.location(), Location::Tenured(1));
+        assert!(is_aligned(&bump, &aligned));
+
+        drop(_padding);
+        bump.compact_now();
+        assert!(is_aligned(&bump, &aligned));
+        assert_eq!(*aligned, 7);
+
+        assert_eq!(
+            unsafe { bump.try_alloc_aligned(1u8, 48) }.err(),
+            Some(AllocError::UnsupportedAlignment(48))
+        );
+        let too_aligned = memory::page_size() * 2;
+        assert_eq!(
+            unsafe { bump.try_alloc_aligned(1u8, too_aligned) }.err(),
+            Some(AllocError::UnsupportedAlignment(too_aligned))
+        );
+    }
+
+    #[test]
+    fn live_fingerprint() {
+        fn build() -> (BumpHeap, Vec<Rooted<[u64; 4]>>, Rooted<u8>) {
+            let mut bump = BumpHeap::default();
+            let objects = (0..10)
+                .map(|i| unsafe { bump.alloc_hashed([i; 4]) })
+                .collect();
+            let byte = unsafe { bump.alloc_hashed(1u8) };
+
+            (bump, objects, byte)
+        }
+
+        let (mut bump, mut objects, mut byte) = build();
+        let (other, _other_objects, _other_byte) = build();
+        let fingerprint = bump.live_fingerprint().unwrap();
+        assert_eq!(other.live_fingerprint(), Some(fingerprint));
+
+        let young_at = bump.locate(objects[0].id());
+        bump.scavenge();
+        assert_ne!(bump.locate(objects[0].id()), young_at);
+        assert_eq!(bump.live_fingerprint(), Some(fingerprint));
+        bump.major();
+        assert_eq!(bump.live_fingerprint(), Some(fingerprint));
+
+        objects[3].replace([30; 4]);
+        let mutated = bump.live_fingerprint().unwrap();
+        assert_ne!(mutated, fingerprint);
+
+        objects[3].replace([3; 4]);
+        assert_eq!(bump.live_fingerprint(), Some(fingerprint));
+
+        byte.replace(2);
+        assert_ne!(bump.live_fingerprint(), Some(fingerprint));
+        byte.replace(1);
+        assert_eq!(bump.live_fingerprint(), Some(fingerprint));
+
+        objects.pop();
+        assert_ne!(bump.live_fingerprint(), Some(fingerprint));
+
+        // Changes to this one's value couldn't be seen
+        let unhashed = unsafe { bump.alloc_unchecked(1u8) };
+        assert_eq!(bump.live_fingerprint(), None);
+        drop(unhashed);
+        bump.scavenge();
+        assert!(bump.live_fingerprint().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-accounting")]
+    fn accounting() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+        let epoch = bump.new_epoch();
+
+        let mut kept: Vec<Rooted<[u64; 4]>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked([i; 4]) })
+            .collect();
+        let mut buffer = unsafe { bump.alloc_buffer(&[1, 2, 3]).unwrap() };
+        let aligned = unsafe { bump.alloc_aligned(1u8, 16) };
+        for i in 0..200u64 {
+            drop(unsafe { bump.alloc_unchecked(i) });
+        }
+        assert!(bump.accounting().live_bytes > 0);
+        bump.verify_accounting();
+
+        kept.truncate(10);
+        unsafe { bump.extend_buffer(&mut buffer, &[4; 100]).unwrap() };
+        bump.scavenge();
+        bump.reset_counters();
+        kept.truncate(5);
+        bump.major();
+        bump.compact_now();
+        bump.collect_mark_sweep_only();
+        bump.verify_accounting();
+
+        assert_eq!(unsafe { bump.free_region(epoch) }, 7);
+        drop((kept, buffer, aligned));
+        bump.scavenge();
+        bump.major();
+
+        let accounting = bump.accounting();
+        assert!(accounting.total_allocated > 0);
+        assert_eq!(accounting.live_bytes, 0);
+        assert_eq!(accounting.total_allocated, accounting.total_reclaimed);
+    }
+
+    #[test]
+    fn with_intermediate() {
+        let pocket_sizes = PocketSizes::new(&[16, 64, 512]);
+        let intermediate = SweepHeap::try_with_pockets(1024 * 64, pocket_sizes).unwrap();
+        let (start, end) = intermediate.bounds();
+
+        let options = BumpOptions::new().tenured_generations(&[1024, 1024 * 8]);
+        let mut bump = BumpHeap::with_intermediate(options, intermediate).unwrap();
+        assert_eq!(bump.tenured_bounds(1), Some((start, end)));
+        let (region_start, region_end) = bump.bounds();
+        assert_eq!(region_end.offset_from(region_start), 1024 * 4 + 1024 * 8);
+        assert_eq!(bump.tenured_bounds(2).unwrap().0, bump.young_bounds().1);
+
+        let objects: Vec<Rooted<[u64; 16]>> = (0..100)
+            .map(|i| unsafe { bump.alloc_unchecked([i; 16]) })
+            .collect();
+        bump.scavenge();
+        for object in &objects {
+            assert_eq!(object.location(), Location::Tenured(1));
+            assert_eq!(object.allocated_size(), 512);
+
+            let address = bump.locate(object.id()).unwrap();
+            assert!(start <= address && address < end);
+        }
+        assert!(bump.tenured_used() > 1024 * 8);
+
+        bump.grow_young_generation(1024 * 8).unwrap();
+        bump.major();
+        bump.compact_now();
+        assert_eq!(bump.tenured_bounds(1), Some((start, end)));
+        assert!(objects
+            .iter()
+            .enumerate()
+            .all(|(i, object)| object.iter().all(|&word| word == i as u64)));
+
+        drop(objects);
+        assert!(!bump.hibernate());
+        unsafe { bump.clear() };
+        assert_eq!(bump.tenured_used(), 0);
+        assert_eq!(bump.tenured_bounds(1), Some((start, end)));
+
+        let again = unsafe { bump.alloc_unchecked(7u64) };
+        bump.scavenge();
+        assert_eq!(bump.locate(again.id()), Some(start));
+    }
+
+    #[test]
+    fn iter_by_address() {
+        let mut bump = BumpHeap::new(BumpOptions::new().tenured_generations(&[1024 * 8, 1024 * 8]));
+
+        let mut objects: Vec<Option<Rooted<[u64; 4]>>> = (0..30)
+            .map(|i| Some(unsafe { bump.alloc_unchecked([i; 4]) }))
+            .collect();
+        bump.scavenge();
+        bump.major();
+        objects.extend((30..40).map(|i| Some(unsafe { bump.alloc_unchecked([i; 4]) })));
+        bump.scavenge();
+        objects.extend((40..50).map(|i| Some(unsafe { bump.alloc_unchecked([i; 4]) })));
+        let _byte = unsafe { bump.alloc_unchecked(1u8) };
+        for object in objects.iter_mut().step_by(3) {
+            *object = None;
+        }
+
+        let sorted: Vec<LiveObject> = bump.iter_by_address().collect();
+        assert!(sorted
+            .windows(2)
+            .all(|pair| pair[0].address <= pair[1].address));
+        for location in &[Location::Eden, Location::Tenured(1), Location::Tenured(2)] {
+            assert!(sorted.iter().any(|object| object.location == *location));
+        }
+        for object in &sorted {
+            assert_eq!(bump.locate(object.id), Some(object.address));
+        }
+
+        let mut ids: Vec<AllocId> = sorted.iter().map(|object| object.id).collect();
+        ids.sort();
+        ids.dedup();
+        let mut live: Vec<AllocId> = bump.iter_live().map(|object| object.id).collect();
+        live.sort();
+        assert_eq!(ids, live);
+        assert_eq!(ids.len(), bump.live_count());
+        assert_eq!(ids.len(), 34);
+    }
+
+    #[test]
+    fn reclaim_larger_than_every_pocket() {
+        const SIZE: usize = 1024 * 40;
+
+        let mut bump = BumpHeap::new(
+            BumpOptions::new()
+                .young_heap_size(1024 * 64)
+                .old_heap_size(1024 * 128),
+        );
+        let block = bump.generations[0].block_size(mem::size_of::<HeapValue<[u8; SIZE]>>());
+        assert!(block > *PocketSizes::default().as_slice().last().unwrap());
+
+        let large: Rooted<[u8; SIZE]> = unsafe { bump.alloc_unchecked([7; SIZE]) };
+        let first = bump.scavenge();
+        assert_eq!(first.promoted, block);
+        assert!(!first.triggered_major);
+        assert_eq!(large.location(), Location::Tenured(1));
+        assert_eq!(large.allocated_size(), block);
+        let address = bump.locate(large.id());
+
+        drop(large);
+        let before = bump.counters().bytes_reclaimed;
+        bump.major();
+        assert_eq!(
+            bump.counters().bytes_reclaimed - before,
+            mem::size_of::<HeapValue<[u8; SIZE]>>()
+        );
+        assert_eq!(bump.tenured_used(), 0);
+
+        let again: Rooted<[u8; SIZE]> = unsafe { bump.alloc_unchecked([8; SIZE]) };
+        bump.scavenge();
+        assert_eq!(bump.locate(again.id()), address);
+        assert!(again.iter().all(|&byte| byte == 8));
+    }
+
+    #[test]
+    fn promote_larger_than_every_pocket() {
+        const SIZE: usize = 1024 * 40;
+
+        let mut bump = BumpHeap::new(
+            BumpOptions::new()
+                .young_heap_size(1024 * 64)
+                .old_heap_size(1024 * 128),
+        );
+
+        let large: Rooted<[u8; SIZE]> = unsafe { bump.alloc_unchecked([7; SIZE]) };
+        let small: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        bump.scavenge();
+
+        assert!(large.iter().all(|&byte| byte == 7));
+        assert_eq!(*small, 1);
+
+        drop(large);
+        bump.major();
+
+        for i in 0..4 {
+            let again: Rooted<[u8; SIZE]> = unsafe { bump.alloc_unchecked([i; SIZE]) };
+            bump.scavenge();
+            assert!(again.iter().all(|&byte| byte == i));
+
+            drop(again);
+            bump.major();
+        }
+        assert_eq!(*small, 1);
+    }
+
+    #[test]
+    fn bump_only() {
+        let mut bump = BumpHeap::new_bump_only(mem::size_of::<HeapValue<usize>>() * 4);
+
+        let objects: Vec<Handle<usize>> = (0..4).map(|i| bump.alloc(i)).collect();
+        let addresses: Vec<_> = objects.iter().map(|o| bump.locate(o.id())).collect();
+
+        bump.scavenge();
+        bump.major();
+        assert_eq!(bump.counters().scavenge_count, 0);
+        assert_eq!(
+            objects
+                .iter()
+                .map(|o| bump.locate(o.id()))
+                .collect::<Vec<_>>(),
+            addresses,
+        );
+
+        assert_eq!(
+            bump.try_alloc(4usize).err(),
+            Some(AllocError::OutOfMemory {
+                size: mem::size_of::<HeapValue<usize>>(),
+            }),
+        );
+        for (i, object) in objects.iter().enumerate() {
+            assert_eq!(*object.get(&bump), i);
+        }
+    }
+
+    #[test]
+    fn deterministic_addresses() {
+        #[repr(align(16))]
+        struct Region([u8; 1024 * 8]);
+
+        let mut region = Box::new(Region([0xFF; 1024 * 8]));
+        let base = HeapPointer::from(region.0.as_mut_ptr());
+        let mut bump = unsafe { BumpHeap::new_at(BumpOptions::default(), base) };
+
+        let a: Handle<usize> = bump.alloc(1);
+        let b: Handle<u8> = bump.alloc(2);
+        let c: Handle<u128> = bump.alloc(3);
+        assert_eq!(bump.locate(a.id()), Some(base));
+        assert_eq!(bump.locate(b.id()), Some(base + 8usize));
+        assert_eq!(bump.locate(c.id()), Some(base + 16usize));
+
+        drop(b);
+        bump.scavenge();
+
+        // The intermediate heap starts right where the young generation ends
+        let intermediate = base + 1024 * 4usize;
+        assert_eq!(bump.locate(a.id()), Some(intermediate));
+        assert_eq!(bump.locate(c.id()), Some(intermediate + 32usize));
+        assert_eq!((*a.get(&bump), *c.get(&bump)), (1, 3));
+
+        drop((a, c));
+        drop(bump);
+    }
+
+    #[test]
+    fn growable_buffers() {
+        let mut bump = BumpHeap::default();
+
+        let mut buffer = unsafe { bump.alloc_buffer(b"hello").unwrap() };
+        let young_at = bump.locate(buffer.id());
+        unsafe { bump.extend_buffer(&mut buffer, b", ").unwrap() };
+        assert_eq!(bump.locate(buffer.id()), young_at);
+
+        // Something else is bumped after the buffer, so it has to move
+        let other: Handle<usize> = bump.alloc(1);
+        unsafe { bump.extend_buffer(&mut buffer, b"world").unwrap() };
+        assert_ne!(bump.locate(buffer.id()), young_at);
+        assert_eq!(buffer.as_slice(), b"hello, world");
+
+        // `other` is promoted after the buffer, so the buffer can't grow into the frontier
+        bump.scavenge();
+        let tenured_at = bump.locate(buffer.id());
+        unsafe { bump.extend_buffer(&mut buffer, &[b'!'; 40]).unwrap() };
+        let moved_to = bump.locate(buffer.id());
+        assert_ne!(moved_to, tenured_at);
+
+        // Still fits in the same pocket
+        unsafe { bump.extend_buffer(&mut buffer, b"??").unwrap() };
+        assert_eq!(bump.locate(buffer.id()), moved_to);
+
+        assert_eq!(buffer.len(), 54);
+        assert_eq!(&buffer.as_slice()[..12], b"hello, world");
+        assert!(buffer.as_slice()[12..52].iter().all(|&byte| byte == b'!'));
+        assert_eq!(*other.get(&bump), 1);
+
+        bump.major();
+        assert_eq!(&buffer.as_slice()[52..], b"??");
+    }
+
+    #[test]
+    fn collect_slice() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let numbers = unsafe { bump.collect_slice(0..100) };
+        let squares = unsafe { bump.collect_slice(numbers.iter().map(|n| (n * n) as u64)) };
+        let empty = unsafe { bump.collect_slice(core::iter::empty::<u8>()) };
+
+        bump.scavenge();
+        assert_eq!(numbers.as_slice(), &(0..100).collect::<Vec<i32>>()[..]);
+        assert_eq!(squares.len(), 100);
+        assert_eq!(squares[99], 99 * 99);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn reuses_dead_metadata() {
+        let mut bump = BumpHeap::default();
+
+        let dead: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let dead_inner = unsafe { dead.inner() as *const RootedInner };
+        drop(dead);
+        bump.scavenge();
+
+        let reused: Rooted<u64> = unsafe { bump.alloc_unchecked(2) };
+        assert_eq!(unsafe { reused.inner() as *const RootedInner }, dead_inner);
+        assert!(bump.spare_roots.is_empty());
+        assert_eq!(*reused, 2);
+
+        bump.scavenge();
+        assert_eq!(*reused, 2);
+    }
+
+    #[test]
+    fn generation_count() {
+        assert_eq!(
+            BumpOptions::new().tenured_generations(&[]).build().err(),
+            Some(OptionsError::InvalidGenerationCount(1))
+        );
+        assert_eq!(
+            BumpOptions::new()
+                .tenured_generations(&[1024 * 4; MAX_GENERATIONS])
+                .build()
+                .err(),
+            Some(OptionsError::InvalidGenerationCount(MAX_GENERATIONS + 1))
+        );
+        assert!(BumpOptions::new()
+            .tenured_generations(&[1024 * 4; MAX_GENERATIONS - 1])
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn ages_through_three_generations() {
+        let mut bump = BumpHeap::new(
+            BumpOptions::new()
+                .tenured_generations(&[1024 * 4, 1024 * 8])
+                .tenure_age(2),
+        );
+        let generation_of = |rooted: &Rooted<usize>| match unsafe { rooted.inner().heap } {
+            ContainingHeap::Eden => 0,
+            ContainingHeap::Generation(generation, _) => generation,
+        };
+
+        let objects: Vec<Rooted<usize>> = (0..20)
+            .map(|i| unsafe { bump.alloc_unchecked(i) })
+            .collect();
+        let dead: Rooted<usize> = unsafe { bump.alloc_unchecked(20) };
+        assert!(objects.iter().all(|rooted| generation_of(rooted) == 0));
 
         bump.scavenge();
-        assert_eq!(*rooted, i);
+        drop(dead);
+        assert!(objects.iter().all(|rooted| generation_of(rooted) == 1));
+
+        // Surviving one major isn't enough to reach the tenure age
+        bump.major();
+        assert!(objects.iter().all(|rooted| generation_of(rooted) == 1));
+        assert_eq!(bump.generations[1].used(), 0);
+
+        bump.major();
+        assert!(objects.iter().all(|rooted| generation_of(rooted) == 2));
+        assert_eq!(bump.generations[0].used(), 0);
+
+        // The last generation has nowhere to promote into
+        bump.major();
+        bump.major();
+        assert!(objects.iter().all(|rooted| generation_of(rooted) == 2));
+
+        for (i, rooted) in objects.iter().enumerate() {
+            assert_eq!(**rooted, i);
+        }
     }
 
     #[test]
@@ -248,9 +5525,9 @@ mod tests {
         let mut bump = BumpHeap::default();
 
         for i in 0..4000 {
-            let rooted: Rooted<usize> = unsafe { bump.alloc(i) };
-            assert_eq!(*rooted, i);
-            drop(rooted);
+            let handle: Handle<usize> = bump.alloc(i);
+            assert_eq!(*handle.get(&bump), i);
+            drop(handle);
         }
     }
 
@@ -260,35 +5537,28 @@ mod tests {
 
         let mut permanent = Vec::with_capacity(50);
         for i in 0..100 {
-            let rooted: Rooted<usize> = unsafe { bump.alloc(i) };
-            assert_eq!(*rooted, i);
-            permanent.push((rooted, i));
+            let handle: Handle<usize> = bump.alloc(i);
+            assert_eq!(*handle.get(&bump), i);
+            permanent.push((handle, i));
         }
 
         bump.major();
-        println!("here");
         for (perm, i) in &permanent {
-            assert_eq!(**perm, *i);
+            assert_eq!(*perm.get(&bump), *i);
         }
-        println!("here");
 
-        println!("here");
         for i in 0..1000 {
-            let rooted: Rooted<usize> = unsafe { bump.alloc(i) };
-            assert_eq!(*rooted, i);
-            drop(rooted);
+            let handle: Handle<usize> = bump.alloc(i);
+            assert_eq!(*handle.get(&bump), i);
+            drop(handle);
         }
-        println!("here");
 
         bump.major();
-        println!("here");
         for (perm, i) in permanent {
-            assert_eq!(*perm, i);
+            assert_eq!(*perm.get(&bump), i);
             drop(perm);
         }
-        println!("here");
 
         bump.major();
-        println!("here");
     }
 }
@@ -0,0 +1,146 @@
+use crate::{
+    bump_heap,
+    memory::AllocId,
+    trace::{Trace, Tracer},
+};
+use core::cell::{Cell, Ref, RefCell, RefMut};
+
+/// A mutable memory location for heap objects, like [`RefCell`]
+///
+/// The cell only exists so that an object's children can be changed through a
+/// shared handle while its [`Trace`] impl keeps reporting them. Writing to it
+/// fires the write barrier for the object it's in, which it learns the first time
+/// the heap traces that object
+#[derive(Debug, Default)]
+pub struct GcCell<T> {
+    value: RefCell<T>,
+    owner: Cell<Option<AllocId>>,
+}
+
+impl<T> GcCell<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: RefCell::new(value),
+            owner: Cell::new(None),
+        }
+    }
+
+    fn write_barrier(&self) {
+        if let Some(owner) = self.owner.get() {
+            bump_heap::write_barrier(owner);
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the value is mutably borrowed
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.value.borrow()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the value is borrowed
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        let value = self.value.borrow_mut();
+        self.write_barrier();
+
+        value
+    }
+
+    /// Replaces the value, returning the old one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is borrowed
+    pub fn replace(&self, value: T) -> T {
+        self.write_barrier();
+        self.value.replace(value)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: Trace> Trace for GcCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the value is mutably borrowed
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(owner) = tracer.owner() {
+            self.owner.set(Some(owner));
+        }
+
+        self.value.borrow().trace(tracer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BumpHeap, Rooted};
+
+    struct Node {
+        child: GcCell<Option<Rooted<usize>>>,
+    }
+
+    impl Trace for Node {
+        fn trace(&self, tracer: &mut Tracer) {
+            self.child.trace(tracer);
+        }
+    }
+
+    #[test]
+    fn mutate_child() {
+        let mut bump = BumpHeap::default();
+
+        let first = unsafe { bump.alloc_unchecked(1) };
+        let node = unsafe {
+            bump.alloc_traced(Node {
+                child: GcCell::new(Some(first)),
+            })
+        };
+        bump.scavenge();
+
+        let second = unsafe { bump.alloc_unchecked(2) };
+        let second_id = second.id();
+        let old = node.child.replace(Some(second));
+        assert_eq!(old.as_deref(), Some(&1));
+        drop(old);
+
+        bump.scavenge();
+        bump.major();
+
+        let mut tracer = Tracer::new();
+        (*node).trace(&mut tracer);
+        assert_eq!(tracer.edges(), &[second_id]);
+        assert_eq!(node.child.borrow().as_deref(), Some(&2));
+
+        *node.child.borrow_mut() = None;
+        assert!(node.child.borrow().is_none());
+    }
+
+    #[test]
+    fn knows_its_owner() {
+        let mut bump = BumpHeap::default();
+
+        let loose = GcCell::new(None::<Rooted<usize>>);
+        let mut tracer = Tracer::new();
+        loose.trace(&mut tracer);
+        assert_eq!(loose.owner.get(), None);
+
+        let node = unsafe {
+            bump.alloc_traced(Node {
+                child: GcCell::new(None),
+            })
+        };
+        assert_eq!(node.child.owner.get(), Some(node.id()));
+
+        bump.scavenge();
+        assert!(!bump.is_remembered(node.id()));
+        *node.child.borrow_mut() = Some(unsafe { bump.alloc_unchecked(1) });
+        assert_eq!(node.child.owner.get(), Some(node.id()));
+        assert!(bump.is_remembered(node.id()));
+    }
+}
@@ -0,0 +1,107 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The tri-color mark of an object, ordered from unmarked to fully marked so
+/// that comparing colors compares how far along marking an object is
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Color {
+    /// Not reached by marking (yet)
+    #[default]
+    White = 0,
+    /// Reached, but its children haven't been visited
+    Grey = 1,
+    /// Reached, along with all of its children
+    Black = 2,
+}
+
+impl Color {
+    pub const fn is_white(self) -> bool {
+        matches!(self, Self::White)
+    }
+
+    pub const fn is_grey(self) -> bool {
+        matches!(self, Self::Grey)
+    }
+
+    pub const fn is_black(self) -> bool {
+        matches!(self, Self::Black)
+    }
+}
+
+/// A [`Color`] that several markers can update at once
+///
+/// Loads and stores are relaxed, so they're plain moves on the common targets
+/// and single-threaded code pays nothing for them. Only [`AtomicColor::try_mark`]
+/// needs a read-modify-write
+#[derive(Debug, Default)]
+pub(crate) struct AtomicColor(AtomicU8);
+
+impl AtomicColor {
+    pub(crate) const fn new(color: Color) -> Self {
+        Self(AtomicU8::new(color as u8))
+    }
+
+    pub(crate) fn load(&self) -> Color {
+        match self.0.load(Ordering::Relaxed) {
+            0 => Color::White,
+            1 => Color::Grey,
+            _ => Color::Black,
+        }
+    }
+
+    pub(crate) fn store(&self, color: Color) {
+        self.0.store(color as u8, Ordering::Relaxed);
+    }
+
+    /// Marks a white object black, returning whether this call was the one that
+    /// marked it, so that racing markers each claim an object at most once
+    pub(crate) fn try_mark(&self) -> bool {
+        self.0
+            .compare_exchange(
+                Color::White as u8,
+                Color::Black as u8,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+}
+
+impl Clone for AtomicColor {
+    fn clone(&self) -> Self {
+        Self::new(self.load())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discriminants() {
+        assert_eq!(Color::White as u8, 0);
+        assert_eq!(Color::Grey as u8, 1);
+        assert_eq!(Color::Black as u8, 2);
+        assert!(Color::White < Color::Grey && Color::Grey < Color::Black);
+    }
+
+    #[test]
+    fn predicates() {
+        assert!(Color::White.is_white() && !Color::White.is_grey() && !Color::White.is_black());
+        assert!(Color::Grey.is_grey() && !Color::Grey.is_white() && !Color::Grey.is_black());
+        assert!(Color::Black.is_black() && !Color::Black.is_white() && !Color::Black.is_grey());
+    }
+
+    #[test]
+    fn atomic_colors() {
+        let color = AtomicColor::new(Color::Grey);
+        assert_eq!(color.load(), Color::Grey);
+        assert!(!color.try_mark());
+
+        color.store(Color::White);
+        assert!(color.try_mark());
+        assert_eq!(color.load(), Color::Black);
+        assert!(!color.try_mark());
+        assert_eq!(color.clone().load(), Color::Black);
+    }
+}
@@ -0,0 +1,84 @@
+/// Running totals of the work a heap has done, reset with [`GcCounters::reset`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GcCounters {
+    pub total_allocations: usize,
+    pub bytes_allocated: usize,
+    pub scavenge_count: usize,
+    pub major_count: usize,
+    pub compaction_count: usize,
+    pub bytes_promoted: usize,
+    pub bytes_reclaimed: usize,
+    /// The number of times the old generation couldn't fit a promoted object
+    pub oom_events: usize,
+}
+
+impl GcCounters {
+    pub const fn new() -> Self {
+        Self {
+            total_allocations: 0,
+            bytes_allocated: 0,
+            scavenge_count: 0,
+            major_count: 0,
+            compaction_count: 0,
+            bytes_promoted: 0,
+            bytes_reclaimed: 0,
+            oom_events: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Every byte a heap has allocated and reclaimed, from
+/// [`BumpHeap::accounting`](crate::BumpHeap::accounting)
+///
+/// Unlike [`GcCounters`] the totals are never reset, so every byte allocated is
+/// either reclaimed or still held by the heap. `live_bytes` counts every object
+/// the heap holds, including dead ones that haven't been reclaimed yet
+#[cfg(feature = "debug-accounting")]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Accounting {
+    pub total_allocated: usize,
+    pub total_reclaimed: usize,
+    pub live_bytes: usize,
+}
+
+#[cfg(feature = "debug-accounting")]
+impl Accounting {
+    /// Whether every byte allocated was either reclaimed or is still held
+    pub const fn is_balanced(&self) -> bool {
+        self.total_allocated == self.total_reclaimed + self.live_bytes
+    }
+}
+
+/// Where objects were allocated, from [`BumpHeap::leak_report`](crate::BumpHeap::leak_report)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AllocSite {
+    /// The tag the objects were allocated with by
+    /// [`BumpHeap::alloc_tagged`](crate::BumpHeap::alloc_tagged)
+    Tagged(&'static str),
+    /// The backtrace of the objects' allocation, for untagged objects
+    #[cfg(feature = "capture-backtrace")]
+    Backtrace(std::string::String),
+    /// Untagged objects
+    Unknown,
+}
+
+/// The live objects allocated at a single site, from
+/// [`BumpHeap::leak_report`](crate::BumpHeap::leak_report)
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SiteStat {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// The live objects of a single type, from [`BumpHeap::type_profile`](crate::BumpHeap::type_profile)
+#[cfg(feature = "type-profile")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TypeStat {
+    pub name: &'static str,
+    pub count: usize,
+    pub bytes: usize,
+}
@@ -0,0 +1,333 @@
+use crate::{
+    fx_hash::FxHasher,
+    memory::{AllocId, HeapPointer},
+    rooted::{ContainingHeap, RootedInner},
+    trace::Tracer,
+};
+use alloc::vec::Vec;
+use core::{
+    any::TypeId,
+    hash::{Hash, Hasher},
+};
+use std::io::{self, Read, Write};
+
+/// The version of the format written by [`BumpHeap::dump`](crate::BumpHeap::dump),
+/// bumped whenever the layout changes
+pub const DUMP_VERSION: u32 = 2;
+
+const MAGIC: [u8; 4] = *b"BLST";
+
+/// A read-only view of a heap dump, for inspection rather than for running
+/// anything on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapDump {
+    pub version: u32,
+    /// The `[start, end)` address range of the young generation
+    pub young_bounds: (usize, usize),
+    /// The `[start, end)` address ranges of the tenured generations, youngest first
+    pub tenured_bounds: Vec<(usize, usize)>,
+    pub tenure_age: u8,
+    pub objects: Vec<DumpedObject>,
+}
+
+impl HeapDump {
+    pub fn object(&self, id: AllocId) -> Option<&DumpedObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+}
+
+/// A live object as it was when the heap was dumped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpedObject {
+    pub id: AllocId,
+    pub address: usize,
+    pub size: usize,
+    /// A hash of the object's `TypeId`, which is only meaningful to the build
+    /// that wrote the dump
+    pub type_hash: u64,
+    /// The generation the object was in, where the young generation is 0
+    pub generation: u8,
+    /// A hash of the object's value, or `None` if it wasn't allocated with a
+    /// `Hash` bound, see [`BumpHeap::alloc_hashed`](crate::BumpHeap::alloc_hashed)
+    pub value_hash: Option<u64>,
+    /// The object's children, or `None` if it wasn't allocated with a `Trace` bound
+    pub children: Option<Vec<AllocId>>,
+}
+
+impl DumpedObject {
+    /// Whether the object is a `T`, assuming the dump was written by this build
+    pub fn is<T: 'static>(&self) -> bool {
+        self.type_hash == type_hash(TypeId::of::<T>())
+    }
+}
+
+fn type_hash(type_id: TypeId) -> u64 {
+    let mut hasher = FxHasher::default();
+    type_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes the dump format, everything is little endian
+pub(crate) struct DumpWriter<'a, W: Write> {
+    w: &'a mut W,
+    tracer: Tracer,
+}
+
+impl<'a, W: Write> DumpWriter<'a, W> {
+    pub(crate) fn new(w: &'a mut W) -> Self {
+        Self {
+            w,
+            tracer: Tracer::new(),
+        }
+    }
+
+    pub(crate) fn header(
+        &mut self,
+        young_bounds: (HeapPointer, HeapPointer),
+        tenured_bounds: &[(HeapPointer, HeapPointer)],
+        tenure_age: u8,
+        objects: usize,
+    ) -> io::Result<()> {
+        self.w.write_all(&MAGIC)?;
+        self.w.write_all(&DUMP_VERSION.to_le_bytes())?;
+
+        self.bounds(young_bounds)?;
+        self.usize(tenured_bounds.len())?;
+        for &bounds in tenured_bounds {
+            self.bounds(bounds)?;
+        }
+        self.w.write_all(&[tenure_age])?;
+
+        self.usize(objects)
+    }
+
+    pub(crate) fn object(&mut self, root: &RootedInner) -> io::Result<()> {
+        let generation = match root.containing_heap() {
+            ContainingHeap::Eden => 0,
+            ContainingHeap::Generation(generation, _) => generation,
+        };
+
+        self.usize(root.id.as_usize())?;
+        self.usize(root.data_ptr().as_usize())?;
+        self.usize(root.size())?;
+        self.w.write_all(&type_hash(root.type_id).to_le_bytes())?;
+        self.w.write_all(&[generation])?;
+
+        // The object's own bytes include padding that can't be read
+        let mut hasher = FxHasher::default();
+        if root.hash_value(&mut hasher) {
+            self.w.write_all(&[1])?;
+            self.w.write_all(&hasher.finish().to_le_bytes())?;
+        } else {
+            self.w.write_all(&[0])?;
+        }
+
+        self.tracer.clear();
+        if root.trace_children(&mut self.tracer) {
+            self.w.write_all(&[1])?;
+            self.usize(self.tracer.edges().len())?;
+            for id in self.tracer.edges().to_vec() {
+                self.usize(id.as_usize())?;
+            }
+        } else {
+            self.w.write_all(&[0])?;
+        }
+
+        Ok(())
+    }
+
+    fn bounds(&mut self, (start, end): (HeapPointer, HeapPointer)) -> io::Result<()> {
+        self.usize(start.as_usize())?;
+        self.usize(end.as_usize())
+    }
+
+    fn usize(&mut self, value: usize) -> io::Result<()> {
+        self.w.write_all(&(value as u64).to_le_bytes())
+    }
+}
+
+/// Reads a dump written by [`BumpHeap::dump`](crate::BumpHeap::dump)
+pub fn read_dump<R: Read>(r: &mut R) -> io::Result<HeapDump> {
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(invalid_data("Not a heap dump"));
+    }
+
+    let version = read_u32(r)?;
+    if version != DUMP_VERSION {
+        return Err(invalid_data("Unsupported heap dump version"));
+    }
+
+    let young_bounds = read_bounds(r)?;
+    let tenured_bounds = (0..read_usize(r)?)
+        .map(|_| read_bounds(r))
+        .collect::<io::Result<Vec<_>>>()?;
+    let tenure_age = read_u8(r)?;
+
+    let objects = (0..read_usize(r)?)
+        .map(|_| read_object(r))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(HeapDump {
+        version,
+        young_bounds,
+        tenured_bounds,
+        tenure_age,
+        objects,
+    })
+}
+
+fn read_object<R: Read>(r: &mut R) -> io::Result<DumpedObject> {
+    let id = read_id(r)?;
+    let address = read_usize(r)?;
+    let size = read_usize(r)?;
+    let type_hash = read_u64(r)?;
+    let generation = read_u8(r)?;
+
+    let value_hash = match read_u8(r)? {
+        0 => None,
+        1 => Some(read_u64(r)?),
+        _ => return Err(invalid_data("Invalid value hash marker")),
+    };
+
+    let children = match read_u8(r)? {
+        0 => None,
+        1 => Some(
+            (0..read_usize(r)?)
+                .map(|_| read_id(r))
+                .collect::<io::Result<Vec<_>>>()?,
+        ),
+        _ => return Err(invalid_data("Invalid children marker")),
+    };
+
+    Ok(DumpedObject {
+        id,
+        address,
+        size,
+        type_hash,
+        generation,
+        value_hash,
+        children,
+    })
+}
+
+fn read_bounds<R: Read>(r: &mut R) -> io::Result<(usize, usize)> {
+    Ok((read_usize(r)?, read_usize(r)?))
+}
+
+fn read_id<R: Read>(r: &mut R) -> io::Result<AllocId> {
+    read_usize(r).map(AllocId::from_usize)
+}
+
+fn read_usize<R: Read>(r: &mut R) -> io::Result<usize> {
+    let value = read_u64(r)?;
+    if value > usize::MAX as u64 {
+        return Err(invalid_data("Value doesn't fit in a usize"));
+    }
+
+    Ok(value as usize)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut byte = [0];
+    r.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BumpHeap, Rooted, Trace};
+
+    struct Node {
+        value: usize,
+        children: Vec<Rooted<Node>>,
+    }
+
+    impl Trace for Node {
+        fn trace(&self, tracer: &mut Tracer) {
+            self.children.trace(tracer);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bump = BumpHeap::default();
+
+        let leaves: Vec<Rooted<Node>> = (0..2)
+            .map(|value| unsafe {
+                bump.alloc_traced(Node {
+                    value,
+                    children: Vec::new(),
+                })
+            })
+            .collect();
+        let leaf_ids: Vec<AllocId> = leaves.iter().map(|leaf| leaf.id()).collect();
+        let root = unsafe {
+            bump.alloc_traced(Node {
+                value: 2,
+                children: leaves,
+            })
+        };
+        bump.scavenge();
+        let untraced: Rooted<u64> = unsafe { bump.alloc_hashed(0xDEAD_BEEF) };
+
+        let mut buffer = Vec::new();
+        bump.dump(&mut buffer).unwrap();
+        let dump = read_dump(&mut &buffer[..]).unwrap();
+
+        assert_eq!(dump.version, DUMP_VERSION);
+        assert_eq!(dump.tenured_bounds.len(), 1);
+        assert_eq!(dump.objects.len(), 4);
+
+        let dumped_root = dump.object(root.id()).unwrap();
+        assert!(dumped_root.is::<Node>());
+        assert_eq!(dumped_root.generation, 1);
+        assert_eq!(dumped_root.children.as_ref(), Some(&leaf_ids));
+        assert_eq!(
+            dumped_root.address,
+            bump.locate(root.id()).unwrap().as_usize()
+        );
+        assert_eq!(root.value, 2);
+
+        for id in &leaf_ids {
+            assert_eq!(dump.object(*id).unwrap().children, Some(Vec::new()));
+        }
+
+        let dumped_untraced = dump.object(untraced.id()).unwrap();
+        assert!(dumped_untraced.is::<u64>());
+        assert_eq!(dumped_untraced.generation, 0);
+        assert_eq!(dumped_untraced.children, None);
+        let mut hasher = FxHasher::default();
+        0xDEAD_BEEFu64.hash(&mut hasher);
+        assert_eq!(dumped_untraced.value_hash, Some(hasher.finish()));
+        assert_eq!(dumped_root.value_hash, None);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut buffer = Vec::new();
+        BumpHeap::default().dump(&mut buffer).unwrap();
+        buffer[4] = buffer[4].wrapping_add(1);
+
+        let err = read_dump(&mut &buffer[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
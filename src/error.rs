@@ -0,0 +1,115 @@
+use core::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocError {
+    /// The object can't fit in the young generation, even after a scavenge
+    TooLarge { size: usize, capacity: usize },
+    /// The object's alignment is larger than the heap supports
+    UnsupportedAlignment(usize),
+    /// The object can't fit in its tenured generation, even after a major collection
+    GenerationFull { size: usize, generation: u8 },
+    /// The heap doesn't collect and has no room left for the object
+    OutOfMemory { size: usize },
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooLarge { size, capacity } => write!(
+                f,
+                "Allocation of {} bytes is too large for the {} byte young generation",
+                size, capacity,
+            ),
+            Self::UnsupportedAlignment(align) => {
+                write!(f, "Objects aligned to {} bytes are not supported", align)
+            }
+            Self::GenerationFull { size, generation } => write!(
+                f,
+                "Allocation of {} bytes doesn't fit in tenured generation {}",
+                size, generation,
+            ),
+            Self::OutOfMemory { size } => write!(
+                f,
+                "Allocation of {} bytes doesn't fit in the non-collecting heap",
+                size,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OptionsError {
+    /// The old generation can't hold every survivor of a full young generation
+    UndersizedOldGeneration { young: usize, old: usize },
+    /// The backing alignment isn't a power of two or is too small for objects
+    InvalidBackingAlign(usize),
+    /// The heap needs at least two generations and at most `MAX_GENERATIONS`
+    InvalidGenerationCount(usize),
+    /// The pocket sizes are empty, too many, zero or not strictly ascending
+    InvalidPocketSizes,
+    /// The young generation is empty or the generations are too large to add up
+    InvalidHeapSize,
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UndersizedOldGeneration { young, old } => write!(
+                f,
+                "The {} byte old generation is smaller than the {} byte young generation",
+                old, young,
+            ),
+            Self::InvalidBackingAlign(align) => {
+                write!(f, "{} is not a valid backing alignment", align)
+            }
+            Self::InvalidGenerationCount(count) => {
+                write!(f, "A heap can't have {} generations", count)
+            }
+            Self::InvalidHeapSize => write!(
+                f,
+                "The young generation must be non-empty and the heap must fit in the address space",
+            ),
+            Self::InvalidPocketSizes => write!(
+                f,
+                "Pocket sizes must be between 1 and {} non-zero, strictly ascending sizes",
+                crate::MAX_POCKETS,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HeapCreateError {
+    /// The heap's options are invalid
+    InvalidOptions(OptionsError),
+    /// The heap's size and alignment don't make a valid allocation layout
+    InvalidLayout { size: usize, align: usize },
+    /// The heap's memory couldn't be allocated
+    AllocationFailed { size: usize },
+    /// The heap is laid out over memory it doesn't own or holds objects that
+    /// are pinned in place, so it can't be moved
+    FixedRegion,
+}
+
+impl From<OptionsError> for HeapCreateError {
+    fn from(err: OptionsError) -> Self {
+        Self::InvalidOptions(err)
+    }
+}
+
+impl fmt::Display for HeapCreateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidOptions(err) => write!(f, "Invalid heap options: {}", err),
+            Self::InvalidLayout { size, align } => write!(
+                f,
+                "A {} byte heap aligned to {} bytes can't be allocated",
+                size, align,
+            ),
+            Self::AllocationFailed { size } => {
+                write!(f, "Failed to allocate {} bytes for the heap", size)
+            }
+            Self::FixedRegion => write!(f, "The heap can't be moved"),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::memory::{AllocId, HeapPointer};
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::time::Instant;
+
+/// Fired when a surviving object is copied into an older generation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PromoteEvent {
+    pub id: AllocId,
+    /// The size of the object
+    pub size: usize,
+    /// The size of the pocket the object was promoted into
+    pub pocket_size: usize,
+    /// The tenured generation the object was promoted into, numbered from 1
+    pub generation: u8,
+}
+
+/// Fired when compaction moves an object within its tenured generation, so that
+/// anything holding the object's old address can be pointed at the new one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RelocateEvent {
+    pub id: AllocId,
+    pub from: HeapPointer,
+    pub to: HeapPointer,
+}
+
+/// The kind of collection a [`PauseEvent`] records
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PauseKind {
+    Scavenge,
+    Major,
+    /// A major collection that was forced to compact, from [`BumpHeap::compact_now`](crate::BumpHeap::compact_now)
+    Compaction,
+}
+
+/// A collection, as recorded in [`BumpHeap::recent_events`](crate::BumpHeap::recent_events)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PauseEvent {
+    pub kind: PauseKind,
+    pub bytes_reclaimed: usize,
+    /// When the collection finished, relative to when the heap was created
+    pub timestamp: Duration,
+}
+
+/// Keeps the last `capacity` events, in order
+///
+/// Every event is written twice, `capacity` slots apart, so the most recent
+/// events are always a contiguous slice of `events`
+#[derive(Debug, Clone)]
+pub(crate) struct EventRing {
+    events: Vec<PauseEvent>,
+    capacity: usize,
+    next: usize,
+    len: usize,
+    created: Instant,
+}
+
+impl EventRing {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let placeholder = PauseEvent {
+            kind: PauseKind::Scavenge,
+            bytes_reclaimed: 0,
+            timestamp: Duration::from_secs(0),
+        };
+
+        Self {
+            events: vec![placeholder; capacity * 2],
+            capacity,
+            next: 0,
+            len: 0,
+            created: Instant::now(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, kind: PauseKind, bytes_reclaimed: usize) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let event = PauseEvent {
+            kind,
+            bytes_reclaimed,
+            timestamp: self.created.elapsed(),
+        };
+        self.events[self.next] = event;
+        self.events[self.next + self.capacity] = event;
+
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+    }
+
+    /// The recorded events, oldest first
+    pub(crate) fn events(&self) -> &[PauseEvent] {
+        if self.len < self.capacity {
+            &self.events[..self.len]
+        } else {
+            &self.events[self.next..self.next + self.capacity]
+        }
+    }
+}
@@ -1,135 +1,716 @@
-use crate::memory::HeapPointer;
-use alloc::vec::Vec;
+use crate::{
+    error::OptionsError,
+    memory::{self, HeapPointer},
+    rooted::HeapValue,
+};
+use alloc::{vec, vec::Vec};
+use core::{marker::PhantomData, mem};
+
+/// The most sizes a [`PocketSizes`] table can hold
+pub const MAX_POCKETS: usize = 8;
+
+/// The most freed blocks a pocket keeps room for once it's been emptied, so that
+/// a fragmented phase doesn't hold on to its free lists' memory forever
+const RETAINED_POCKET_CAPACITY: usize = 64;
+
+/// The block sizes a free list hands out and sorts freed blocks into, smallest first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PocketSizes {
+    sizes: [usize; MAX_POCKETS],
+    len: usize,
+}
+
+impl PocketSizes {
+    /// # Panics
+    ///
+    /// Panics if the sizes aren't a valid table, see [`PocketSizes::try_new`]
+    pub fn new(sizes: &[usize]) -> Self {
+        match Self::try_new(sizes) {
+            Ok(table) => table,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Creates a table from between one and [`MAX_POCKETS`] sizes, which must be
+    /// non-zero and strictly ascending so that every size has exactly one pocket
+    pub fn try_new(sizes: &[usize]) -> Result<Self, OptionsError> {
+        let ascending = sizes.windows(2).all(|pair| pair[0] < pair[1]);
+        if sizes.is_empty() || sizes.len() > MAX_POCKETS || sizes[0] == 0 || !ascending {
+            return Err(OptionsError::InvalidPocketSizes);
+        }
+
+        let mut table = [0; MAX_POCKETS];
+        table[..sizes.len()].copy_from_slice(sizes);
+
+        Ok(Self {
+            sizes: table,
+            len: sizes.len(),
+        })
+    }
+
+    pub fn as_slice(&self) -> &[usize] {
+        &self.sizes[..self.len]
+    }
+
+    /// The index of the smallest pocket that can hold `size` bytes
+    pub(crate) fn class_of(&self, size: usize) -> Option<usize> {
+        self.as_slice().iter().position(|&pocket| pocket >= size)
+    }
+}
+
+impl Default for PocketSizes {
+    fn default() -> Self {
+        Self::new(DEFAULT_POCKETS)
+    }
+}
+
+/// A free region of a tenured generation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FreeBlock {
+    pub ptr: HeapPointer,
+    pub size: usize,
+    /// The pocket the block is parked in, or `None` for freed blocks too large
+    /// for any pocket and for the untouched memory after the bump frontier
+    pub pocket: Option<usize>,
+}
+
+/// Fails to compile if a `T` is too large for every pocket of the default pocket
+/// table, meaning that tenured `T`s would be allocated as large blocks instead
+///
+/// ```
+/// ballast::assert_fits_pocket::<[u8; 1024]>();
+/// ```
+///
+/// ```compile_fail
+/// ballast::assert_fits_pocket::<[u8; 1024 * 64]>();
+/// ```
+pub const fn assert_fits_pocket<T: 'static>() {
+    struct Check<T>(PhantomData<T>);
+
+    impl<T: 'static> Check<T> {
+        const FITS: () = assert!(
+            mem::size_of::<HeapValue<T>>() <= HUGE_POCKET,
+            "The type is too large for any pocket",
+        );
+    }
+
+    #[allow(clippy::let_unit_value)]
+    let () = Check::<T>::FITS;
+}
 
 #[derive(Debug, Clone)]
 pub struct FreeList {
     pub(crate) start: HeapPointer,
     pub(crate) current: HeapPointer,
     pub(crate) size: usize,
-    pub(crate) pockets: [Vec<HeapPointer>; NUMBER_MEMORY_POCKETS],
+    pub(crate) pocket_sizes: PocketSizes,
+    /// The freed blocks of each pocket size, indexed like `pocket_sizes`
+    pub(crate) pockets: Vec<Vec<HeapPointer>>,
+    /// Freed blocks too large for any pocket, as `(start, size)`
+    pub(crate) large: Vec<(HeapPointer, usize)>,
 }
 
 impl FreeList {
-    pub const fn new(start: HeapPointer, size: usize) -> Self {
+    // Heaps always build their free lists with their own pocket sizes, so only
+    // the tests use the default ones
+    #[allow(dead_code)]
+    pub fn new(start: HeapPointer, size: usize) -> Self {
+        Self::with_pockets(start, size, PocketSizes::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `start + size` wraps around the address space
+    pub fn with_pockets(start: HeapPointer, size: usize, pocket_sizes: PocketSizes) -> Self {
+        assert!(
+            start.checked_offset(size).is_some(),
+            "A {} byte region at {:p} wraps around the address space",
+            size,
+            start,
+        );
+
         Self {
             start,
             current: start,
             size,
-            pockets: create_memory_pocket_array(),
+            pocket_sizes,
+            pockets: vec![Vec::new(); pocket_sizes.as_slice().len()],
+            large: Vec::new(),
+        }
+    }
+
+    /// The size of the block handed out for an allocation of `size` bytes, which
+    /// is either a pocket or, for anything larger, `size` rounded up to `MAX_ALIGN`
+    pub fn block_size(&self, size: usize) -> usize {
+        match self.pocket_sizes.class_of(size) {
+            Some(class) => self.pocket_sizes.as_slice()[class],
+            None => size + memory::padding_for(size, memory::MAX_ALIGN),
         }
     }
 
     pub fn alloc(&mut self, size: usize) -> Option<(HeapPointer, usize)> {
-        let pocket = PocketSize::next_up(size)?;
-        if self.current.offset(pocket.size()) < self.start.offset(self.size) {
-            let ptr = self.current;
-            self.current += pocket.size();
-
-            Some((ptr, pocket.size()))
-        } else if let Some(ptr) = self.pockets[pocket.index()].pop() {
-            Some((ptr, pocket.size()))
+        let class = match self.pocket_sizes.class_of(size) {
+            Some(class) => class,
+            None => return self.alloc_large(self.block_size(size)),
+        };
+        let pocket_size = self.pocket_sizes.as_slice()[class];
+
+        // Reuse freed blocks before touching fresh memory
+        if let Some(ptr) = self.pockets[class].pop() {
+            return Some((ptr, pocket_size));
+        }
+
+        let ptr =
+            self.current + memory::padding_for(self.current.as_usize(), pocket_align(pocket_size));
+
+        if ptr.offset(pocket_size) <= self.start.offset(self.size) {
+            self.current = ptr + pocket_size;
+
+            Some((ptr, pocket_size))
         } else {
             None
         }
     }
-}
 
-macro_rules! pocket {
-    ($name1:tt: $variant1:tt = $bytes1:expr $( , $name:tt: $variant:tt = $bytes:expr )* ) => {
-        const KILOBYTE: usize = 1024;
+    /// Allocates a block for `size` bytes whose address is a multiple of `align`,
+    /// which can be more than blocks are normally aligned to
+    ///
+    /// Freed blocks are only reused if they happen to be aligned, otherwise the
+    /// block is bumped and the padding in front of it is parked as a large free
+    /// block, which only compaction can reuse
+    pub fn alloc_aligned(&mut self, size: usize, align: usize) -> Option<(HeapPointer, usize)> {
+        if align <= memory::MAX_ALIGN {
+            return self.alloc(size);
+        }
 
-        const $name1: usize = $bytes1;
-        $( const $name: usize = $bytes; )*
+        let block = self.block_size(size);
+        if let Some(class) = self.pocket_sizes.class_of(size) {
+            let pocket = &mut self.pockets[class];
+            if let Some(index) = pocket
+                .iter()
+                .rposition(|ptr| ptr.as_usize().is_multiple_of(align))
+            {
+                return Some((pocket.swap_remove(index), block));
+            }
+        }
 
-        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-        #[repr(u8)]
-        pub enum PocketSize {
-            $variant1 = 0,
-            $( $variant ),*
+        let ptr = self.current + memory::padding_for(self.current.as_usize(), align);
+        if ptr.offset(block) > self.start.offset(self.size) {
+            return None;
         }
 
-        impl PocketSize {
-            #[inline]
-            pub fn from_usize(pocket: usize) -> Self {
-                match pocket {
-                    0 => PocketSize::$variant1,
+        if ptr > self.current {
+            self.large
+                .push((self.current, ptr.offset_from(self.current)));
+        }
+        self.current = ptr + block;
 
-                    $( var if var == PocketSize::$variant.index() => PocketSize::$variant, )*
+        Some((ptr, block))
+    }
 
-                    var => panic!("Unrecognized pocket variant: {}", var),
-                }
+    /// Allocates a block too large for any pocket, first fit from the freed large
+    /// blocks and bumped otherwise
+    fn alloc_large(&mut self, size: usize) -> Option<(HeapPointer, usize)> {
+        if let Some(index) = self.large.iter().position(|&(_, free)| free >= size) {
+            let (ptr, free) = self.large.swap_remove(index);
+            if free > size {
+                self.large.push((ptr + size, free - size));
             }
 
-            #[inline]
-            pub fn from_pocket_size(size: usize) -> Self {
-                if size == $name1 {
-                    PocketSize::$variant1
-                } $( else if size == $name {
-                    PocketSize::$variant
-                } )* else {
-                    panic!("Unrecognized pocket size: {}", size);
-                }
-            }
+            return Some((ptr, size));
+        }
 
-            #[inline]
-            pub fn next_up(size: usize) -> Option<PocketSize> {
-                assert!(size >= $name1);
-
-                if size <= $name1 {
-                    Some(PocketSize::$variant1)
-                } $( else if size <= $name {
-                        Some(PocketSize::$variant)
-                } )* else {
-                    None
-                }
-            }
+        let ptr = self.current + memory::padding_for(self.current.as_usize(), memory::MAX_ALIGN);
+        if ptr.offset(size) <= self.start.offset(self.size) {
+            self.current = ptr + size;
 
-            #[inline]
-            pub fn next_down(size: usize) -> Option<PocketSize> {
-                assert!(size >= $name1);
-
-                if size < $name1 {
-                    Some(PocketSize::$variant1)
-                } $( else if size < $name {
-                        Some(PocketSize::$variant)
-                } )* else {
-                    None
-                }
-            }
+            Some((ptr, size))
+        } else {
+            None
+        }
+    }
 
-            pub const fn index(&self) -> usize {
-                *self as u8 as usize
-            }
+    /// The size of the largest block `alloc` could currently hand out
+    pub fn largest_free_block(&self) -> usize {
+        let end = self.start.as_usize() + self.size;
+        let frontier = self.current.as_usize()
+            + memory::padding_for(self.current.as_usize(), memory::MAX_ALIGN);
+        let fresh = end.saturating_sub(frontier);
 
-            pub const fn size(&self) -> usize {
-                MEMORY_POCKETS[self.index()]
-            }
+        let pocket = self
+            .pockets
+            .iter()
+            .zip(self.pocket_sizes.as_slice())
+            .filter(|(pocket, _)| !pocket.is_empty())
+            .map(|(_, &size)| size)
+            .max()
+            .unwrap_or(0);
+        let large = self.large.iter().map(|&(_, size)| size).max().unwrap_or(0);
 
-            #[inline]
-            pub fn reclaim(size: usize, ptr: HeapPointer, list: &mut FreeList) {
-                let pocket = PocketSize::from_pocket_size(size);
-                list.pockets[pocket.index()].push(ptr);
-            }
+        fresh.max(pocket).max(large)
+    }
+
+    /// Points the list at `start`, for when the memory it manages has been copied there
+    pub(crate) fn rebase(&mut self, start: HeapPointer) {
+        let old_start = self.start;
+        let moved = |ptr: HeapPointer| start + ptr.offset_from(old_start);
+
+        for ptr in self.pockets.iter_mut().flatten() {
+            *ptr = moved(*ptr);
+        }
+        for (ptr, _) in self.large.iter_mut() {
+            *ptr = moved(*ptr);
         }
+        self.current = moved(self.current);
+        self.start = start;
+    }
+
+    /// Every free block, sorted by address, ending with the untouched memory after
+    /// the bump frontier if there is any
+    pub fn free_blocks(&self) -> impl Iterator<Item = FreeBlock> {
+        let parked = self
+            .pockets
+            .iter()
+            .zip(self.pocket_sizes.as_slice())
+            .flat_map(|(pocket, &size)| {
+                pocket.iter().map(move |&ptr| FreeBlock {
+                    ptr,
+                    size,
+                    pocket: Some(size),
+                })
+            });
+        let large = self.large.iter().map(|&(ptr, size)| FreeBlock {
+            ptr,
+            size,
+            pocket: None,
+        });
 
-        const NUMBER_MEMORY_POCKETS: usize = [ (), $( pocket!(@replace_with_unit $name) ),* ].len();
-        const MEMORY_POCKETS: [usize; NUMBER_MEMORY_POCKETS] = [ $name1 $( , $name )* ];
+        let mut blocks: Vec<FreeBlock> = parked.chain(large).collect();
+        blocks.sort_unstable_by_key(|block| block.ptr);
 
-        const fn create_memory_pocket_array() -> [Vec<HeapPointer>; NUMBER_MEMORY_POCKETS] {
-            [ Vec::new(), $( { pocket!(@replace_with_unit $name); Vec::new() } ),* ]
+        let end = self.start + self.size;
+        if self.current < end {
+            blocks.push(FreeBlock {
+                ptr: self.current,
+                size: end.offset_from(self.current),
+                pocket: None,
+            });
+        }
+
+        blocks.into_iter()
+    }
+
+    /// Resizes a block without moving it, which is possible when the new size
+    /// needs the same pocket or when the block is the last one handed out and
+    /// there's room after it, returning whether the block was resized
+    pub fn resize_in_place(&mut self, ptr: HeapPointer, old_size: usize, new_size: usize) -> bool {
+        let (old, new) = match (
+            self.pocket_sizes.class_of(old_size),
+            self.pocket_sizes.class_of(new_size),
+        ) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return false,
+        };
+
+        if old == new {
+            return true;
+        }
+
+        let (old, new) = (
+            self.pocket_sizes.as_slice()[old],
+            self.pocket_sizes.as_slice()[new],
+        );
+        if new > old
+            && ptr + old == self.current
+            && ptr.as_usize().is_multiple_of(pocket_align(new))
+            && ptr.offset(new) <= self.start.offset(self.size)
+        {
+            self.current = ptr + new;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The number of bytes currently handed out
+    pub fn used(&self) -> usize {
+        let parked: usize = self
+            .pockets
+            .iter()
+            .zip(self.pocket_sizes.as_slice())
+            .map(|(pocket, size)| pocket.len() * size)
+            .sum();
+        let parked_large: usize = self.large.iter().map(|&(_, size)| size).sum();
+
+        self.current.offset_from(self.start) - parked - parked_large
+    }
+
+    /// Forgets every freed block, shrinking the pockets back down if they grew
+    /// large, for when the memory they pointed to is about to be reused wholesale
+    pub(crate) fn forget_freed_blocks(&mut self) {
+        for pocket in self.pockets.iter_mut() {
+            pocket.clear();
+            pocket.shrink_to(RETAINED_POCKET_CAPACITY);
         }
-    };
 
-    (@replace_with_unit $( $t:tt ),* ) => {
-        ()
+        self.large.clear();
+        self.large.shrink_to(RETAINED_POCKET_CAPACITY);
+    }
+
+    /// Frees the whole region, leaving the list as it was when it was created
+    ///
+    /// Every block it's handed out is forgotten, for when whatever was in them
+    /// has been moved out or is about to be written over
+    pub fn reset(&mut self) {
+        self.current = self.start;
+        self.forget_freed_blocks();
+    }
+
+    /// Returns a block previously handed out by `alloc` for `size` bytes
+    pub fn dealloc(&mut self, ptr: HeapPointer, size: usize) {
+        debug_assert!(
+            ptr >= self.start && ptr < self.current,
+            "Deallocated a pointer outside of the free list: {:p}",
+            ptr,
+        );
+
+        let class = match self.pocket_sizes.class_of(size) {
+            Some(class) => class,
+            None => {
+                debug_assert!(
+                    !self.large.iter().any(|&(free, _)| free == ptr),
+                    "Double free of {:p}",
+                    ptr,
+                );
+
+                let block = self.block_size(size);
+                self.large.push((ptr, block));
+                return;
+            }
+        };
+
+        debug_assert!(
+            !self.pockets[class].contains(&ptr),
+            "Double free of {:p}",
+            ptr,
+        );
+
+        self.pockets[class].push(ptr);
+    }
+}
+
+/// The alignment of a pocket's blocks, the largest power of two that's no bigger
+/// than the pocket (since that's the largest alignment anything that fits in it
+/// can have) capped at `MAX_ALIGN`
+fn pocket_align(pocket_size: usize) -> usize {
+    let align = 1 << (usize::BITS - 1 - pocket_size.leading_zeros());
+    align.min(memory::MAX_ALIGN)
+}
+
+macro_rules! pocket {
+    ($( $name:ident = $bytes:expr ),* $(,)?) => {
+        $( const $name: usize = $bytes; )*
+
+        /// The pocket table used unless a heap is given another one
+        const DEFAULT_POCKETS: &[usize] = &[ $( $name ),* ];
     };
 }
 
+const KILOBYTE: usize = 1024;
+
 pocket! {
-    MINI_POCKET:   Mini   = 1,
-    TINY_POCKET:   Tiny   = 32,
-    SMALL_POCKET:  Small  = 128,
-    MEDIUM_POCKET: Medium = KILOBYTE * 2,
-    LARGE_POCKET:  Large  = KILOBYTE * 8,
-    HUGE_POCKET:   Huge   = KILOBYTE * 32
+    MINI_POCKET   = 1,
+    TINY_POCKET   = 32,
+    SMALL_POCKET  = 128,
+    MEDIUM_POCKET = KILOBYTE * 2,
+    LARGE_POCKET  = KILOBYTE * 8,
+    HUGE_POCKET   = KILOBYTE * 32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc_dealloc_reuses_address() {
+        let mut backing = vec![0u8; KILOBYTE * 4];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let (ptr, pocket) = list.alloc(24).unwrap();
+        assert_eq!(pocket, TINY_POCKET);
+
+        list.dealloc(ptr, 24);
+        assert_eq!(list.alloc(24), Some((ptr, pocket)));
+    }
+
+    #[test]
+    #[should_panic(expected = "wraps around the address space")]
+    fn wrapping_region() {
+        FreeList::new(HeapPointer::new(usize::MAX - KILOBYTE), KILOBYTE * 4);
+    }
+
+    #[test]
+    fn fills_to_capacity() {
+        let len = KILOBYTE * 4;
+        let mut backing = vec![0u8; len + memory::MAX_ALIGN];
+        let start = HeapPointer::from(backing.as_mut_ptr());
+        let start = start + memory::padding_for(start.as_usize(), memory::MAX_ALIGN);
+        let end = start + len;
+
+        // The last pocket ends exactly at the end of the list
+        let mut list = FreeList::new(start, len);
+        for _ in 0..len / TINY_POCKET {
+            assert!(list.alloc(24).is_some());
+        }
+        assert_eq!(list.current, end);
+        assert_eq!(list.largest_free_block(), 0);
+        assert_eq!(list.alloc(24), None);
+
+        // Large blocks can also take the very last bytes, but not one more
+        let mut list = FreeList::new(start, len);
+        assert_eq!(list.largest_free_block(), len);
+        assert_eq!(list.alloc_large(len + 1), None);
+        assert_eq!(list.alloc_large(len), Some((start, len)));
+        assert_eq!(list.alloc_large(memory::MAX_ALIGN), None);
+    }
+
+    #[test]
+    fn resize_in_place() {
+        let mut backing = vec![0u8; KILOBYTE * 4];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let (first, _) = list.alloc(24).unwrap();
+        assert!(list.resize_in_place(first, 24, 30));
+        assert!(list.resize_in_place(first, 30, 100));
+        assert_eq!(list.current, first + SMALL_POCKET);
+
+        let (second, _) = list.alloc(24).unwrap();
+        assert!(!list.resize_in_place(first, 100, 200));
+        assert!(list.resize_in_place(second, 24, 100));
+    }
+
+    #[test]
+    fn large_blocks() {
+        let mut backing = vec![0u8; KILOBYTE * 128];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let size = HUGE_POCKET + 100;
+        let (ptr, block) = list.alloc(size).unwrap();
+        assert_eq!(block, list.block_size(size));
+        assert_eq!(block % memory::MAX_ALIGN, 0);
+        assert!(block >= size);
+        assert_eq!(list.used(), block + padding(&list, ptr));
+
+        list.dealloc(ptr, size);
+        assert!(list.largest_free_block() >= block);
+
+        // A smaller large block is carved out of the freed one
+        let (reused, smaller) = list.alloc(HUGE_POCKET + 1).unwrap();
+        assert_eq!(reused, ptr);
+        assert!(smaller < block);
+        assert_eq!(list.large, vec![(ptr + smaller, block - smaller)]);
+    }
+
+    #[test]
+    fn custom_pockets() {
+        let mut backing = vec![0u8; KILOBYTE * 4];
+        let mut list = FreeList::with_pockets(
+            HeapPointer::from(backing.as_mut_ptr()),
+            backing.len(),
+            PocketSizes::new(&[12, 48, 256]),
+        );
+
+        assert_eq!(list.alloc(1).map(|(_, pocket)| pocket), Some(12));
+        let (ptr, pocket) = list.alloc(40).unwrap();
+        assert_eq!(pocket, 48);
+        assert_eq!(ptr.as_usize() % 16, 0);
+        assert_eq!(list.block_size(200), 256);
+        assert_eq!(list.block_size(257), 272);
+
+        list.dealloc(ptr, 40);
+        assert_eq!(list.alloc(33), Some((ptr, 48)));
+    }
+
+    #[test]
+    fn invalid_pocket_sizes() {
+        let invalid: &[&[usize]] = &[
+            &[],
+            &[256, 48, 12],
+            &[12, 48, 48, 256],
+            &[0, 12],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9],
+        ];
+        for &sizes in invalid {
+            assert_eq!(
+                PocketSizes::try_new(sizes),
+                Err(OptionsError::InvalidPocketSizes),
+                "{:?} was accepted",
+                sizes,
+            );
+        }
+
+        assert_eq!(
+            PocketSizes::try_new(DEFAULT_POCKETS).unwrap().as_slice(),
+            DEFAULT_POCKETS
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    fn descending_pocket_sizes() {
+        PocketSizes::new(&[48, 12]);
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let mut backing = vec![0u8; KILOBYTE * 4];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        list.alloc(1).unwrap();
+        let (ptr, block) = list.alloc_aligned(64, 256).unwrap();
+        assert_eq!((ptr.as_usize() % 256, block), (0, SMALL_POCKET));
+        assert_eq!(list.used(), 1 + SMALL_POCKET);
+
+        list.dealloc(ptr, 64);
+        assert_eq!(list.alloc_aligned(64, 256), Some((ptr, block)));
+    }
+
+    #[test]
+    fn reset() {
+        let mut backing = vec![0u8; KILOBYTE * 16];
+        let start = HeapPointer::from(backing.as_mut_ptr());
+        let sizes = [24, 100, 24, 3000, 8, 600];
+
+        let fresh: Vec<_> = {
+            let mut list = FreeList::new(start, KILOBYTE * 16);
+            sizes.iter().map(|&size| list.alloc(size)).collect()
+        };
+
+        let mut list = FreeList::new(start, KILOBYTE * 16);
+        for &size in &sizes[..4] {
+            let (ptr, _) = list.alloc(size).unwrap();
+            list.dealloc(ptr, size);
+        }
+        list.alloc(KILOBYTE * 4).unwrap();
+
+        list.reset();
+        assert_eq!(list.used(), 0);
+        assert_eq!(list.current, start);
+        assert!(list.pockets.iter().all(Vec::is_empty) && list.large.is_empty());
+        assert_eq!(
+            sizes
+                .iter()
+                .map(|&size| list.alloc(size))
+                .collect::<Vec<_>>(),
+            fresh
+        );
+    }
+
+    #[test]
+    fn forget_freed_blocks() {
+        let mut backing = vec![0u8; KILOBYTE * 16];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let blocks: Vec<HeapPointer> = (0..400).map(|_| list.alloc(24).unwrap().0).collect();
+        for &block in &blocks {
+            list.dealloc(block, 24);
+        }
+        assert!(list.pockets[1].capacity() >= blocks.len());
+
+        list.forget_freed_blocks();
+        assert!(list.pockets.iter().all(Vec::is_empty));
+        assert!(list.pockets[1].capacity() <= RETAINED_POCKET_CAPACITY);
+        assert_eq!(list.free_blocks().count(), 1);
+    }
+
+    #[test]
+    fn free_blocks() {
+        let mut backing = vec![0u8; KILOBYTE * 128];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let (tiny, _) = list.alloc(24).unwrap();
+        let (small, _) = list.alloc(100).unwrap();
+        let (_kept, _) = list.alloc(24).unwrap();
+        let (large, large_size) = list.alloc(HUGE_POCKET + 1).unwrap();
+        let (other_tiny, _) = list.alloc(1).unwrap();
+
+        list.dealloc(other_tiny, 1);
+        list.dealloc(small, 100);
+        list.dealloc(large, HUGE_POCKET + 1);
+        list.dealloc(tiny, 24);
+
+        let end = list.start + list.size;
+        let blocks: Vec<FreeBlock> = list.free_blocks().collect();
+        assert_eq!(
+            blocks,
+            vec![
+                FreeBlock {
+                    ptr: tiny,
+                    size: TINY_POCKET,
+                    pocket: Some(TINY_POCKET),
+                },
+                FreeBlock {
+                    ptr: small,
+                    size: SMALL_POCKET,
+                    pocket: Some(SMALL_POCKET),
+                },
+                FreeBlock {
+                    ptr: large,
+                    size: large_size,
+                    pocket: None,
+                },
+                FreeBlock {
+                    ptr: other_tiny,
+                    size: MINI_POCKET,
+                    pocket: Some(MINI_POCKET),
+                },
+                FreeBlock {
+                    ptr: list.current,
+                    size: end.as_usize() - list.current.as_usize(),
+                    pocket: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rebase() {
+        let mut backing = vec![0u8; KILOBYTE * 128];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), KILOBYTE * 64);
+
+        let (small, _) = list.alloc(24).unwrap();
+        let (large, _) = list.alloc(HUGE_POCKET + 1).unwrap();
+        list.dealloc(small, 24);
+        list.dealloc(large, HUGE_POCKET + 1);
+        let blocks: Vec<FreeBlock> = list.free_blocks().collect();
+
+        let start = list.start + KILOBYTE * 64;
+        list.rebase(start);
+        let moved: Vec<FreeBlock> = list
+            .free_blocks()
+            .map(|block| FreeBlock {
+                ptr: block.ptr - KILOBYTE * 64,
+                ..block
+            })
+            .collect();
+
+        assert_eq!(list.start, start);
+        assert_eq!(moved, blocks);
+    }
+
+    fn padding(list: &FreeList, first: HeapPointer) -> usize {
+        first.as_usize() - list.start.as_usize()
+    }
+
+    #[test]
+    #[should_panic(expected = "Double free")]
+    #[cfg(debug_assertions)]
+    fn double_free() {
+        let mut backing = vec![0u8; KILOBYTE * 4];
+        let mut list = FreeList::new(HeapPointer::from(backing.as_mut_ptr()), backing.len());
+
+        let (ptr, _) = list.alloc(24).unwrap();
+        list.dealloc(ptr, 24);
+        list.dealloc(ptr, 24);
+    }
 }
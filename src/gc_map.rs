@@ -0,0 +1,305 @@
+use crate::{
+    bump_heap::BumpHeap,
+    memory::AllocId,
+    rooted::Rooted,
+    trace::{Trace, Tracer},
+    weak::Weak,
+};
+use core::{
+    any::Any,
+    hash::{BuildHasher, Hash},
+};
+use std::collections::{hash_map::RandomState, HashMap};
+
+/// A hash map holding heap objects as its values
+///
+/// The map owns its values' handles, so they're kept alive for as long as
+/// they're in it, and its [`Trace`] impl reports them. Like [`HashMap`] it
+/// hashes with [`RandomState`] unless it's given another hasher
+#[derive(Debug)]
+pub struct GcHashMap<K, V: Sized + Any, S = RandomState> {
+    map: HashMap<K, Rooted<V>, S>,
+}
+
+impl<K: Hash + Eq, V: Sized + Any> GcHashMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V: Sized + Any, S: BuildHasher> GcHashMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hasher),
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: Rooted<V>) -> Option<Rooted<V>> {
+        self.map.insert(key, value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&Rooted<V>> {
+        self.map.get(key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Rooted<V>> {
+        self.map.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V: Sized + Any, S: BuildHasher + Default> Default for GcHashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Trace, V: Sized + Any, S> Trace for GcHashMap<K, V, S> {
+    fn trace(&self, tracer: &mut Tracer) {
+        for (key, value) in &self.map {
+            key.trace(tracer);
+            tracer.edge(value);
+        }
+    }
+}
+
+/// A hash map whose values are held by [`Weak`] handles, so it doesn't keep
+/// them alive
+///
+/// Entries whose values have been reclaimed are treated as missing and are
+/// dropped by [`WeakValueMap::prune`]
+#[derive(Debug)]
+pub struct WeakValueMap<K, V: Sized + Any, S = RandomState> {
+    map: HashMap<K, Weak<V>, S>,
+}
+
+impl<K: Hash + Eq, V: Sized + Any> WeakValueMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V: Sized + Any, S: BuildHasher> WeakValueMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hasher),
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: &Rooted<V>) {
+        self.map.insert(key, Weak::new(value));
+    }
+
+    /// Borrows the value for `key` for as long as `heap` is borrowed, see
+    /// [`Weak::get`]
+    ///
+    /// # Safety
+    ///
+    /// See [`Weak::get`]
+    pub unsafe fn get<'a>(&'a self, heap: &'a BumpHeap, key: &K) -> Option<&'a V> {
+        self.map.get(key).and_then(|value| value.get(heap))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Weak<V>> {
+        self.map.remove(key)
+    }
+
+    /// Removes every entry whose value has been reclaimed, returning how many there were
+    pub fn prune(&mut self) -> usize {
+        let len = self.map.len();
+        self.map.retain(|_, value| !value.is_cleared());
+
+        len - self.map.len()
+    }
+
+    /// The number of entries, including ones whose values have been reclaimed
+    /// since the last [prune](WeakValueMap::prune)
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V: Sized + Any, S: BuildHasher + Default> Default for WeakValueMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Trace, V: Sized + Any, S> Trace for WeakValueMap<K, V, S> {
+    fn trace(&self, tracer: &mut Tracer) {
+        for key in self.map.keys() {
+            key.trace(tracer);
+        }
+    }
+}
+
+/// A hash map keyed by the identity of heap objects held by [`Weak`] handles,
+/// so it doesn't keep its keys alive
+///
+/// Entries whose keys have been reclaimed are treated as missing and are
+/// dropped by [`WeakKeyMap::prune`]
+#[derive(Debug)]
+pub struct WeakKeyMap<K: Sized + Any, V, S = RandomState> {
+    map: HashMap<AllocId, (Weak<K>, V), S>,
+}
+
+impl<K: Sized + Any, V> WeakKeyMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Sized + Any, V, S: BuildHasher> WeakKeyMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hasher),
+        }
+    }
+
+    pub fn insert(&mut self, key: &Rooted<K>, value: V) -> Option<V> {
+        self.map
+            .insert(key.id(), (Weak::new(key), value))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get(&self, key: &Rooted<K>) -> Option<&V> {
+        self.map.get(&key.id()).map(|(_, value)| value)
+    }
+
+    pub fn remove(&mut self, key: &Rooted<K>) -> Option<V> {
+        self.map.remove(&key.id()).map(|(_, value)| value)
+    }
+
+    /// Removes every entry whose key has been reclaimed, returning how many there were
+    pub fn prune(&mut self) -> usize {
+        let len = self.map.len();
+        self.map.retain(|_, (key, _)| !key.is_cleared());
+
+        len - self.map.len()
+    }
+
+    /// The number of entries, including ones whose keys have been reclaimed
+    /// since the last [prune](WeakKeyMap::prune)
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Sized + Any, V, S: BuildHasher + Default> Default for WeakKeyMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Sized + Any, V: Trace, S> Trace for WeakKeyMap<K, V, S> {
+    fn trace(&self, tracer: &mut Tracer) {
+        for (_, value) in self.map.values() {
+            value.trace(tracer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BumpHeap;
+
+    #[test]
+    fn strong_values_are_traced() {
+        let mut bump = BumpHeap::default();
+        let mut map = GcHashMap::new();
+
+        let value: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let id = value.id();
+        map.insert(10u32, value);
+        bump.scavenge();
+        bump.major();
+
+        assert_eq!(map.get(&10).map(|value| **value), Some(1));
+        let mut tracer = Tracer::new();
+        map.trace(&mut tracer);
+        assert_eq!(tracer.edges(), &[id]);
+
+        assert!(map.remove(&10).is_some());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn custom_hashers() {
+        use core::hash::BuildHasherDefault;
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut bump = BumpHeap::default();
+
+        let mut strong: GcHashMap<u32, usize, BuildHasherDefault<DefaultHasher>> =
+            GcHashMap::default();
+        let mut weak_values = WeakValueMap::with_hasher(RandomState::new());
+        let mut weak_keys: WeakKeyMap<usize, &str, BuildHasherDefault<DefaultHasher>> =
+            WeakKeyMap::default();
+
+        let key: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let value: Rooted<usize> = unsafe { bump.alloc_unchecked(2) };
+        weak_values.insert("value", &value);
+        weak_keys.insert(&key, "key");
+        strong.insert(3, value);
+        bump.scavenge();
+
+        assert_eq!(strong.get(&3).map(|value| **value), Some(2));
+        assert_eq!(unsafe { weak_values.get(&bump, &"value") }, Some(&2));
+        assert_eq!(weak_keys.get(&key), Some(&"key"));
+    }
+
+    #[test]
+    fn weak_values_are_pruned() {
+        let mut bump = BumpHeap::default();
+        let mut map = WeakValueMap::new();
+
+        let kept: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let dropped: Rooted<usize> = unsafe { bump.alloc_unchecked(2) };
+        map.insert("kept", &kept);
+        map.insert("dropped", &dropped);
+        bump.scavenge();
+
+        drop(dropped);
+        bump.major();
+
+        assert_eq!(unsafe { map.get(&bump, &"dropped") }, None);
+        assert_eq!(map.prune(), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(unsafe { map.get(&bump, &"kept") }, Some(&1));
+    }
+
+    #[test]
+    fn weak_keys_are_pruned() {
+        let mut bump = BumpHeap::default();
+        let mut map = WeakKeyMap::new();
+
+        let kept: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let dropped: Rooted<usize> = unsafe { bump.alloc_unchecked(2) };
+        map.insert(&kept, "kept");
+        map.insert(&dropped, "dropped");
+
+        drop(dropped);
+        bump.scavenge();
+
+        assert_eq!(map.prune(), 1);
+        assert_eq!(map.get(&kept), Some(&"kept"));
+        assert_eq!(map.remove(&kept), Some("kept"));
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{
+    bump_heap::BumpHeap,
+    error::AllocError,
+    memory::AllocId,
+    rooted::Rooted,
+    trace::{Trace, Tracer},
+    weak::Weak,
+};
+use core::{any::Any, mem, ptr, slice};
+
+/// The capacity a vector grows to the first time it's pushed to
+const MIN_CAPACITY: usize = 4;
+
+/// A growable array whose items live in the heap
+///
+/// The items are stored in a single heap object that's grown in place when
+/// there's room directly after it and moved otherwise, so growing only copies
+/// the items when it has to. The vector owns the object's handle and drops its
+/// items when it's dropped. The heap only ever drops the object itself, whose
+/// destructor leaves the items alone, so [freeing](BumpHeap::free_region) or
+/// [clearing](BumpHeap::clear) the heap under a vector leaks them
+///
+/// References into the vector, like the ones [`GcVec::as_slice`] hands out,
+/// can't be held across anything that collects. [`GcVec::iter_copied`] and
+/// [`GcVec::iter_weak`] can be, since they look each item up again as they reach
+/// it and only yield values that stay valid when the items move
+#[derive(Debug)]
+pub struct GcVec<T: Any> {
+    storage: Rooted<VecStorage<T>>,
+    capacity: usize,
+}
+
+/// The heap object holding a [`GcVec`]'s items, which are stored directly after
+/// its length like a [`HeapSlice`](crate::HeapSlice)'s
+#[derive(Debug)]
+#[repr(C)]
+struct VecStorage<T> {
+    len: usize,
+    items: [T; 0],
+}
+
+impl<T: Any> GcVec<T> {
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector can't be allocated
+    pub unsafe fn new(heap: &mut BumpHeap) -> Self {
+        match Self::try_with_capacity(heap, 0) {
+            Ok(vec) => vec,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Allocates a vector with room for `capacity` items
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_with_capacity(
+        heap: &mut BumpHeap,
+        capacity: usize,
+    ) -> Result<Self, AllocError> {
+        let mut vec = Self {
+            storage: heap.try_alloc_unchecked(VecStorage { len: 0, items: [] })?,
+            capacity: 0,
+        };
+        vec.try_reserve(heap, capacity)?;
+
+        Ok(vec)
+    }
+
+    /// Makes room for at least `additional` more items, growing the vector's
+    /// heap object if it has to
+    ///
+    /// # Safety
+    ///
+    /// `heap` must be the heap the vector was allocated in, see [`BumpHeap::try_alloc_unchecked`]
+    /// for the rest
+    pub unsafe fn try_reserve(
+        &mut self,
+        heap: &mut BumpHeap,
+        additional: usize,
+    ) -> Result<(), AllocError> {
+        let required = self
+            .len()
+            .checked_add(additional)
+            .expect("GcVec capacity overflow");
+        if required <= self.capacity {
+            return Ok(());
+        }
+
+        let capacity = required.max(self.capacity * 2).max(MIN_CAPACITY);
+        let bytes = (capacity - self.capacity)
+            .checked_mul(mem::size_of::<T>())
+            .expect("GcVec capacity overflow");
+        heap.grow_object(&mut self.storage, bytes)?;
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    /// Appends an item, growing the vector if it's full
+    ///
+    /// # Safety
+    ///
+    /// See [`GcVec::try_reserve`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector can't be grown
+    pub unsafe fn push(&mut self, heap: &mut BumpHeap, value: T) {
+        if let Err(err) = self.try_reserve(heap, 1) {
+            panic!("{}", err);
+        }
+
+        let storage = self.storage_mut();
+        storage.items.as_mut_ptr().add(storage.len).write(value);
+        storage.len += 1;
+    }
+
+    /// Removes the last item, returning `None` if the vector is empty
+    pub fn pop(&mut self) -> Option<T> {
+        let storage = self.storage_mut();
+        if storage.len == 0 {
+            return None;
+        }
+
+        storage.len -= 1;
+        // Safety: The item was initialized and is no longer counted by the length
+        Some(unsafe { storage.items.as_ptr().add(storage.len).read() })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // Safety: The storage was grown to hold `capacity` items, the first `len`
+        // of which are initialized
+        unsafe { slice::from_raw_parts(self.storage.items.as_ptr(), self.storage.len) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of items the vector can hold without growing
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Iterates over copies of the items, so the loop body can allocate
+    ///
+    /// The iterator borrows the vector, so no items can be added or removed
+    /// while it's running. Collections can still move the items, so each one is
+    /// read through the vector's handle when it's reached rather than from a
+    /// slice taken up front
+    pub fn iter_copied(&self) -> impl Iterator<Item = T> + '_
+    where
+        T: Copy,
+    {
+        (0..self.len()).map(move |index| self.as_slice()[index])
+    }
+
+    /// The identity of the heap object holding the items, which stays the same
+    /// as it's grown
+    pub fn id(&self) -> AllocId {
+        self.storage.id()
+    }
+
+    fn storage_mut(&mut self) -> &mut VecStorage<T> {
+        assert!(
+            unsafe { !self.storage.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+
+        unsafe { self.storage.inner_mut().downcast_mut().unwrap() }
+    }
+}
+
+impl<T: Sized + Any> GcVec<Rooted<T>> {
+    /// Iterates over [`Weak`] handles to the objects the items point to, so the
+    /// loop body can allocate
+    ///
+    /// Like [`GcVec::iter_copied`] each item is looked up when it's reached. The
+    /// vector keeps every object alive for as long as the iterator borrows it, so
+    /// the handles are only cleared if the objects are reclaimed after the vector
+    /// lets go of them
+    pub fn iter_weak(&self) -> impl Iterator<Item = Weak<T>> + '_ {
+        (0..self.len()).map(move |index| Weak::new(&self.as_slice()[index]))
+    }
+}
+
+impl<T: Any + Trace> Trace for GcVec<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.as_slice().trace(tracer);
+    }
+}
+
+impl<T: Any> Drop for GcVec<T> {
+    fn drop(&mut self) {
+        // Items whose heap is already gone can't be dropped, and are leaked with it
+        if self.storage.try_deref().is_some() {
+            let storage = self.storage_mut();
+            let len = mem::replace(&mut storage.len, 0);
+
+            unsafe {
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                    storage.items.as_mut_ptr(),
+                    len,
+                ))
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgePolicy, BumpOptions, Location, Rooted};
+    use alloc::rc::Rc;
+
+    #[test]
+    fn growth() {
+        let mut bump = BumpHeap::default();
+
+        let mut vec = unsafe { GcVec::try_with_capacity(&mut bump, 2).unwrap() };
+        let id = vec.id();
+        let size = vec.storage.allocated_size();
+        assert_eq!(vec.capacity(), 4);
+
+        for i in 0..100u64 {
+            unsafe { vec.push(&mut bump, i) };
+        }
+        assert_eq!(vec.id(), id);
+        assert_eq!(vec.len(), 100);
+        assert!(vec.capacity() >= 100);
+        assert!(vec.storage.allocated_size() >= size + 96 * mem::size_of::<u64>());
+
+        bump.scavenge();
+        bump.major();
+        assert_eq!(vec.storage.location(), Location::Tenured(1));
+        assert_eq!(vec.as_slice(), (0..100).collect::<Vec<_>>().as_slice());
+
+        assert_eq!(vec.pop(), Some(99));
+        assert_eq!(vec.get(98), Some(&98));
+        assert_eq!(vec.get(99), None);
+    }
+
+    #[test]
+    fn young_growth_kept_young() {
+        static POLICY: AgePolicy = AgePolicy { age: u8::MAX };
+        let mut bump = BumpHeap::new(BumpOptions::new().promotion_policy(&POLICY));
+
+        // Growing the vector moves it past the objects allocated after it
+        let mut vec = unsafe { GcVec::new(&mut bump) };
+        let after: Vec<Rooted<u64>> = (0..4).map(|i| unsafe { bump.alloc_unchecked(i) }).collect();
+        for i in 0..64u64 {
+            unsafe { vec.push(&mut bump, i) };
+        }
+        let moved = vec.storage.as_ptr() as usize;
+        assert!(after.iter().all(|after| (after.as_ptr() as usize) < moved));
+
+        let outcome = bump.scavenge();
+        assert_eq!((outcome.survived, outcome.kept), (0, 5));
+        assert_eq!(vec.as_slice(), (0..64).collect::<Vec<_>>().as_slice());
+        assert!(after
+            .iter()
+            .enumerate()
+            .all(|(i, after)| **after == i as u64));
+
+        // Survivors were compacted in address order, so the vector is still last
+        assert!(after
+            .iter()
+            .all(|after| (after.as_ptr() as usize) < vec.storage.as_ptr() as usize));
+    }
+
+    #[test]
+    fn young_growth_past_the_young_generation() {
+        static POLICY: AgePolicy = AgePolicy { age: u8::MAX };
+        let mut bump = BumpHeap::new(
+            BumpOptions::new()
+                .young_heap_size(1024)
+                .promotion_policy(&POLICY),
+        );
+
+        // The scavenge keeps the vector young, so it never has room to grow into
+        let mut vec = unsafe { GcVec::<u64>::new(&mut bump) };
+        let err = unsafe { vec.try_reserve(&mut bump, 1024).unwrap_err() };
+        assert!(matches!(err, AllocError::OutOfMemory { .. }));
+        assert_eq!(bump.counters().scavenge_count, 1);
+        assert_eq!(vec.storage.location(), Location::Eden);
+    }
+
+    #[test]
+    fn tenured_growth() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let mut vec = unsafe { GcVec::new(&mut bump) };
+        unsafe { vec.push(&mut bump, 0u64) };
+        bump.scavenge();
+        assert_eq!(vec.storage.location(), Location::Tenured(1));
+
+        for i in 1..200 {
+            unsafe { vec.push(&mut bump, i) };
+        }
+        bump.scavenge();
+        bump.major();
+        assert_eq!(vec.as_slice(), (0..200).collect::<Vec<_>>().as_slice());
+    }
+
+    #[test]
+    fn traces_items() {
+        let mut bump = BumpHeap::default();
+        let mut vec = unsafe { GcVec::new(&mut bump) };
+
+        let items: Vec<Rooted<usize>> =
+            (0..3).map(|i| unsafe { bump.alloc_unchecked(i) }).collect();
+        let ids: Vec<_> = items.iter().map(Rooted::id).collect();
+        for item in items {
+            unsafe { vec.push(&mut bump, item) };
+        }
+        bump.scavenge();
+
+        let mut tracer = Tracer::new();
+        vec.trace(&mut tracer);
+        assert_eq!(tracer.edges(), ids.as_slice());
+        assert_eq!(*vec.as_slice()[2], 2);
+    }
+
+    #[test]
+    fn iterate_while_allocating() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+        let mut numbers = unsafe { GcVec::new(&mut bump) };
+        let mut objects = unsafe { GcVec::new(&mut bump) };
+        for i in 0..20u64 {
+            unsafe {
+                numbers.push(&mut bump, i);
+                let object = bump.alloc_unchecked(i * 10);
+                objects.push(&mut bump, object);
+            }
+        }
+
+        let scavenges = bump.counters().scavenge_count;
+        let mut garbage = Vec::new();
+        let mut visited = Vec::new();
+        for (number, object) in numbers.iter_copied().zip(objects.iter_weak()) {
+            for _ in 0..16 {
+                garbage.push(unsafe { bump.alloc_unchecked([number; 16]) });
+            }
+            garbage.clear();
+
+            visited.push((number, *unsafe { object.get(&bump) }.unwrap()));
+        }
+
+        assert!(bump.counters().scavenge_count > scavenges);
+        assert_eq!(visited, (0..20).map(|i| (i, i * 10)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drops_items() {
+        let mut bump = BumpHeap::default();
+        let counted = Rc::new(());
+
+        let mut vec = unsafe { GcVec::new(&mut bump) };
+        for _ in 0..10 {
+            unsafe { vec.push(&mut bump, Rc::clone(&counted)) };
+        }
+        drop(vec.pop());
+        assert_eq!(Rc::strong_count(&counted), 10);
+
+        drop(vec);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+}
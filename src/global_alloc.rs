@@ -0,0 +1,198 @@
+use crate::{
+    error::HeapCreateError,
+    memory::{self, HeapPointer},
+    sweep_heap::SweepHeap,
+};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    hint,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::alloc::System;
+
+/// A global allocator that hands out manually managed memory from a tenured
+/// generation, for whole-program arena experiments
+///
+/// ```ignore
+/// #[global_allocator]
+/// static GLOBAL: BumpGlobalAlloc = BumpGlobalAlloc::new();
+///
+/// fn main() {
+///     GLOBAL.init(1024 * 1024 * 64).unwrap();
+///     // ...
+/// }
+/// ```
+///
+/// Nothing allocated through it is traced or collected, it's exactly as manual
+/// as any other global allocator. There are some severe caveats:
+///
+/// - Until [`BumpGlobalAlloc::init`] is called every allocation is forwarded to
+///   the system allocator, so it should be called as early as possible
+/// - The free list allocates its own bookkeeping while it's locked, and those
+///   reentrant allocations are forwarded to the system allocator too, as are
+///   allocations made while another thread holds the lock
+/// - Alignments larger than `MAX_ALIGN` aren't supported and fail to allocate
+/// - Once the generation is full, allocations fail rather than falling back
+pub struct BumpGlobalAlloc {
+    locked: AtomicBool,
+    heap: UnsafeCell<Option<SweepHeap>>,
+    /// The generation's `[start, end)` address range, which is set once by `init`
+    /// and read without the lock
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+// Safety: The heap is only ever touched while `locked` is held
+unsafe impl Sync for BumpGlobalAlloc {}
+
+impl BumpGlobalAlloc {
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            heap: UnsafeCell::new(None),
+            start: AtomicUsize::new(0),
+            end: AtomicUsize::new(0),
+        }
+    }
+
+    /// Allocates the `size` byte generation that allocations are served from
+    ///
+    /// # Panics
+    ///
+    /// Panics if called more than once
+    pub fn init(&self, size: usize) -> Result<(), HeapCreateError> {
+        self.lock();
+        let heap = unsafe { &mut *self.heap.get() };
+        assert!(heap.is_none(), "BumpGlobalAlloc was initialized twice");
+
+        // Everything the heap allocates is forwarded to the system allocator,
+        // since the lock is held
+        let result = SweepHeap::try_new(size).map(|new| {
+            let (start, end) = new.bounds();
+            self.start.store(start.as_usize(), Ordering::Release);
+            self.end.store(end.as_usize(), Ordering::Release);
+
+            *heap = Some(new);
+        });
+        self.unlock();
+
+        result
+    }
+
+    fn try_lock(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn lock(&self) {
+        while !self.try_lock() {
+            hint::spin_loop();
+        }
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+
+    /// The size of the block a layout is given, which is at least its alignment
+    /// so that the pocket it lands in is aligned enough
+    fn block_size(layout: Layout) -> usize {
+        layout.size().max(layout.align())
+    }
+
+    /// Whether `ptr` was allocated from the generation rather than the system allocator
+    fn owns(&self, ptr: *mut u8) -> bool {
+        let (start, end) = (
+            self.start.load(Ordering::Acquire),
+            self.end.load(Ordering::Acquire),
+        );
+
+        (start..end).contains(&(ptr as usize))
+    }
+}
+
+impl Default for BumpGlobalAlloc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for BumpGlobalAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !self.try_lock() {
+            return System.alloc(layout);
+        }
+
+        let ptr = match &mut *self.heap.get() {
+            Some(_) if layout.align() > memory::MAX_ALIGN => core::ptr::null_mut(),
+            Some(heap) => heap
+                .alloc(Self::block_size(layout))
+                .map_or(core::ptr::null_mut(), |(ptr, _)| ptr.as_mut_ptr()),
+            None => System.alloc(layout),
+        };
+        self.unlock();
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // Reentrant deallocations are of the free list's own bookkeeping, which
+        // always comes from the system allocator, so this never waits on itself
+        if !self.owns(ptr) {
+            System.dealloc(ptr, layout);
+            return;
+        }
+
+        self.lock();
+        let heap = (*self.heap.get()).as_mut().unwrap();
+        heap.free(HeapPointer::new(ptr as usize), Self::block_size(layout));
+        self.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+
+    #[test]
+    fn box_round_trip() {
+        static GLOBAL: BumpGlobalAlloc = BumpGlobalAlloc::new();
+
+        // Uninitialized allocators forward to the system allocator
+        let layout = Layout::new::<[u64; 4]>();
+        let early = unsafe { GLOBAL.alloc(layout) };
+        assert!(!GLOBAL.owns(early));
+        unsafe { GLOBAL.dealloc(early, layout) };
+
+        GLOBAL.init(1024 * 64).unwrap();
+
+        let allocate = |value: [u64; 4]| unsafe {
+            let ptr = GLOBAL.alloc(layout) as *mut [u64; 4];
+            assert!(GLOBAL.owns(ptr as *mut u8));
+            assert_eq!(ptr as usize % layout.align(), 0);
+
+            ptr.write(value);
+            Box::from_raw(ptr)
+        };
+        let free = |boxed: Box<[u64; 4]>| unsafe {
+            GLOBAL.dealloc(Box::into_raw(boxed) as *mut u8, layout)
+        };
+
+        let boxed = allocate([1, 2, 3, 4]);
+        let address = &*boxed as *const [u64; 4];
+        assert_eq!(*boxed, [1, 2, 3, 4]);
+        free(boxed);
+
+        // The freed block is handed out again
+        let reused = allocate([5; 4]);
+        assert_eq!(&*reused as *const [u64; 4], address);
+        assert_eq!(*reused, [5; 4]);
+        free(reused);
+
+        let overaligned = Layout::from_size_align(8, memory::MAX_ALIGN * 2).unwrap();
+        assert!(unsafe { GLOBAL.alloc(overaligned) }.is_null());
+    }
+}
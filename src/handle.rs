@@ -0,0 +1,139 @@
+use crate::{
+    bump_heap::BumpHeap,
+    memory::AllocId,
+    rooted::{Location, Rooted},
+    trace::{Trace, Tracer},
+};
+use core::any::Any;
+
+/// A handle to a heap object that can only be dereferenced through a borrow of
+/// its heap, returned by [`BumpHeap::alloc`]
+///
+/// Everything that can move an object takes the heap mutably, so a reference
+/// borrowed through [`Handle::get`] keeps the heap borrowed for as long as it's
+/// alive and the object can't move out from under it. That's what makes
+/// allocating one safe, where a [`Rooted`]'s references aren't tied to anything
+/// and it's up to the caller not to hold them across a collection
+#[derive(Debug)]
+pub struct Handle<T: Sized + Any> {
+    rooted: Rooted<T>,
+}
+
+impl<T: Sized + Any> Handle<T> {
+    pub(crate) fn new(rooted: Rooted<T>) -> Self {
+        Self { rooted }
+    }
+
+    /// The object's identity, which stays the same when the collector moves it
+    pub fn id(&self) -> AllocId {
+        self.rooted.id()
+    }
+
+    /// Where the object currently lives
+    pub fn location(&self) -> Location {
+        self.rooted.location()
+    }
+
+    /// Borrows the value for as long as `heap` is borrowed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's [epoch](BumpHeap::free_region) was freed or the
+    /// object isn't in `heap`
+    pub fn get<'a>(&'a self, heap: &'a BumpHeap) -> &'a T {
+        self.check(heap);
+
+        self.rooted.try_deref().unwrap()
+    }
+
+    /// Borrows the value for as long as `heap` is borrowed, returning `None` if
+    /// the object's [epoch](BumpHeap::free_region) was freed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object isn't in `heap`
+    pub fn try_get<'a>(&'a self, heap: &'a BumpHeap) -> Option<&'a T> {
+        self.rooted.try_deref()?;
+        self.check(heap);
+
+        self.rooted.try_deref()
+    }
+
+    /// Mutably borrows the value for as long as `heap` is borrowed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's [epoch](BumpHeap::free_region) was freed or the
+    /// object isn't in `heap`
+    pub fn get_mut<'a>(&'a mut self, heap: &'a mut BumpHeap) -> &'a mut T {
+        self.check(heap);
+
+        // Handles are never pinned, so the value can be moved out of through the reference
+        unsafe { self.rooted.inner_mut().downcast_mut().unwrap() }
+    }
+
+    /// Moves the value out of the heap, the same as [`Rooted::into_inner`]
+    pub fn into_inner(self) -> T {
+        self.rooted.into_inner()
+    }
+
+    /// Turns the handle into a [`Rooted`], whose value can be borrowed without
+    /// borrowing the heap
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn into_rooted(self) -> Rooted<T> {
+        self.rooted
+    }
+
+    fn check(&self, heap: &BumpHeap) {
+        assert!(
+            self.rooted.try_deref().is_some(),
+            "Accessed a Handle whose object was freed",
+        );
+        assert!(
+            heap.contains(unsafe { self.rooted.inner().data_ptr() }),
+            "Accessed a Handle through a heap it isn't in",
+        );
+    }
+}
+
+impl<T: Sized + Any> Trace for Handle<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.rooted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BumpHeap, BumpOptions, Location};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn survives_collections() {
+        let mut bump = BumpHeap::new(BumpOptions::new());
+
+        let mut handle = bump.alloc(Vec::from([1, 2, 3]));
+        let id = handle.id();
+        assert_eq!(handle.location(), Location::Eden);
+
+        handle.get_mut(&mut bump).push(4);
+        bump.scavenge();
+        bump.major();
+
+        assert_eq!(handle.id(), id);
+        assert_eq!(handle.get(&bump), &[1, 2, 3, 4]);
+        assert_eq!(handle.into_inner(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Accessed a Handle through a heap it isn't in")]
+    fn wrong_heap() {
+        let mut bump = BumpHeap::new(BumpOptions::new());
+        let other = BumpHeap::new(BumpOptions::new());
+
+        let handle = bump.alloc(10usize);
+        handle.get(&other);
+    }
+}
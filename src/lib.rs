@@ -3,7 +3,7 @@
 
 extern crate alloc;
 
-#[cfg(feature = "mimalloc")]
+#[cfg(feature = "global-mimalloc")]
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
@@ -34,11 +34,71 @@ mod log {
     dummy_log!(debug, error, info, warn, trace);
 }
 
+mod background_mark;
+mod buffer;
 mod bump_heap;
+mod cell;
+mod color;
+mod counters;
+mod dump;
+mod error;
+mod events;
 mod free_list;
+mod fx_hash;
+mod gc_map;
+mod gc_vec;
+mod global_alloc;
+mod handle;
 mod memory;
+mod native;
+mod pressure;
+mod promotion;
 mod rooted;
+mod scoped_heap;
+mod soft;
+mod static_heap;
+#[cfg(any(test, feature = "testing"))]
+mod stress;
 mod sweep_heap;
+mod trace;
+mod weak;
+#[cfg(any(test, feature = "testing"))]
+mod workload;
 
-pub use bump_heap::{BumpHeap, BumpOptions};
-pub use rooted::Rooted;
+pub use buffer::{HeapBuffer, HeapSlice};
+pub use bump_heap::{
+    BumpHeap, BumpOptions, CollectionPolicy, LiveObject, ScavengeOutcome, YoungReclaim,
+    MAX_GENERATIONS,
+};
+pub use cell::GcCell;
+pub use color::Color;
+#[cfg(feature = "debug-accounting")]
+pub use counters::Accounting;
+#[cfg(feature = "type-profile")]
+pub use counters::TypeStat;
+pub use counters::{AllocSite, GcCounters, SiteStat};
+pub use dump::{read_dump, DumpedObject, HeapDump, DUMP_VERSION};
+pub use error::{AllocError, HeapCreateError, OptionsError};
+pub use events::{PauseEvent, PauseKind, PromoteEvent, RelocateEvent};
+pub use free_list::{assert_fits_pocket, FreeBlock, PocketSizes, MAX_POCKETS};
+pub use gc_map::{GcHashMap, WeakKeyMap, WeakValueMap};
+pub use gc_vec::GcVec;
+pub use global_alloc::BumpGlobalAlloc;
+pub use handle::Handle;
+#[cfg(feature = "mimalloc")]
+pub use memory::MiMallocSource;
+pub use memory::{padding_for, AllocId, GlobalSource, HeapPointer, MemorySource, HUGE_PAGE_SIZE};
+pub use native::NativeSize;
+pub use pressure::{Pressure, PressureThresholds};
+pub use promotion::{AgePolicy, PromotionPolicy, SizeThresholdPolicy, Survivor};
+pub use rooted::{Epoch, Location, Rooted, RootedAny};
+pub use scoped_heap::{ScopedBumpHeap, ScopedRooted};
+pub use soft::Soft;
+pub use static_heap::StaticBumpHeap;
+#[cfg(any(test, feature = "testing"))]
+pub use stress::StressTester;
+pub use sweep_heap::{CompactStats, SweepHeap};
+pub use trace::{Trace, Tracer};
+pub use weak::Weak;
+#[cfg(any(test, feature = "testing"))]
+pub use workload::{ObjectSize, WorkloadShape, WorkloadSpec, WorkloadStats};
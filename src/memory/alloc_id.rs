@@ -0,0 +1,403 @@
+use crate::{
+    memory::HeapPointer,
+    rooted::{ContainingHeap, RootedInner},
+};
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{cell::RefCell, fmt, ptr, ptr::NonNull};
+use std::collections::HashSet;
+
+const INDEX_BITS: u32 = usize::BITS / 2;
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+const HEAP_BITS: u32 = 8;
+const GENERATION_BITS: u32 = usize::BITS - INDEX_BITS - HEAP_BITS;
+const GENERATION_MASK: usize = (1 << GENERATION_BITS) - 1;
+
+/// A stable identity for a heap object that survives the object being moved
+///
+/// The low half of the id is an index into the heap's id table, followed by
+/// the generation of that slot, so an id that outlives its object is never
+/// confused with whatever object reuses the slot afterwards. The top byte is
+/// the number of the heap the id came from, which is what lets `{:p}` print
+/// the object's current address
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct AllocId(usize);
+
+impl AllocId {
+    #[inline]
+    pub(crate) const fn new(heap: usize, index: usize, generation: usize) -> Self {
+        Self(
+            (heap << (INDEX_BITS + GENERATION_BITS))
+                | ((generation & GENERATION_MASK) << INDEX_BITS)
+                | (index & INDEX_MASK),
+        )
+    }
+
+    /// Rebuilds an id from the bits [`AllocId::as_usize`] returned
+    #[inline]
+    pub(crate) const fn from_usize(id: usize) -> Self {
+        Self(id)
+    }
+
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 & INDEX_MASK
+    }
+
+    #[inline]
+    pub const fn generation(self) -> usize {
+        (self.0 >> INDEX_BITS) & GENERATION_MASK
+    }
+
+    /// The number of the heap the id came from, which is only unique among the
+    /// heaps alive on the same thread
+    #[inline]
+    pub(crate) const fn heap(self) -> usize {
+        self.0 >> (INDEX_BITS + GENERATION_BITS)
+    }
+
+    #[inline]
+    pub const fn as_usize(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Debug for AllocId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AllocId({}v{})", self.index(), self.generation())
+    }
+}
+
+impl fmt::Display for AllocId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}v{}", self.index(), self.generation())
+    }
+}
+
+/// Formats the current address of the id's object, or a null pointer if the
+/// object has been collected or its heap isn't alive on this thread
+impl fmt::Pointer for AllocId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let address = TABLES
+            .try_with(|tables| tables.borrow().locate(*self))
+            .ok()
+            .flatten();
+
+        fmt::Pointer::fmt(&address.map_or(ptr::null(), HeapPointer::as_ptr::<u8>), f)
+    }
+}
+
+/// Records that the id's object is about to be written to in its heap's
+/// remembered set, if it's tenured and its heap is alive on this thread
+pub(crate) fn remember(id: AllocId) {
+    let _ = TABLES.try_with(|tables| tables.borrow().remember(id));
+}
+
+thread_local! {
+    /// The id tables of the heaps alive on this thread, indexed by heap number
+    static TABLES: RefCell<Tables> = RefCell::new(Tables::new());
+}
+
+/// Heap numbers are handed out round robin so that a dropped heap's number
+/// isn't reused right away, and number 0 is for heaps that didn't get one
+/// because every other number was taken
+struct Tables {
+    tables: Vec<*const Table>,
+    next: usize,
+}
+
+impl Tables {
+    fn new() -> Self {
+        Self {
+            tables: vec![ptr::null(); 1 << HEAP_BITS],
+            next: 1,
+        }
+    }
+
+    fn register(&mut self, table: *const Table) -> usize {
+        let numbers = self.tables.len() - 1;
+        let free = (0..numbers)
+            .map(|offset| (self.next - 1 + offset) % numbers + 1)
+            .find(|&heap| self.tables[heap].is_null());
+
+        match free {
+            Some(heap) => {
+                self.tables[heap] = table;
+                self.next = heap % numbers + 1;
+
+                heap
+            }
+            None => 0,
+        }
+    }
+
+    fn unregister(&mut self, heap: usize) {
+        if heap != 0 {
+            self.tables[heap] = ptr::null();
+        }
+    }
+
+    fn remember(&self, id: AllocId) {
+        let table = self.tables[id.heap()];
+        if id.heap() == 0 || table.is_null() {
+            return;
+        }
+
+        // Safety: See `Tables::locate`
+        unsafe {
+            let tenured = (*table)
+                .slots
+                .borrow()
+                .resolve(id)
+                .is_some_and(|inner| (*inner).heap != ContainingHeap::Eden);
+            if tenured {
+                (*table).remembered.borrow_mut().insert(id);
+            }
+        }
+    }
+
+    fn locate(&self, id: AllocId) -> Option<HeapPointer> {
+        let table = self.tables[id.heap()];
+        if id.heap() == 0 || table.is_null() {
+            return None;
+        }
+
+        // Safety: Heaps aren't `Send`, so tables are unregistered on the thread
+        // that registered them before they're freed, and ids are released before
+        // their metadata is
+        unsafe {
+            (*table)
+                .slots
+                .try_borrow()
+                .ok()?
+                .resolve(id)
+                .map(|inner| (*inner).data_ptr())
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IdSlot {
+    generation: usize,
+    inner: *const RootedInner,
+}
+
+#[derive(Debug, Default)]
+struct Slots {
+    slots: Vec<IdSlot>,
+    free: Vec<usize>,
+}
+
+impl Slots {
+    fn resolve(&self, id: AllocId) -> Option<*const RootedInner> {
+        self.slots
+            .get(id.index())
+            .filter(|slot| slot.generation == id.generation() && !slot.inner.is_null())
+            .map(|slot| slot.inner)
+    }
+}
+
+/// What a heap's [`IdTable`] points to, which formatting an id can read while
+/// the heap is borrowed
+#[derive(Debug)]
+struct Table {
+    heap: usize,
+    slots: RefCell<Slots>,
+    /// The tenured objects written to since the last scavenge, which the write
+    /// barrier adds to without access to the heap
+    remembered: RefCell<HashSet<AllocId>>,
+}
+
+/// Maps [`AllocId`]s to the (pinned, never moving) `RootedInner` of their object
+#[derive(Debug)]
+pub(crate) struct IdTable {
+    table: NonNull<Table>,
+}
+
+impl IdTable {
+    pub(crate) fn new() -> Self {
+        let table = NonNull::from(Box::leak(Box::new(Table {
+            heap: 0,
+            slots: RefCell::new(Slots::default()),
+            remembered: RefCell::new(HashSet::new()),
+        })));
+
+        // Safety: Nothing else can have seen the table yet
+        let heap = TABLES.with(|tables| tables.borrow_mut().register(table.as_ptr()));
+        unsafe { (*table.as_ptr()).heap = heap };
+
+        Self { table }
+    }
+
+    fn table(&self) -> &Table {
+        // Safety: The table lives as long as `self`
+        unsafe { self.table.as_ref() }
+    }
+
+    /// Reserves an id whose slot will be filled in by `bind`
+    pub(crate) fn reserve(&mut self) -> AllocId {
+        let heap = self.table().heap;
+        let mut slots = self.table().slots.borrow_mut();
+
+        if let Some(index) = slots.free.pop() {
+            AllocId::new(heap, index, slots.slots[index].generation)
+        } else {
+            let index = slots.slots.len();
+            assert!(index <= INDEX_MASK, "Ran out of allocation ids");
+
+            slots.slots.push(IdSlot {
+                generation: 0,
+                inner: ptr::null(),
+            });
+
+            AllocId::new(heap, index, 0)
+        }
+    }
+
+    pub(crate) fn bind(&mut self, id: AllocId, inner: *const RootedInner) {
+        let mut slots = self.table().slots.borrow_mut();
+        let slot = &mut slots.slots[id.index()];
+        debug_assert_eq!(slot.generation, id.generation());
+        debug_assert!(slot.inner.is_null(), "Bound an id that was already in use");
+
+        slot.inner = inner;
+    }
+
+    /// Frees an id, bumping its slot's generation so the old id goes stale
+    ///
+    /// A slot whose generation can't be bumped any further is retired instead
+    /// of wrapping back around to generation 0, since that would bring every
+    /// id it ever handed out back to life
+    pub(crate) fn release(&mut self, id: AllocId) {
+        let mut slots = self.table().slots.borrow_mut();
+        let slot = &mut slots.slots[id.index()];
+        debug_assert_eq!(slot.generation, id.generation(), "Released a stale id");
+
+        slot.inner = ptr::null();
+        self.table().remembered.borrow_mut().remove(&id);
+        if slot.generation < GENERATION_MASK {
+            slot.generation += 1;
+            slots.free.push(id.index());
+        }
+    }
+
+    pub(crate) fn resolve(&self, id: AllocId) -> Option<*const RootedInner> {
+        self.table().slots.borrow().resolve(id)
+    }
+
+    /// Whether the heap got a number of its own, without which the write barrier
+    /// can't find its remembered set
+    pub(crate) fn is_numbered(&self) -> bool {
+        self.table().heap != 0
+    }
+
+    pub(crate) fn is_remembered(&self, id: AllocId) -> bool {
+        self.table().remembered.borrow().contains(&id)
+    }
+
+    pub(crate) fn remembered(&self) -> HashSet<AllocId> {
+        self.table().remembered.borrow().clone()
+    }
+
+    /// Empties the remembered set, returning what was in it
+    pub(crate) fn take_remembered(&mut self) -> HashSet<AllocId> {
+        self.table().remembered.take()
+    }
+}
+
+impl Drop for IdTable {
+    fn drop(&mut self) {
+        let heap = self.table().heap;
+
+        // The thread's tables are already gone if it's exiting
+        let _ = TABLES.try_with(|tables| tables.borrow_mut().unregister(heap));
+        drop(unsafe { Box::from_raw(self.table.as_ptr()) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BumpHeap, BumpOptions};
+
+    #[test]
+    fn reused_ids_go_stale() {
+        let mut table = IdTable::new();
+        let inner = ptr::NonNull::<RootedInner>::dangling().as_ptr() as *const RootedInner;
+
+        let first = table.reserve();
+        table.bind(first, inner);
+        let second = table.reserve();
+        table.bind(second, inner);
+        assert_ne!(first, second);
+
+        table.release(first);
+        assert_eq!(table.resolve(first), None);
+
+        let reused = table.reserve();
+        table.bind(reused, inner);
+        assert_eq!(reused.index(), first.index());
+        assert_eq!(reused.generation(), first.generation() + 1);
+        assert_ne!(reused, first);
+
+        assert_eq!(table.resolve(first), None);
+        assert_eq!(table.resolve(reused), Some(inner));
+        assert_eq!(table.resolve(second), Some(inner));
+    }
+
+    #[test]
+    fn saturated_slots_are_retired() {
+        let mut table = IdTable::new();
+        let inner = ptr::NonNull::<RootedInner>::dangling().as_ptr() as *const RootedInner;
+
+        let first = table.reserve();
+        table.release(first);
+        table.table().slots.borrow_mut().slots[first.index()].generation = GENERATION_MASK;
+
+        let last = table.reserve();
+        table.bind(last, inner);
+        assert_eq!(last.index(), first.index());
+        assert_eq!(last.generation(), GENERATION_MASK);
+
+        table.release(last);
+        assert_eq!(table.resolve(last), None);
+        assert_eq!(table.resolve(first), None);
+
+        let fresh = table.reserve();
+        table.bind(fresh, inner);
+        assert_ne!(fresh.index(), first.index());
+        assert_eq!(
+            table.resolve(AllocId::new(first.heap(), first.index(), 0)),
+            None
+        );
+        assert_eq!(table.resolve(fresh), Some(inner));
+    }
+
+    #[test]
+    fn pointer_follows_moves() {
+        let mut bump = BumpHeap::new(BumpOptions::new());
+        let mut other = BumpHeap::new(BumpOptions::new());
+
+        let rooted = unsafe { bump.alloc_unchecked(10usize) };
+        let id = rooted.id();
+        assert_ne!(
+            id.heap(),
+            unsafe { other.alloc_unchecked(10usize) }.id().heap()
+        );
+
+        let eden = format!("{:p}", id);
+        assert_eq!(
+            eden,
+            format!("{:p}", bump.locate(id).unwrap().as_ptr::<u8>())
+        );
+
+        bump.scavenge();
+        let survivor = format!("{:p}", id);
+        assert_ne!(survivor, eden);
+        assert_eq!(survivor, format!("{:p}", rooted.as_ptr()));
+
+        drop(rooted);
+        bump.major();
+        assert_eq!(format!("{:p}", id), format!("{:p}", ptr::null::<u8>()));
+    }
+}
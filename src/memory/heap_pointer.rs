@@ -1,4 +1,8 @@
-use core::ops;
+use crate::BumpHeap;
+use core::{
+    any::{type_name, Any},
+    fmt, mem, ops,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(transparent)]
@@ -31,190 +35,295 @@ impl HeapPointer {
         Self::new(self.0 + offset)
     }
 
+    /// Adds to the pointer, returning `None` if it would wrap around the
+    /// address space
     #[inline]
-    pub const fn is_null(self) -> bool {
-        self.0 == 0
+    pub fn checked_offset(self, offset: usize) -> Option<Self> {
+        self.0.checked_add(offset).map(Self)
     }
 
+    /// The number of bytes from `origin` up to the pointer
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if `origin` is past the pointer
     #[inline]
-    pub const fn is_non_null(self) -> bool {
-        self.0 != 0
+    pub fn offset_from(self, origin: Self) -> usize {
+        debug_assert!(
+            self >= origin,
+            "{:p} is before the origin it's offset from, {:p}",
+            self,
+            origin,
+        );
+
+        self.0.wrapping_sub(origin.0)
+    }
+
+    /// Adds to the pointer, wrapping around at the edge of the address space
+    ///
+    /// The arithmetic operators are for offsets that should never overflow, these
+    /// are for computations (like hashing) where wrapping is intended. Wrapping to
+    /// exactly zero gives a null pointer rather than panicking
+    #[inline]
+    pub const fn wrapping_add(self, offset: usize) -> Self {
+        Self(self.0.wrapping_add(offset))
     }
 
     #[inline]
-    pub unsafe fn as_ref<'a, T>(self) -> &'a T {
-        debug_assert!(!self.is_null());
-        &*(self.0 as *const T)
+    pub const fn wrapping_sub(self, offset: usize) -> Self {
+        Self(self.0.wrapping_sub(offset))
     }
 
     #[inline]
-    pub unsafe fn as_mut<'a, T>(self) -> &'a mut T {
-        debug_assert!(!self.is_null());
-        &mut *(self.0 as *mut T)
+    pub const fn wrapping_offset(self, offset: isize) -> Self {
+        Self(self.0.wrapping_add(offset as usize))
     }
-}
-
-impl<T: Into<usize>> ops::Add<T> for HeapPointer {
-    type Output = Self;
 
-    fn add(self, other: T) -> Self::Output {
-        Self::new(self.0 + other.into())
+    /// Asserts in debug builds that `[self, self + size)` lies within `[start, end)`
+    #[inline]
+    pub fn debug_check_bounds(self, size: usize, (start, end): (HeapPointer, HeapPointer)) {
+        debug_assert!(
+            self >= start
+                && self
+                    .0
+                    .checked_add(size)
+                    .is_some_and(|span_end| span_end <= end.0),
+            "Access of {} bytes at {:p} is outside of the heap bounds {:p}..{:p}",
+            size,
+            self,
+            start,
+            end,
+        );
+    }
+
+    /// Reads a `T`, checking in debug builds that it lies within `heap_bounds`
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to a valid, initialized `T`
+    #[inline]
+    pub unsafe fn read<T>(self, heap_bounds: (HeapPointer, HeapPointer)) -> T {
+        self.debug_check_bounds(mem::size_of::<T>(), heap_bounds);
+        self.as_ptr::<T>().read()
     }
-}
 
-impl<T: Into<usize>> ops::AddAssign<T> for HeapPointer {
-    fn add_assign(&mut self, other: T) {
-        let other = other.into();
-        assert!(self.0 + other != 0);
-        self.0 += other;
+    /// Writes a `T`, checking in debug builds that it lies within `heap_bounds`
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be valid for writes of a `T` and properly aligned
+    #[inline]
+    pub unsafe fn write<T>(self, value: T, heap_bounds: (HeapPointer, HeapPointer)) {
+        self.debug_check_bounds(mem::size_of::<T>(), heap_bounds);
+        self.as_mut_ptr::<T>().write(value);
     }
-}
-
-impl<T: Into<usize>> ops::Sub<T> for HeapPointer {
-    type Output = Self;
 
-    fn sub(self, other: T) -> Self::Output {
-        Self::new(self.0 - other.into())
+    #[inline]
+    pub const fn is_null(self) -> bool {
+        self.0 == 0
     }
-}
 
-impl<T: Into<usize>> ops::SubAssign<T> for HeapPointer {
-    fn sub_assign(&mut self, other: T) {
-        self.0 -= other.into()
+    #[inline]
+    pub const fn is_non_null(self) -> bool {
+        self.0 != 0
     }
-}
 
-impl<T: Into<usize>> ops::Mul<T> for HeapPointer {
-    type Output = Self;
-
-    fn mul(self, other: T) -> Self::Output {
-        Self::new(self.0 * other.into())
+    /// # Safety
+    ///
+    /// See [`HeapPointer::as_ref_unbounded`]
+    #[inline]
+    #[deprecated(note = "the lifetime is unbounded, use `with_ref` or `as_ref_unbounded`")]
+    pub unsafe fn as_ref<'a, T>(self) -> &'a T {
+        self.as_ref_unbounded()
     }
-}
 
-impl<T: Into<usize>> ops::MulAssign<T> for HeapPointer {
-    fn mul_assign(&mut self, other: T) {
-        self.0 *= other.into()
+    /// # Safety
+    ///
+    /// See [`HeapPointer::as_mut_unbounded`]
+    #[inline]
+    #[deprecated(note = "the lifetime is unbounded, use `as_mut_unbounded` or `as_mut_ptr`")]
+    pub unsafe fn as_mut<'a, T>(self) -> &'a mut T {
+        self.as_mut_unbounded()
     }
-}
 
-impl<T: Into<usize>> ops::Div<T> for HeapPointer {
-    type Output = Self;
+    /// Creates a reference with a lifetime chosen by the caller, which nothing
+    /// ties to the object
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to a valid, initialized `T` that lives (and isn't
+    /// moved by a collection) for `'a`
+    #[inline]
+    pub unsafe fn as_ref_unbounded<'a, T>(self) -> &'a T {
+        debug_assert!(!self.is_null());
+        &*(self.0 as *const T)
+    }
 
-    fn div(self, other: T) -> Self::Output {
-        Self::new(self.0 + other.into())
+    /// Creates a mutable reference with a lifetime chosen by the caller, which
+    /// nothing ties to the object
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to a valid, initialized `T` that lives (and isn't
+    /// moved by a collection) for `'a` and isn't aliased for the duration of the borrow
+    #[inline]
+    pub unsafe fn as_mut_unbounded<'a, T>(self) -> &'a mut T {
+        debug_assert!(!self.is_null());
+        &mut *(self.0 as *mut T)
     }
-}
 
-impl<T: Into<usize>> ops::DivAssign<T> for HeapPointer {
-    fn div_assign(&mut self, other: T) {
-        self.0 /= other.into()
+    /// Calls `f` with the `T` this points to, borrowing the heap so nothing can
+    /// collect (and move the object) while the reference is alive
+    ///
+    /// The reference can't escape the closure:
+    ///
+    /// ```compile_fail
+    /// # use ballast::BumpHeap;
+    /// let mut bump = BumpHeap::default();
+    /// let handle = bump.alloc(1);
+    /// let ptr = bump.locate(handle.id()).unwrap();
+    ///
+    /// let escaped: &usize = ptr.with_ref(&bump, |value: &usize| value);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pointer isn't the address of a live `T` in `heap`
+    pub fn with_ref<T: Any, R>(self, heap: &BumpHeap, f: impl FnOnce(&T) -> R) -> R {
+        let value = heap
+            .object_at(self)
+            .and_then(|inner| inner.downcast_ref::<T>())
+            .unwrap_or_else(|| {
+                panic!(
+                    "{:p} isn't the address of a live {}",
+                    self,
+                    type_name::<T>()
+                )
+            });
+
+        f(value)
     }
 }
 
-impl<T: Into<usize>> ops::BitAnd<T> for HeapPointer {
-    type Output = Self;
+usize_newtype!(HeapPointer);
 
-    fn bitand(self, other: T) -> Self::Output {
-        Self::new(self.0 & other.into())
+impl ops::Deref for HeapPointer {
+    type Target = usize;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-impl<T: Into<usize>> ops::BitAndAssign<T> for HeapPointer {
-    fn bitand_assign(&mut self, other: T) {
-        self.0 &= other.into()
+impl fmt::Pointer for HeapPointer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.as_ptr::<u8>(), f)
     }
 }
 
-impl<T: Into<usize>> ops::BitOr<T> for HeapPointer {
-    type Output = Self;
-
-    fn bitor(self, other: T) -> Self::Output {
-        Self::new(self.0 | other.into())
+impl<T> From<*mut T> for HeapPointer {
+    #[inline]
+    fn from(ptr: *mut T) -> Self {
+        Self::new(ptr as usize)
     }
 }
 
-impl<T: Into<usize>> ops::BitOrAssign<T> for HeapPointer {
-    fn bitor_assign(&mut self, other: T) {
-        self.0 |= other.into()
+impl<T> From<*const T> for HeapPointer {
+    #[inline]
+    fn from(ptr: *const T) -> Self {
+        Self::new(ptr as usize)
     }
 }
 
-impl<T: Into<usize>> ops::BitXor<T> for HeapPointer {
-    type Output = Self;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Handle;
 
-    fn bitxor(self, other: T) -> Self::Output {
-        Self::new(self.0 ^ other.into())
-    }
-}
+    #[test]
+    fn with_ref() {
+        let mut bump = BumpHeap::default();
+        let handle: Handle<usize> = bump.alloc(10);
 
-impl<T: Into<usize>> ops::BitXorAssign<T> for HeapPointer {
-    fn bitxor_assign(&mut self, other: T) {
-        self.0 ^= other.into()
+        let ptr = bump.locate(handle.id()).unwrap();
+        assert_eq!(ptr.with_ref(&bump, |value: &usize| *value + 1), 11);
+
+        bump.scavenge();
+        let ptr = bump.locate(handle.id()).unwrap();
+        assert_eq!(ptr.with_ref(&bump, |value: &usize| *value), 10);
     }
-}
 
-impl<T: Into<usize>> ops::Shl<T> for HeapPointer {
-    type Output = Self;
+    #[test]
+    #[should_panic(expected = "isn't the address of a live u64")]
+    fn with_ref_checks_type() {
+        let mut bump = BumpHeap::default();
+        let handle: Handle<usize> = bump.alloc(10);
 
-    fn shl(self, other: T) -> Self::Output {
-        Self::new(self.0 << other.into())
+        let ptr = bump.locate(handle.id()).unwrap();
+        ptr.with_ref(&bump, |value: &u64| *value);
     }
-}
 
-impl<T: Into<usize>> ops::ShlAssign<T> for HeapPointer {
-    fn shl_assign(&mut self, other: T) {
-        self.0 <<= other.into()
+    #[test]
+    fn offset_from() {
+        let (low, high) = (HeapPointer::new(0x1000), HeapPointer::new(0x1040));
+        assert_eq!(high.offset_from(low), 0x40);
+        assert_eq!(low.offset_from(low), 0);
     }
-}
-
-impl<T: Into<usize>> ops::Shr<T> for HeapPointer {
-    type Output = Self;
 
-    fn shr(self, other: T) -> Self::Output {
-        Self::new(self.0 >> other.into())
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is before the origin")]
+    fn offset_from_before_origin() {
+        let (low, high) = (HeapPointer::new(0x1000), HeapPointer::new(0x1040));
+        low.offset_from(high);
     }
-}
 
-impl<T: Into<usize>> ops::ShrAssign<T> for HeapPointer {
-    fn shr_assign(&mut self, other: T) {
-        self.0 >>= other.into()
+    #[test]
+    fn checked_offset() {
+        let ptr = HeapPointer::new(usize::MAX - 8);
+
+        assert_eq!(ptr.checked_offset(8), Some(HeapPointer::new(usize::MAX)));
+        assert_eq!(ptr.checked_offset(9), None);
     }
-}
 
-impl ops::Not for HeapPointer {
-    type Output = Self;
+    #[test]
+    fn wrapping() {
+        let max = HeapPointer::new(usize::MAX);
 
-    fn not(self) -> Self::Output {
-        Self::new(!self.0)
+        assert_eq!(max.wrapping_add(2), HeapPointer::new(1));
+        assert_eq!(HeapPointer::new(1).wrapping_sub(2), max);
+        assert_eq!(max.wrapping_offset(3), HeapPointer::new(2));
+        assert_eq!(HeapPointer::new(2).wrapping_offset(-3), max);
+        assert_eq!(HeapPointer::new(8).wrapping_offset(-4), HeapPointer::new(4));
     }
-}
 
-impl ops::Deref for HeapPointer {
-    type Target = usize;
+    #[test]
+    fn read_write_in_bounds() {
+        let mut backing = [0u64; 4];
+        let start = HeapPointer::from(backing.as_mut_ptr());
+        let bounds = (start, start + mem::size_of_val(&backing));
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        unsafe {
+            start.offset(8).write(10u64, bounds);
+            assert_eq!(start.offset(8).read::<u64>(bounds), 10);
+        }
     }
-}
 
-impl From<usize> for HeapPointer {
-    #[inline]
-    fn from(ptr: usize) -> Self {
-        Self::new(ptr)
-    }
-}
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outside of the heap bounds")]
+    fn write_out_of_bounds() {
+        let mut backing = [0u64; 4];
+        let start = HeapPointer::from(backing.as_mut_ptr());
+        let bounds = (start, start + 16usize);
 
-impl<T> From<*mut T> for HeapPointer {
-    #[inline]
-    fn from(ptr: *mut T) -> Self {
-        Self::new(ptr as usize)
+        unsafe { start.offset(12).write(10u64, bounds) };
     }
-}
 
-impl<T> From<*const T> for HeapPointer {
-    #[inline]
-    fn from(ptr: *const T) -> Self {
-        Self::new(ptr as usize)
+    #[test]
+    fn wrapping_to_null() {
+        assert!(HeapPointer::new(usize::MAX).wrapping_add(1).is_null());
+        assert!(HeapPointer::new(1).wrapping_sub(1).is_null());
+        assert!(HeapPointer::new(4).wrapping_offset(-4).is_null());
     }
 }
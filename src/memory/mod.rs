@@ -1,13 +1,237 @@
+/// Implements the arithmetic and bitwise operators for a `#[repr(transparent)]`
+/// newtype over `usize`, building every result with the type's `new`
+///
+/// [`AllocId`]s don't get these, since an id packs its heap, generation and index
+/// into one `usize` and arithmetic on that would forge ids of unrelated objects
+macro_rules! usize_newtype {
+    ($ty:ident) => {
+        usize_newtype!(@binary $ty,
+            Add add AddAssign add_assign +,
+            Sub sub SubAssign sub_assign -,
+            Mul mul MulAssign mul_assign *,
+            Div div DivAssign div_assign /,
+            BitAnd bitand BitAndAssign bitand_assign &,
+            BitOr bitor BitOrAssign bitor_assign |,
+            BitXor bitxor BitXorAssign bitxor_assign ^,
+            Shl shl ShlAssign shl_assign <<,
+            Shr shr ShrAssign shr_assign >>
+        );
+
+        impl core::ops::Not for $ty {
+            type Output = Self;
+
+            fn not(self) -> Self::Output {
+                $ty::new(!self.0)
+            }
+        }
+
+        impl From<usize> for $ty {
+            #[inline]
+            fn from(value: usize) -> Self {
+                $ty::new(value)
+            }
+        }
+    };
+
+    (@binary $ty:ident, $( $trait:ident $method:ident $assign_trait:ident $assign_method:ident $op:tt ),*) => {
+        $(
+            impl<T: Into<usize>> core::ops::$trait<T> for $ty {
+                type Output = Self;
+
+                fn $method(self, other: T) -> Self::Output {
+                    $ty::new(self.0 $op other.into())
+                }
+            }
+
+            impl<T: Into<usize>> core::ops::$assign_trait<T> for $ty {
+                fn $assign_method(&mut self, other: T) {
+                    *self = $ty::new(self.0 $op other.into());
+                }
+            }
+        )*
+    };
+}
+
+mod alloc_id;
 mod heap_pointer;
+mod source;
 
+pub use alloc_id::AllocId;
+pub(crate) use alloc_id::{remember, IdTable};
 pub use heap_pointer::HeapPointer;
+#[cfg(feature = "mimalloc")]
+pub use source::MiMallocSource;
+pub use source::{GlobalSource, MemorySource};
+
+/// The largest alignment the intermediate heap guarantees for promoted objects
+pub(crate) const MAX_ALIGN: usize = 16;
 
+/// The number of bytes needed after `size` to reach the next multiple of `align`,
+/// which must be a power of two
 #[inline]
-pub(crate) const fn padding_for(size: usize, align: usize) -> usize {
+pub const fn padding_for(size: usize, align: usize) -> usize {
+    debug_assert!(align.is_power_of_two(), "Alignments must be powers of two");
+
     let size_rounded_up = size.wrapping_add(align).wrapping_sub(1) & !align.wrapping_sub(1);
     size_rounded_up.wrapping_sub(size)
 }
 
+/// Loads the word at `ptr`, which may be part of a value's padding
+///
+/// Padding is uninitialized, so reading it from Rust is undefined behavior. The
+/// load is done in assembly instead, which hands back whatever bytes are there
+///
+/// # Safety
+///
+/// `ptr` must be valid for reading a `usize`, it doesn't have to be aligned
+#[cfg(all(feature = "trace-audit", debug_assertions))]
+pub(crate) unsafe fn read_word(ptr: *const u8) -> usize {
+    let word: usize;
+
+    #[cfg(target_arch = "x86_64")]
+    core::arch::asm!(
+        "mov {word}, qword ptr [{ptr}]",
+        word = out(reg) word,
+        ptr = in(reg) ptr,
+        options(nostack, readonly, preserves_flags),
+    );
+
+    #[cfg(target_arch = "aarch64")]
+    core::arch::asm!(
+        "ldr {word}, [{ptr}]",
+        word = out(reg) word,
+        ptr = in(reg) ptr,
+        options(nostack, readonly, preserves_flags),
+    );
+
+    word
+}
+
+#[cfg(all(
+    feature = "trace-audit",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+compile_error!("The trace-audit feature can only read objects' words on x86-64 and aarch64");
+
+/// The size of a transparent huge page on x86-64 and most aarch64 kernels
+pub const HUGE_PAGE_SIZE: usize = 1024 * 1024 * 2;
+
+/// Regions at least this large are zeroed across threads when the
+/// `parallel-zeroing` feature is enabled
+#[cfg(feature = "parallel-zeroing")]
+pub(crate) const PARALLEL_ZEROING_THRESHOLD: usize = 1024 * 1024 * 4;
+
+/// Zeroes `len` bytes starting at `start`
+///
+/// # Safety
+///
+/// `start` must be valid for writes of `len` bytes
+pub(crate) unsafe fn zero(start: HeapPointer, len: usize) {
+    #[cfg(feature = "parallel-zeroing")]
+    {
+        if len >= PARALLEL_ZEROING_THRESHOLD {
+            return zero_parallel(start, len);
+        }
+    }
+
+    start.as_mut_ptr::<u8>().write_bytes(0x00, len);
+}
+
+#[cfg(feature = "parallel-zeroing")]
+unsafe fn zero_parallel(start: HeapPointer, len: usize) {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    // Whole huge pages go to each thread so a single huge page is never faulted
+    // in from two threads at once
+    let chunk = len.div_ceil(threads).next_multiple_of(HUGE_PAGE_SIZE);
+
+    // Pointers aren't `Send`, so each thread gets its chunk as an address
+    std::thread::scope(|scope| {
+        for chunk_start in (0..len).step_by(chunk) {
+            let address = start.as_usize() + chunk_start;
+            let chunk_len = chunk.min(len - chunk_start);
+
+            scope.spawn(move || unsafe { (address as *mut u8).write_bytes(0x00, chunk_len) });
+        }
+    });
+}
+
+/// Lets the kernel reclaim the whole pages among the `len` bytes at `start`
+/// with `MADV_FREE`, zeroing the partial pages at either end right away
+///
+/// Reclaimed pages read back as zeroes, but the kernel only reclaims them when
+/// it needs the memory, so until then they keep their old bytes. Everywhere but
+/// Linux, and if the kernel refuses, this zeroes the whole span instead
+///
+/// # Safety
+///
+/// `start` must be valid for writes of `len` bytes
+#[cfg(all(target_os = "linux", not(miri)))]
+pub(crate) unsafe fn free_lazily(start: HeapPointer, len: usize) {
+    let page = page_size();
+    let end = start + len;
+    let first_page = start + padding_for(start.as_usize(), page);
+    let last_page = HeapPointer::new(end.as_usize() - end.as_usize() % page);
+    if first_page >= last_page {
+        return zero(start, len);
+    }
+
+    zero(start, first_page.offset_from(start));
+    zero(last_page, end.offset_from(last_page));
+
+    let pages = last_page.offset_from(first_page);
+    if libc::madvise(first_page.as_mut_ptr(), pages, libc::MADV_FREE) != 0 {
+        trace!(
+            "madvise(MADV_FREE) failed for {} bytes at {:p}",
+            pages,
+            first_page
+        );
+        zero(first_page, pages);
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(miri))))]
+pub(crate) unsafe fn free_lazily(start: HeapPointer, len: usize) {
+    zero(start, len);
+}
+
+/// Touches every page of the `len` bytes at `start`, so that the kernel commits
+/// them now instead of faulting them in on first use
+///
+/// Each page is written with what it already holds, so its contents are left alone
+///
+/// # Safety
+///
+/// `start` must be valid for reads and writes of `len` bytes
+pub(crate) unsafe fn prefault(start: HeapPointer, len: usize) {
+    for offset in (0..len).step_by(page_size()) {
+        let byte = (start + offset).as_mut_ptr::<u8>();
+        byte.write_volatile(byte.read_volatile());
+    }
+}
+
+/// Asks the kernel to back the `len` bytes at `start` with transparent huge
+/// pages, returning whether it accepted
+///
+/// This is only a hint and only exists on Linux, everywhere else it does nothing
+#[cfg(all(target_os = "linux", not(miri)))]
+pub(crate) fn advise_hugepages(start: HeapPointer, len: usize) -> bool {
+    let result = unsafe { libc::madvise(start.as_mut_ptr(), len, libc::MADV_HUGEPAGE) };
+    if result != 0 {
+        trace!(
+            "madvise(MADV_HUGEPAGE) failed for {} bytes at {:p}",
+            len,
+            start
+        );
+    }
+
+    result == 0
+}
+
+#[cfg(not(all(target_os = "linux", not(miri))))]
+pub(crate) fn advise_hugepages(_start: HeapPointer, _len: usize) -> bool {
+    false
+}
+
 #[inline(always)]
 #[cfg(all(target_family = "unix", not(miri)))]
 pub(crate) fn page_size() -> usize {
@@ -42,3 +266,92 @@ pub(crate) fn page_size() -> usize {
 
     size
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn misaligned_hugepage_advice() {
+        let mut bytes = vec![0u8; page_size() * 2];
+        let start = HeapPointer::from(bytes.as_mut_ptr());
+        let misaligned = start + (padding_for(start.as_usize(), page_size()) + 1);
+
+        assert!(!advise_hugepages(misaligned, page_size() / 2));
+    }
+
+    #[test]
+    fn zeroing() {
+        for &len in &[0, 1, 4096, 1024 * 1024 * 5 + 3] {
+            let mut bytes = vec![0xFFu8; len];
+            unsafe { zero(HeapPointer::from(bytes.as_mut_ptr()), len) };
+
+            assert!(bytes.iter().all(|&byte| byte == 0));
+        }
+    }
+
+    #[test]
+    fn padding() {
+        let reference = |size: usize, align: usize| (align - (size % align)) % align;
+
+        for &align in &[1, 2, 8, 16, 4096] {
+            assert_eq!(padding_for(0, align), 0);
+            assert_eq!(padding_for(align, align), 0);
+            assert_eq!(padding_for(align * 3, align), 0);
+        }
+
+        assert_eq!(padding_for(1, 2), 1);
+        assert_eq!(padding_for(15, 16), 1);
+        assert_eq!(padding_for(17, 16), 15);
+
+        for &align in &[2, 4, 8, 16, 64, 4096] {
+            for size in (0..align * 4).step_by(3) {
+                assert_eq!(padding_for(size, align), reference(size, align));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "powers of two")]
+    #[cfg(debug_assertions)]
+    fn padding_for_non_power_of_two() {
+        padding_for(5, 12);
+    }
+
+    #[test]
+    fn heap_pointer_operators() {
+        let value = HeapPointer::from;
+
+        assert_eq!(value(12) + 4usize, value(16));
+        assert_eq!(value(12) - 4usize, value(8));
+        assert_eq!(value(12) * 4usize, value(48));
+        assert_eq!(value(12) / 4usize, value(3));
+        assert_eq!(value(12) & 4usize, value(4));
+        assert_eq!(value(12) | 3usize, value(15));
+        assert_eq!(value(12) ^ 5usize, value(9));
+        assert_eq!(value(12) << 2usize, value(48));
+        assert_eq!(value(12) >> 2usize, value(3));
+        assert_eq!(!value(12), value(!12));
+
+        let mut x = value(12);
+        x += 4usize;
+        assert_eq!(x, value(16));
+        x -= 4usize;
+        assert_eq!(x, value(12));
+        x *= 4usize;
+        assert_eq!(x, value(48));
+        x /= 4usize;
+        assert_eq!(x, value(12));
+        x &= 4usize;
+        assert_eq!(x, value(4));
+        x |= 3usize;
+        assert_eq!(x, value(7));
+        x ^= 5usize;
+        assert_eq!(x, value(2));
+        x <<= 3usize;
+        assert_eq!(x, value(16));
+        x >>= 2usize;
+        assert_eq!(x, value(4));
+    }
+}
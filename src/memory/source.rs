@@ -0,0 +1,52 @@
+use alloc::alloc::Layout;
+use core::fmt::Debug;
+
+/// Where a heap gets its backing memory from
+///
+/// # Safety
+///
+/// `alloc_zeroed` must return either null or a zeroed allocation that fits
+/// `layout` and stays valid until it's passed to `dealloc`
+pub unsafe trait MemorySource: Debug {
+    /// Allocates zeroed memory for `layout`, returning null on failure
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `alloc_zeroed` on this source with the same `layout`
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout);
+}
+
+/// Allocates through the global allocator, which is what heaps use by default
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GlobalSource;
+
+unsafe impl MemorySource for GlobalSource {
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc::alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::alloc::dealloc(ptr, layout)
+    }
+}
+
+/// Allocates from mimalloc directly, without it having to be the global allocator
+#[cfg(feature = "mimalloc")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MiMallocSource;
+
+#[cfg(feature = "mimalloc")]
+unsafe impl MemorySource for MiMallocSource {
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc::GlobalAlloc::alloc_zeroed(&mimalloc::MiMalloc, layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::alloc::GlobalAlloc::dealloc(&mimalloc::MiMalloc, ptr, layout)
+    }
+}
@@ -0,0 +1,40 @@
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::mem;
+
+/// Reports the memory a value owns outside of the heap, like a `Vec`'s buffer
+///
+/// Only the memory the value itself owns should be counted, not that of any
+/// objects it has handles to
+pub trait NativeSize {
+    fn native_bytes(&self) -> usize;
+}
+
+impl<T> NativeSize for Vec<T> {
+    fn native_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl NativeSize for String {
+    fn native_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T> NativeSize for Box<[T]> {
+    fn native_bytes(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+}
+
+impl NativeSize for Box<str> {
+    fn native_bytes(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: NativeSize> NativeSize for Option<T> {
+    fn native_bytes(&self) -> usize {
+        self.as_ref().map_or(0, NativeSize::native_bytes)
+    }
+}
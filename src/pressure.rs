@@ -0,0 +1,41 @@
+/// How close the old generation is to running out of memory
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Pressure {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// The old generation occupancy, as a percentage, at which each [`Pressure`] level begins
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PressureThresholds {
+    pub medium: u8,
+    pub high: u8,
+    pub critical: u8,
+}
+
+impl PressureThresholds {
+    pub fn level(&self, percent_used: usize) -> Pressure {
+        if percent_used >= self.critical as usize {
+            Pressure::Critical
+        } else if percent_used >= self.high as usize {
+            Pressure::High
+        } else if percent_used >= self.medium as usize {
+            Pressure::Medium
+        } else {
+            Pressure::Low
+        }
+    }
+}
+
+impl Default for PressureThresholds {
+    fn default() -> Self {
+        Self {
+            medium: 50,
+            high: 75,
+            critical: 90,
+        }
+    }
+}
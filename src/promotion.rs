@@ -0,0 +1,53 @@
+use crate::memory::AllocId;
+use core::{any::TypeId, fmt::Debug};
+
+/// A live young object that a scavenge is deciding whether to promote
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Survivor {
+    pub id: AllocId,
+    /// The size of the object
+    pub size: usize,
+    pub type_id: TypeId,
+    /// The tag the object was allocated with, see
+    /// [`BumpHeap::alloc_tagged`](crate::BumpHeap::alloc_tagged)
+    pub tag: Option<&'static str>,
+}
+
+/// Decides which young survivors a scavenge promotes into the first tenured
+/// generation, see [`BumpOptions::promotion_policy`](crate::BumpOptions::promotion_policy)
+///
+/// Survivors that aren't promoted are compacted to the start of the young
+/// generation and offered again by the next scavenge
+pub trait PromotionPolicy: Debug {
+    /// Whether to promote `survivor`, which has now survived `survivals` scavenges
+    fn should_promote(&self, survivor: &Survivor, survivals: u8) -> bool;
+}
+
+/// Promotes objects once they've survived `age` scavenges
+///
+/// The default policy has an age of 1, promoting every survivor
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AgePolicy {
+    pub age: u8,
+}
+
+impl PromotionPolicy for AgePolicy {
+    fn should_promote(&self, _survivor: &Survivor, survivals: u8) -> bool {
+        survivals >= self.age
+    }
+}
+
+/// Promotes objects larger than `threshold` bytes the first time they survive,
+/// so they aren't copied around the young generation, and everything else once
+/// it's survived `age` scavenges
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SizeThresholdPolicy {
+    pub threshold: usize,
+    pub age: u8,
+}
+
+impl PromotionPolicy for SizeThresholdPolicy {
+    fn should_promote(&self, survivor: &Survivor, survivals: u8) -> bool {
+        survivor.size > self.threshold || survivals >= self.age
+    }
+}
@@ -1,10 +1,42 @@
+//! Handles to heap objects and the metadata behind them
+//!
+//! Objects are kept alive by their handles rather than by references from other
+//! objects. A handle stored into another object keeps its object alive wherever
+//! it's stored, so the write barrier that the ways of writing to an object fire
+//! only records which tenured objects were written to, see
+//! [`BumpHeap::is_remembered`](crate::BumpHeap::is_remembered)
+
+use crate::{
+    bump_heap::{self, BumpHeap},
+    color::{AtomicColor, Color},
+    counters::AllocSite,
+    memory::{AllocId, HeapPointer},
+    native::NativeSize,
+    trace::{Trace, Tracer},
+};
+use alloc::boxed::Box;
 use core::{
-    any::Any,
+    any::{Any, TypeId},
+    hash::{Hash, Hasher},
     marker::{PhantomData, PhantomPinned},
     mem, ops,
+    pin::Pin,
+    ptr, raw,
 };
 
-#[derive(Debug, PartialEq)]
+/// A handle to a heap object
+///
+/// `==` compares the objects' values, use [`Rooted::same_object`] to check if
+/// two handles point to the same object
+///
+/// Dropping a handle only unroots its object, the value is left in the heap. The
+/// heap drops an object's value when it reclaims the object while it's still
+/// rooted, which [`BumpHeap::free_region`], [`BumpHeap::clear`], clearing
+/// [soft handles](crate::Soft) and marking collections like
+/// [`BumpHeap::collect_mark_sweep_only`] do, and never drops the value of an
+/// unrooted object, whatever reclaims it. Dropping the heap itself doesn't drop
+/// any values
+#[derive(Debug)]
 pub struct Rooted<T: ?Sized + Any> {
     static_inner: *mut RootedInner,
     __type: PhantomData<T>,
@@ -22,6 +54,73 @@ impl<T: ?Sized + Any> Rooted<T> {
         self.static_inner.is_null()
     }
 
+    /// The object's identity, which stays the same when the collector moves it
+    pub fn id(&self) -> AllocId {
+        debug_assert!(!self.is_null());
+
+        unsafe { self.inner().id }
+    }
+
+    /// Unroots the object, which is what dropping the handle does unless it was
+    /// allocated with [`BumpHeap::alloc_unmanaged`](crate::BumpHeap::alloc_unmanaged)
+    pub fn unroot(mut self) {
+        unsafe { self.inner_mut().managed = true };
+        drop(self);
+    }
+
+    /// Where the object currently lives
+    pub fn location(&self) -> Location {
+        debug_assert!(!self.is_null());
+
+        match unsafe { self.inner().heap } {
+            ContainingHeap::Eden => Location::Eden,
+            ContainingHeap::Generation(generation, _) => Location::Tenured(generation),
+        }
+    }
+
+    /// The object's mark color
+    #[cfg(feature = "research")]
+    pub fn color(&self) -> Color {
+        debug_assert!(!self.is_null());
+
+        unsafe { self.inner().color() }
+    }
+
+    /// Sets the object's mark color, which the next collection that marks
+    /// overwrites, see [`BumpHeap::collect_mark_sweep_only`]
+    #[cfg(feature = "research")]
+    pub fn set_color(&mut self, color: Color) {
+        debug_assert!(!self.is_null());
+
+        unsafe { self.inner().color.store(color) };
+    }
+
+    /// Marks the object black if it's white, returning whether this call marked
+    /// it, so that several threads marking at once each claim it at most once
+    #[cfg(feature = "research")]
+    pub fn try_mark(&self) -> bool {
+        debug_assert!(!self.is_null());
+
+        unsafe { self.inner().try_mark() }
+    }
+
+    /// The number of bytes the object takes up in the heap, including its
+    /// [overhead](crate::BumpHeap::object_overhead) and, once it's tenured, the
+    /// rest of its pocket
+    pub fn allocated_size(&self) -> usize {
+        debug_assert!(!self.is_null());
+
+        match unsafe { self.inner().heap } {
+            ContainingHeap::Eden => unsafe { self.inner().size() },
+            ContainingHeap::Generation(_, pocket_size) => pocket_size,
+        }
+    }
+
+    /// Whether both handles point to the same object
+    pub fn same_object<U: ?Sized + Any>(&self, other: &Rooted<U>) -> bool {
+        ptr::eq(self.static_inner, other.static_inner)
+    }
+
     pub(crate) unsafe fn inner(&self) -> &RootedInner {
         &*self.static_inner
     }
@@ -36,23 +135,314 @@ impl<T: Sized + Any> ops::Deref for Rooted<T> {
 
     fn deref(&self) -> &Self::Target {
         debug_assert!(!self.is_null());
-        debug_assert!(unsafe { !self.inner().is_null() });
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
 
-        info!("Accessing rooted value at {:p}", self.inner().value_ptr());
+        #[cfg(feature = "trace-access")]
+        trace!("Accessing rooted value at {:p}", unsafe {
+            self.inner().value_ptr()
+        });
 
         unsafe { self.inner().value().value.downcast_ref().unwrap() }
     }
 }
 
+impl<T: Sized + Any> Rooted<T> {
+    /// Borrows the value, returning `None` instead of panicking if the object is
+    /// gone because its heap was dropped or its [epoch](crate::BumpHeap::free_region)
+    /// was freed
+    pub fn try_deref(&self) -> Option<&T> {
+        if self.is_null() {
+            return None;
+        }
+
+        unsafe { self.inner().downcast_ref() }
+    }
+
+    /// The address of the value, which is only valid until a collection moves
+    /// the object, see [`Rooted::refresh`]
+    pub fn as_ptr(&self) -> *const T {
+        &**self as *const T
+    }
+
+    /// Re-reads the address of the value after a collection that may have moved
+    /// it, for updating raw pointers taken from [`Rooted::as_ptr`]
+    ///
+    /// The handle itself stays valid across collections since the collector
+    /// updates it in place, only pointers derived from it go stale. Scavenges move
+    /// every surviving young object, and compactions report what they move to
+    /// [`BumpOptions::on_relocate`](crate::BumpOptions::on_relocate)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's heap was dropped or the object isn't in `heap`
+    pub fn refresh(&mut self, heap: &BumpHeap) -> *const T {
+        let ptr = self.as_ptr();
+
+        assert!(
+            heap.contains(unsafe { self.inner().data_ptr() }),
+            "Refreshed a Rooted against a heap it isn't in",
+        );
+
+        ptr
+    }
+
+    /// Moves the value out of the heap, like [`Box::into_inner`]
+    ///
+    /// The object is unrooted and its old copy is reclaimed by the next
+    /// collection that finds it. The object is marked as moved out of, so that
+    /// [`BumpHeap::free_region`](crate::BumpHeap::free_region) doesn't drop the
+    /// value a second time
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is [pinned](crate::BumpHeap::alloc_pinned_permanent)
+    pub fn into_inner(mut self) -> T {
+        assert!(
+            unsafe { !self.inner().pinned },
+            "Moved the value out of a pinned object",
+        );
+
+        // Reading the value also checks that the heap is still alive
+        let value = unsafe { ptr::read(&*self as *const T) };
+
+        // The bytes left behind mustn't be read again, so the object is retyped as
+        // one that marking (which traces unrooted objects too) skips and that
+        // sweeping it or freeing its region won't drop a second time
+        unsafe { self.inner_mut().forget_value() };
+        drop(self);
+
+        value
+    }
+
+    /// Keeps the object alive and in place forever, returning a reference to it
+    /// like [`Box::leak`]
+    ///
+    /// The object is tenured if it's still young and pinned where it lands, and it
+    /// stays rooted for good, so the collector never reclaims or relocates it and
+    /// it keeps its id. A heap holding leaked objects never gives its memory back,
+    /// even when it's dropped, so the reference can outlive the heap
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object isn't in `heap`, `heap` doesn't own its memory (like
+    /// a [`StaticBumpHeap`](crate::StaticBumpHeap)'s), or the object doesn't fit
+    /// in the first tenured generation
+    pub fn leak(self, heap: &mut BumpHeap) -> &'static T {
+        heap.leak_object(unsafe { self.inner() });
+
+        // Reading the value also checks that the heap is still alive
+        let value: *const T = &*self;
+        mem::forget(self);
+
+        unsafe { &*value }
+    }
+
+    /// Replaces the object's value, returning the old one
+    ///
+    /// The object stays where it is and keeps its identity. The old value is only
+    /// ever dropped by whoever it's returned to
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is [pinned](crate::BumpHeap::alloc_pinned_permanent)
+    pub fn replace(&mut self, value: T) -> T {
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+        assert!(
+            unsafe { !self.inner().pinned },
+            "Moved the value out of a pinned object",
+        );
+
+        bump_heap::write_barrier(self.id());
+        let slot = unsafe { self.inner_mut().downcast_mut::<T>().unwrap() };
+        mem::replace(slot, value)
+    }
+
+    /// Overwrites the object's value in place, dropping the old one
+    ///
+    /// The old value is dropped where it is rather than moved out, so unlike
+    /// [`Rooted::replace`] pinned objects can be written to, the same as with
+    /// [`Pin::set`]
+    pub fn write(&mut self, value: T) {
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+
+        bump_heap::write_barrier(self.id());
+        *unsafe { self.inner_mut().downcast_mut::<T>().unwrap() } = value;
+    }
+
+    /// Replaces the object's value with `f` applied to it, writing the result back
+    /// into the same place in the heap
+    ///
+    /// The same as [`Rooted::replace`], but for new values built from the old one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is [pinned](crate::BumpHeap::alloc_pinned_permanent).
+    /// If `f` panics the object is left without a value, so its destructor is
+    /// never run and reading it through the handle panics
+    pub fn map_in_place(&mut self, f: impl FnOnce(T) -> T) {
+        /// Forgets the object's value if `f` unwinds, since it's already been moved out
+        struct ForgetOnUnwind(*mut RootedInner);
+
+        impl Drop for ForgetOnUnwind {
+            fn drop(&mut self) {
+                unsafe { (*self.0).forget_value() };
+            }
+        }
+
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+        assert!(
+            unsafe { !self.inner().pinned },
+            "Moved the value out of a pinned object",
+        );
+
+        bump_heap::write_barrier(self.id());
+        let slot: *mut T = unsafe { self.inner_mut().downcast_mut::<T>().unwrap() };
+        unsafe {
+            let guard = ForgetOnUnwind(self.inner_mut());
+            slot.write(f(slot.read()));
+            mem::forget(guard);
+        }
+    }
+
+    /// Mutably borrows the value of a [pinned](crate::BumpHeap::alloc_pinned_permanent)
+    /// object, which the collector never moves and which can't be moved out of
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object isn't pinned
+    pub fn as_pin_mut(&mut self) -> Pin<&mut T> {
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+        assert!(
+            unsafe { self.inner().pinned },
+            "Pinned a Rooted that wasn't allocated with BumpHeap::alloc_pinned_permanent",
+        );
+
+        // Safety: Pinned objects are never relocated, and nothing that could move
+        // the value out of the object hands out a `&mut T` to it
+        unsafe { Pin::new_unchecked(self.inner_mut().downcast_mut::<T>().unwrap()) }
+    }
+
+    /// Forgets the object's type so that handles to different types can be kept
+    /// together, the type can be recovered with [`Rooted::downcast`]
+    pub fn erase(self) -> RootedAny {
+        let static_inner = self.static_inner;
+        mem::forget(self);
+
+        Rooted::new(static_inner)
+    }
+
+    /// Whether both handles' objects have equal values, which is what `==` does
+    pub fn value_eq(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        **self == **other
+    }
+}
+
+/// A handle to an object of any type, see [`Rooted::erase`]
+pub type RootedAny = Rooted<dyn Any>;
+
+impl Rooted<dyn Any> {
+    /// Whether the object is a `T`
+    pub fn is<T: Any>(&self) -> bool {
+        debug_assert!(!self.is_null());
+
+        unsafe { self.inner().type_id == TypeId::of::<T>() }
+    }
+
+    /// Recovers the handle's type, giving the handle back if the object isn't a `T`
+    pub fn downcast<T: Any>(self) -> Result<Rooted<T>, Self> {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        let static_inner = self.static_inner;
+        mem::forget(self);
+
+        Ok(Rooted::new(static_inner))
+    }
+
+    /// Borrows the value as a `T`, like `dyn Any`'s `downcast_ref`, returning `None`
+    /// if the object isn't a `T`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's heap was dropped
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+
+        unsafe { self.inner().downcast_ref() }
+    }
+
+    /// Mutably borrows the value as a `T`, returning `None` if the object isn't a `T`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object's heap was dropped or the object is
+    /// [pinned](crate::BumpHeap::alloc_pinned_permanent)
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        debug_assert!(!self.is_null());
+        assert!(
+            unsafe { !self.inner().is_null() },
+            "Accessed a Rooted after its heap was dropped",
+        );
+        assert!(
+            unsafe { !self.inner().pinned },
+            "Mutably borrowed a pinned object",
+        );
+
+        unsafe { self.inner_mut().downcast_mut() }
+    }
+}
+
+impl<T: Sized + Any + PartialEq> PartialEq for Rooted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value_eq(other)
+    }
+}
+
+impl<T: Sized + Any + Eq> Eq for Rooted<T> {}
+
 impl<T: ?Sized + Any> Drop for Rooted<T> {
     fn drop(&mut self) {
         debug_assert!(!self.is_null());
-        debug_assert!(unsafe { !self.inner().is_null() });
 
-        trace!("Dropping value at {:p}", self.inner().value_ptr());
+        trace!("Dropping value at {:p}", unsafe {
+            self.inner().value_ptr()
+        });
 
         unsafe {
-            self.inner_mut().rooted = false;
+            // The heap is gone and left the metadata for us to clean up, unless a
+            // weak handle still needs it
+            if self.inner().is_null() && self.inner().weak_handles == 0 {
+                drop(Box::from_raw(self.static_inner));
+            } else if self.inner().managed || self.inner().is_null() {
+                self.inner_mut().rooted = false;
+            }
         }
     }
 }
@@ -61,20 +451,134 @@ impl<T: ?Sized + Any> Drop for Rooted<T> {
 pub(crate) struct RootedInner {
     pub(crate) value: *mut HeapValue<dyn Any>,
     pub(crate) rooted: bool,
-    pub(crate) color: Color,
+    /// Whether dropping the handle unroots the object, see [`Rooted::unroot`]
+    pub(crate) managed: bool,
+    /// Whether the object must never be moved, see
+    /// [`BumpHeap::alloc_pinned_permanent`](crate::BumpHeap::alloc_pinned_permanent)
+    pub(crate) pinned: bool,
+    /// Whether the object's handle is a [`Soft`](crate::Soft) one, which the heap
+    /// clears under memory pressure
+    pub(crate) soft: bool,
+    /// Whether the object was [leaked](Rooted::leak), which keeps it rooted and
+    /// in place for as long as the program runs
+    pub(crate) leaked: bool,
+    /// The number of [`Weak`](crate::Weak) handles to the object, which keep its
+    /// metadata alive but not the object itself
+    pub(crate) weak_handles: usize,
+    pub(crate) color: AtomicColor,
     pub(crate) heap: ContainingHeap,
+    /// The number of collections the object has survived in its current generation,
+    /// scavenges while it's young and major collections once it's tenured
+    pub(crate) age: u8,
     pub(crate) size: usize,
+    /// The alignment the object is always moved to, which is more than its type's
+    /// for objects allocated with [`BumpHeap::alloc_aligned`](crate::BumpHeap::alloc_aligned)
+    pub(crate) align: usize,
+    pub(crate) id: AllocId,
+    pub(crate) type_id: TypeId,
+    #[cfg(feature = "type-profile")]
+    pub(crate) type_name: &'static str,
+    /// Reports the object's children, for objects allocated with a [`Trace`] bound
+    pub(crate) trace: Option<TraceFn>,
+    /// Measures the memory the object owns outside of the heap, for objects
+    /// allocated with a [`NativeSize`] bound
+    pub(crate) native: Option<NativeSizeFn>,
+    /// Hashes the object's value, for objects allocated with a [`Hash`] bound
+    pub(crate) hash: Option<HashFn>,
+    /// Drops the object's value in place, which is only done if the object is
+    /// reclaimed while it's still rooted, see [`Rooted`]
+    pub(crate) drop_value: DropFn,
+    /// The tag the object was allocated with, see
+    /// [`BumpHeap::alloc_tagged`](crate::BumpHeap::alloc_tagged)
+    pub(crate) tag: Option<&'static str>,
+    /// Where the object was allocated, see
+    /// [`BumpHeap::leak_report`](crate::BumpHeap::leak_report)
+    #[cfg(feature = "capture-backtrace")]
+    pub(crate) backtrace: Option<alloc::sync::Arc<std::backtrace::Backtrace>>,
+    /// The epoch the object was allocated in, see [`BumpHeap::free_region`](crate::BumpHeap::free_region)
+    pub(crate) epoch: Epoch,
     pub(crate) __pinned: PhantomPinned,
 }
 
+/// Traces the `HeapValue<T>` at the given address, with `T` erased
+pub(crate) type TraceFn = unsafe fn(*const u8, &mut Tracer);
+
+pub(crate) fn trace_fn<T: Trace + 'static>() -> TraceFn {
+    unsafe fn trace<T: Trace + 'static>(value: *const u8, tracer: &mut Tracer) {
+        (*(value as *const HeapValue<T>)).value.trace(tracer);
+    }
+
+    trace::<T>
+}
+
+/// Hashes the `HeapValue<T>` at the given address, with `T` erased
+pub(crate) type HashFn = unsafe fn(*const u8, &mut dyn Hasher);
+
+pub(crate) fn hash_fn<T: Hash + 'static>() -> HashFn {
+    unsafe fn hash<T: Hash + 'static>(value: *const u8, mut hasher: &mut dyn Hasher) {
+        (*(value as *const HeapValue<T>)).value.hash(&mut hasher);
+    }
+
+    hash::<T>
+}
+
+/// Drops the `HeapValue<T>` at the given address in place, with `T` erased
+pub(crate) type DropFn = unsafe fn(*mut u8);
+
+pub(crate) fn drop_fn<T: 'static>() -> DropFn {
+    unsafe fn drop_value<T: 'static>(value: *mut u8) {
+        ptr::drop_in_place(value as *mut HeapValue<T>);
+    }
+
+    drop_value::<T>
+}
+
+/// The [`DropFn`] of an object whose value has been moved out, which has nothing
+/// left to drop
+pub(crate) unsafe fn drop_nothing(_value: *mut u8) {}
+
+/// Measures the native memory of the `HeapValue<T>` at the given address, with `T` erased
+pub(crate) type NativeSizeFn = unsafe fn(*const u8) -> usize;
+
+pub(crate) fn native_size_fn<T: NativeSize + 'static>() -> NativeSizeFn {
+    unsafe fn native_bytes<T: NativeSize + 'static>(value: *const u8) -> usize {
+        (*(value as *const HeapValue<T>)).value.native_bytes()
+    }
+
+    native_bytes::<T>
+}
+
 impl RootedInner {
-    pub(crate) fn new<T: Any + 'static>(ptr: *mut HeapValue<T>, heap: ContainingHeap) -> Self {
+    pub(crate) fn new<T: Any + 'static>(
+        ptr: *mut HeapValue<T>,
+        heap: ContainingHeap,
+        id: AllocId,
+    ) -> Self {
         Self {
             value: ptr,
             rooted: true,
-            color: Color::White,
+            managed: true,
+            pinned: false,
+            soft: false,
+            leaked: false,
+            weak_handles: 0,
+            color: AtomicColor::new(Color::White),
             heap,
+            age: 0,
             size: mem::size_of::<HeapValue<T>>(),
+            align: mem::align_of::<HeapValue<T>>(),
+            id,
+            type_id: TypeId::of::<T>(),
+            #[cfg(feature = "type-profile")]
+            type_name: core::any::type_name::<T>(),
+            trace: None,
+            native: None,
+            hash: None,
+            drop_value: drop_fn::<T>(),
+            tag: None,
+            #[cfg(feature = "capture-backtrace")]
+            backtrace: None,
+            epoch: Epoch(0),
             __pinned: PhantomPinned,
         }
     }
@@ -87,8 +591,13 @@ impl RootedInner {
         self.rooted
     }
 
-    pub(crate) const fn color(&self) -> Color {
-        self.color
+    pub(crate) fn color(&self) -> Color {
+        self.color.load()
+    }
+
+    /// See [`AtomicColor::try_mark`]
+    pub(crate) fn try_mark(&self) -> bool {
+        self.color.try_mark()
     }
 
     pub(crate) const fn containing_heap(&self) -> ContainingHeap {
@@ -100,32 +609,155 @@ impl RootedInner {
     }
 
     #[inline]
-    pub(crate) unsafe fn value_mut(&mut self) -> &mut HeapValue<dyn Any> {
-        &mut *self.value
+    pub(crate) fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        if self.type_id == TypeId::of::<T>() && !self.is_null() {
+            Some(unsafe { &(*(self.value as *const HeapValue<T>)).value })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub(crate) unsafe fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        if self.type_id == TypeId::of::<T>() {
+            Some(&mut (*(self.value as *mut HeapValue<T>)).value)
+        } else {
+            None
+        }
     }
 
     pub(crate) fn is_null(&self) -> bool {
         self.value.is_null()
     }
 
+    /// Hands the object's children to `tracer`, returning `false` if the object
+    /// wasn't allocated with a way to trace it
+    pub(crate) fn trace_children(&self, tracer: &mut Tracer) -> bool {
+        match self.trace {
+            Some(trace) => {
+                tracer.set_owner(self.id);
+                unsafe { trace(self.data_ptr().as_ptr(), tracer) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The memory the object owns outside of the heap, or 0 if it wasn't
+    /// allocated with a way to measure it
+    pub(crate) fn native_bytes(&self) -> usize {
+        match self.native {
+            Some(native_bytes) => unsafe { native_bytes(self.data_ptr().as_ptr()) },
+            None => 0,
+        }
+    }
+
+    /// Hands the object's value to `hasher`, returning `false` if the object
+    /// wasn't allocated with a way to hash it
+    pub(crate) fn hash_value(&self, hasher: &mut dyn Hasher) -> bool {
+        match self.hash {
+            Some(hash) => {
+                unsafe { hash(self.data_ptr().as_ptr(), hasher) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The site the object's backtrace was captured at, if it has one
+    pub(crate) fn backtrace_site(&self) -> Option<AllocSite> {
+        #[cfg(feature = "capture-backtrace")]
+        {
+            self.backtrace
+                .as_ref()
+                .map(|backtrace| AllocSite::Backtrace(alloc::format!("{}", backtrace)))
+        }
+
+        #[cfg(not(feature = "capture-backtrace"))]
+        {
+            None
+        }
+    }
+
     pub(crate) fn value_ptr(&self) -> *mut HeapValue<dyn Any> {
         self.value
     }
+
+    /// The address of the object, without its vtable
+    pub(crate) fn data_ptr(&self) -> HeapPointer {
+        let raw_root =
+            unsafe { mem::transmute::<*mut HeapValue<dyn Any>, raw::TraitObject>(self.value) };
+        HeapPointer::new(raw_root.data as usize)
+    }
+
+    /// Marks the object's value as moved out of, so that it's never dropped,
+    /// traced, measured or hashed again and borrowing it through a handle fails
+    ///
+    /// The object keeps its size and place in the heap, it's only retyped as an
+    /// empty value so that nothing reads the bytes left behind
+    pub(crate) fn forget_value(&mut self) {
+        struct MovedOut;
+
+        let data = self.data_ptr().as_mut_ptr::<HeapValue<MovedOut>>();
+        self.value = data as *mut HeapValue<dyn Any>;
+        self.type_id = TypeId::of::<MovedOut>();
+        self.trace = None;
+        self.native = None;
+        self.hash = None;
+        self.drop_value = drop_nothing;
+    }
+
+    /// Points the root at the object's new address, keeping its vtable
+    pub(crate) unsafe fn relocate(&mut self, to: HeapPointer) {
+        let raw_root = mem::transmute::<*mut HeapValue<dyn Any>, raw::TraitObject>(self.value);
+
+        self.value =
+            mem::transmute::<raw::TraitObject, *mut HeapValue<dyn Any>>(raw::TraitObject {
+                data: to.as_mut_ptr(),
+                vtable: raw_root.vtable,
+            });
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[repr(u8)]
-pub(crate) enum Color {
-    Black,
-    Grey,
-    White,
+/// Releases a dead object's metadata, returning it if nothing points at it anymore
+///
+/// If a handle still points at the metadata its value is nulled out and it's
+/// leaked for the last handle to free
+pub(crate) fn leave_to_handles(root: Pin<Box<RootedInner>>) -> Option<Pin<Box<RootedInner>>> {
+    if !root.is_rooted() && root.weak_handles == 0 {
+        return Some(root);
+    }
+
+    unsafe {
+        let inner = Box::into_raw(Pin::into_inner_unchecked(root));
+        (*inner).value = ptr::null_mut::<HeapValue<()>>();
+    }
+
+    None
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub(crate) enum ContainingHeap {
     Eden,
-    Intermediate(usize),
+    /// A tenured generation, numbered from 1, and the size of the block the object
+    /// is in, which is either a pocket or, for objects too large for every pocket,
+    /// an exact-fit block that sweeping hands back to the generation's free list
+    Generation(u8, usize),
+}
+
+/// A cohort of allocations that can be freed all at once, see
+/// [`BumpHeap::free_region`](crate::BumpHeap::free_region)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Epoch(pub(crate) u32);
+
+/// The generation an object lives in, see [`Rooted::location`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// The young generation, where every object is allocated
+    Eden,
+    /// A tenured generation, numbered from 1
+    Tenured(u8),
 }
 
 pub(crate) struct HeapValue<T: Any + ?Sized + 'static> {
@@ -137,3 +769,397 @@ impl<T> HeapValue<T> {
         Self { value }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Location, RootedAny, RootedInner};
+    use crate::{BumpHeap, BumpOptions, Handle, Rooted, Trace, Tracer};
+    use alloc::rc::Rc;
+    use core::{
+        cell::Cell,
+        mem,
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use std::{panic, thread};
+
+    #[test]
+    fn identity_and_value_equality() {
+        let mut bump = BumpHeap::default();
+
+        let (a, b, c) = unsafe {
+            (
+                bump.alloc_unchecked(1usize),
+                bump.alloc_unchecked(1usize),
+                bump.alloc_unchecked(2usize),
+            )
+        };
+
+        assert!(a.same_object(&a));
+        assert!(!a.same_object(&b));
+        assert!(a.value_eq(&b));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn refresh() {
+        let mut bump = BumpHeap::default();
+
+        let mut rooted = unsafe { bump.alloc_unchecked(42usize) };
+        let cached = rooted.as_ptr();
+        assert_eq!(rooted.refresh(&bump), cached);
+
+        bump.scavenge();
+        let refreshed = rooted.refresh(&bump);
+        assert_ne!(refreshed, cached);
+        assert_eq!(refreshed, &*rooted as *const usize);
+        assert_eq!(unsafe { *refreshed }, 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "against a heap it isn't in")]
+    fn refresh_in_other_heap() {
+        let (mut bump, other) = (BumpHeap::default(), BumpHeap::default());
+
+        let mut rooted = unsafe { bump.alloc_unchecked(42usize) };
+        rooted.refresh(&other);
+    }
+
+    #[test]
+    fn into_inner() {
+        let mut bump = BumpHeap::default();
+
+        let handle: Handle<String> = bump.alloc(String::from("moved out"));
+        let id = handle.id();
+        bump.scavenge();
+
+        let mut owned = handle.into_inner();
+        owned.push('!');
+        assert_eq!(owned, "moved out!");
+
+        bump.major();
+        assert!(bump.locate(id).is_none());
+        assert_eq!(bump.live_count(), 0);
+    }
+
+    #[test]
+    fn try_deref() {
+        let mut bump = BumpHeap::default();
+
+        let kept: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let epoch = bump.new_epoch();
+        let freed: Rooted<usize> = unsafe { bump.alloc_unchecked(2) };
+        bump.scavenge();
+        assert_eq!(freed.try_deref(), Some(&2));
+
+        unsafe { bump.free_region(epoch) };
+        assert_eq!(freed.try_deref(), None);
+        assert_eq!(kept.try_deref(), Some(&1));
+
+        drop(bump);
+        assert_eq!(kept.try_deref(), None);
+    }
+
+    #[test]
+    fn allocated_size() {
+        let mut bump = BumpHeap::default();
+
+        let byte: Rooted<u8> = unsafe { bump.alloc_unchecked(1) };
+        let word: Rooted<[u8; 20]> = unsafe { bump.alloc_unchecked([0; 20]) };
+        assert_eq!(
+            BumpHeap::object_overhead::<u8>(),
+            byte.allocated_size() - mem::size_of::<u8>()
+        );
+        assert_eq!(
+            word.allocated_size(),
+            20 + BumpHeap::object_overhead::<[u8; 20]>()
+        );
+
+        // Tenured objects take up their whole pocket
+        bump.scavenge();
+        assert_eq!(word.allocated_size(), 32);
+    }
+
+    #[test]
+    fn location() {
+        let mut bump = BumpHeap::new(crate::BumpOptions::new().tenured_generations(&[1024 * 4; 2]));
+
+        let handle = bump.alloc(1usize);
+        assert_eq!(handle.location(), Location::Eden);
+
+        bump.scavenge();
+        assert_eq!(handle.location(), Location::Tenured(1));
+
+        bump.major();
+        assert_eq!(handle.location(), Location::Tenured(2));
+    }
+
+    #[test]
+    fn moved_out_values_arent_freed() {
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+        let drops = Rc::new(Cell::new(0));
+        let epoch = bump.new_epoch();
+
+        let moved = unsafe { bump.alloc_unchecked(Counted(Rc::clone(&drops))) }.into_inner();
+        let leaked: &'static Counted =
+            unsafe { bump.alloc_unchecked(Counted(Rc::clone(&drops))) }.leak(&mut bump);
+        let freed = unsafe { bump.alloc_unchecked(Counted(Rc::clone(&drops))) };
+
+        // Leaked objects are never freed, so their values aren't dropped
+        assert_eq!(unsafe { bump.free_region(epoch) }, 2);
+        assert_eq!(drops.get(), 1);
+        assert!(freed.try_deref().is_none());
+        assert!(Rc::ptr_eq(&leaked.0, &drops));
+
+        drop(moved);
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn replace() {
+        let mut bump = BumpHeap::default();
+
+        let mut rooted = unsafe { bump.alloc_unchecked(String::from("old")) };
+        let id = rooted.id();
+        assert_eq!(rooted.replace(String::from("new")), "old");
+
+        // Young objects aren't remembered, only tenured ones are
+        assert!(!bump.is_remembered(id));
+        bump.scavenge();
+        assert_eq!(*rooted, "new");
+        assert_eq!(rooted.id(), id);
+
+        assert_eq!(rooted.replace(String::from("newer")), "new");
+        assert!(bump.is_remembered(id));
+        bump.scavenge();
+        assert!(!bump.is_remembered(id));
+        assert_eq!(*rooted, "newer");
+    }
+
+    #[test]
+    fn write() {
+        let mut bump = BumpHeap::new(BumpOptions::new().old_heap_size(1024 * 16));
+
+        let old_child = bump.alloc(1u64);
+        let old_id = old_child.id();
+        let mut parent = unsafe { bump.alloc_traced(vec![old_child]) };
+        bump.scavenge();
+        assert_eq!(parent.location(), Location::Tenured(1));
+
+        let new_child = bump.alloc(2u64);
+        let new_id = new_child.id();
+        parent.write(vec![new_child]);
+        assert!(bump.is_remembered(parent.id()));
+        bump.scavenge();
+        assert!(!bump.is_remembered(parent.id()));
+        bump.major();
+
+        let mut tracer = Tracer::new();
+        (*parent).trace(&mut tracer);
+        assert_eq!(tracer.edges(), &[new_id]);
+        assert_eq!(*parent[0].get(&bump), 2);
+        assert_eq!(bump.locate(old_id), None);
+
+        let counted = Rc::new(());
+        let mut pinned = unsafe { bump.alloc_pinned_permanent(Rc::clone(&counted)) };
+        pinned.write(Rc::new(()));
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn poll_pinned_future() {
+        use core::{
+            future::Future,
+            task::{Context, Poll, Waker},
+        };
+
+        struct YieldOnce(bool);
+
+        impl Future for YieldOnce {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+                if self.0 {
+                    Poll::Ready(())
+                } else {
+                    self.0 = true;
+                    Poll::Pending
+                }
+            }
+        }
+
+        let mut bump = BumpHeap::default();
+
+        // Holds a reference to its own state across the yield, so it's `!Unpin`
+        let mut future = unsafe {
+            bump.alloc_pinned_permanent(async {
+                let numbers = [1, 2, 3];
+                let sum = &numbers;
+                YieldOnce(false).await;
+                sum.iter().sum::<i32>()
+            })
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(future.as_pin_mut().poll(&mut cx), Poll::Pending);
+        let garbage: Vec<Handle<usize>> = (0..100).map(|i| bump.alloc(i)).collect();
+        drop(garbage);
+        bump.scavenge();
+        bump.major();
+        bump.compact_now();
+        assert_eq!(future.as_pin_mut().poll(&mut cx), Poll::Ready(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "wasn't allocated with BumpHeap::alloc_pinned_permanent")]
+    fn pin_unpinned_object() {
+        let mut bump = BumpHeap::default();
+        let mut rooted = unsafe { bump.alloc_unchecked(1usize) };
+
+        let _ = rooted.as_pin_mut();
+    }
+
+    #[test]
+    fn leaked_objects_live_forever() {
+        let mut bump = BumpHeap::default();
+
+        let rooted = unsafe { bump.alloc_unchecked(String::from("interned")) };
+        let id = rooted.id();
+        let leaked: &'static String = rooted.leak(&mut bump);
+        let address = bump.locate(id);
+        assert!(address.is_some());
+        assert_eq!(bump.live_count(), 1);
+
+        // There's no single full collection, so this runs every kind there is
+        let garbage: Vec<Handle<usize>> = (0..100).map(|i| bump.alloc(i)).collect();
+        drop(garbage);
+        for _ in 0..4 {
+            bump.scavenge();
+            bump.major();
+            bump.compact_now();
+            bump.collect_mark_sweep_only();
+        }
+        assert_eq!(bump.locate(id), address);
+        assert_eq!(leaked, "interned");
+        assert!(!bump.hibernate());
+
+        drop(bump);
+        assert_eq!(leaked, "interned");
+    }
+
+    #[test]
+    fn racing_marks() {
+        struct Shared<'a>(&'a RootedInner);
+        unsafe impl Sync for Shared<'_> {}
+
+        let mut bump = BumpHeap::default();
+        let rooted = unsafe { bump.alloc_unchecked(1usize) };
+        let inner = Shared(unsafe { rooted.inner() });
+
+        let winners = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    if inner.0.try_mark() {
+                        winners.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(winners.load(Ordering::Relaxed), 1);
+        assert_eq!(unsafe { rooted.inner() }.color(), Color::Black);
+    }
+
+    #[test]
+    fn map_in_place() {
+        let mut bump = BumpHeap::default();
+
+        let mut counter: Rooted<u64> = unsafe { bump.alloc_unchecked(41) };
+        let address = bump.locate(counter.id());
+        counter.map_in_place(|count| count + 1);
+        assert_eq!(*counter, 42);
+        assert_eq!(bump.locate(counter.id()), address);
+
+        let mut name: Rooted<String> = unsafe { bump.alloc_unchecked(String::from("heap")) };
+        name.map_in_place(|name| name.to_uppercase());
+        bump.scavenge();
+        assert_eq!(*name, "HEAP");
+
+        assert!(!bump.is_remembered(name.id()));
+        name.map_in_place(|name| name + "S");
+        assert!(bump.is_remembered(name.id()));
+        assert_eq!(*name, "HEAPS");
+    }
+
+    #[test]
+    fn map_in_place_panics() {
+        let mut bump = BumpHeap::default();
+        let epoch = bump.new_epoch();
+        let counted = Rc::new(());
+
+        let mut rooted = unsafe { bump.alloc_unchecked(Rc::clone(&counted)) };
+        let panicked = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            rooted.map_in_place(|_| panic!("Failed to map"));
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(Rc::strong_count(&counted), 1);
+
+        assert!(rooted.try_deref().is_none());
+        assert!(
+            panic::catch_unwind(panic::AssertUnwindSafe(|| Rc::strong_count(&*rooted))).is_err()
+        );
+
+        bump.scavenge();
+        bump.major();
+        assert_eq!(unsafe { bump.free_region(epoch) }, 1);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn erased_handles() {
+        let mut bump = BumpHeap::default();
+
+        let values: Vec<RootedAny> = unsafe {
+            vec![
+                bump.alloc_unchecked(10u32).erase(),
+                bump.alloc_unchecked(String::from("ten")).erase(),
+            ]
+        };
+        bump.scavenge();
+        assert!(values[0].is::<u32>() && !values[0].is::<String>());
+
+        let mut values = values.into_iter();
+        let number = values.next().unwrap();
+        let number = number.downcast::<String>().unwrap_err();
+        assert_eq!(*number.downcast::<u32>().unwrap(), 10);
+
+        let string = values.next().unwrap().downcast::<String>().unwrap();
+        assert_eq!(*string, "ten");
+        assert_eq!(bump.live_count(), 1);
+    }
+
+    #[test]
+    fn erased_borrows() {
+        let mut bump = BumpHeap::default();
+
+        let mut value: RootedAny = unsafe { bump.alloc_unchecked(10u32).erase() };
+        assert_eq!(value.downcast_ref::<u64>(), None);
+        assert_eq!(value.downcast_mut::<String>(), None);
+        assert_eq!(value.downcast_ref::<u32>(), Some(&10));
+
+        *value.downcast_mut::<u32>().unwrap() += 1;
+        bump.scavenge();
+        assert_eq!(value.downcast_ref::<u32>(), Some(&11));
+        assert_eq!(*value.downcast::<u32>().unwrap(), 11);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    buffer::HeapBuffer,
+    bump_heap::{BumpHeap, BumpOptions},
+    error::AllocError,
+    memory::AllocId,
+    rooted::Rooted,
+};
+use core::{marker::PhantomData, mem, ops};
+
+/// A [`BumpHeap`] that can hold values borrowing for `'a`, handing out
+/// [`ScopedRooted`]s that can't outlive the borrow
+///
+/// Scoped values can't be downcast since they aren't `'static`, so they're
+/// stored as the bytes of a [`HeapBuffer`] and only ever read back as the type
+/// they were allocated as. Their values are moved by collections but never
+/// dropped, since the heap only sees their bytes
+pub struct ScopedBumpHeap<'a> {
+    heap: BumpHeap,
+    __scope: PhantomData<&'a ()>,
+}
+
+impl<'a> ScopedBumpHeap<'a> {
+    /// # Panics
+    ///
+    /// Panics if the heap can't be created, see [`BumpHeap::try_new`]
+    pub fn new(options: BumpOptions) -> Self {
+        Self {
+            heap: BumpHeap::new(options),
+            __scope: PhantomData,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object can't be allocated
+    pub unsafe fn alloc<T: 'a>(&mut self, value: T) -> ScopedRooted<'a, T> {
+        match self.try_alloc(value) {
+            Ok(rooted) => rooted,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Buffers only keep their bytes aligned to a `usize`, so more strictly
+    /// aligned values are rejected
+    ///
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc<T: 'a>(&mut self, value: T) -> Result<ScopedRooted<'a, T>, AllocError> {
+        let align = mem::align_of::<T>();
+        if align > mem::align_of::<HeapBuffer>() {
+            return Err(AllocError::UnsupportedAlignment(align));
+        }
+
+        let value = mem::ManuallyDrop::new(value);
+        let buffer = self
+            .heap
+            .alloc_raw_buffer(&*value as *const T as *const u8, mem::size_of::<T>())?;
+
+        Ok(ScopedRooted {
+            buffer,
+            __type: PhantomData,
+        })
+    }
+
+    pub fn heap(&self) -> &BumpHeap {
+        &self.heap
+    }
+
+    /// The heap itself, for collecting and allocating `'static` objects
+    pub fn heap_mut(&mut self) -> &mut BumpHeap {
+        &mut self.heap
+    }
+}
+
+impl Default for ScopedBumpHeap<'_> {
+    fn default() -> Self {
+        Self::new(BumpOptions::default())
+    }
+}
+
+/// A handle to an object in a [`ScopedBumpHeap`], which can't outlive the
+/// borrows its value holds
+#[derive(Debug)]
+pub struct ScopedRooted<'a, T: 'a> {
+    buffer: Rooted<HeapBuffer>,
+    __type: PhantomData<&'a T>,
+}
+
+impl<'a, T: 'a> ScopedRooted<'a, T> {
+    /// The object's identity, which stays the same when the collector moves it
+    pub fn id(&self) -> AllocId {
+        self.buffer.id()
+    }
+}
+
+impl<'a, T: 'a> ops::Deref for ScopedRooted<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The buffer holds exactly the bytes of a `T`, and its bytes are
+        // aligned to a `usize`, which `try_alloc` checked is enough for a `T`
+        unsafe { &*(self.buffer.as_slice().as_ptr() as *const T) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Token<'a> {
+        text: &'a str,
+        line: usize,
+    }
+
+    #[test]
+    fn borrowed_values() {
+        let source = String::from("let x = 10;");
+        let mut heap = ScopedBumpHeap::default();
+
+        let tokens: Vec<ScopedRooted<Token>> = source
+            .split(' ')
+            .map(|text| unsafe { heap.alloc(Token { text, line: 1 }) })
+            .collect();
+        heap.heap_mut().scavenge();
+        heap.heap_mut().major();
+
+        assert_eq!(*tokens[1], Token { text: "x", line: 1 });
+        assert_eq!(
+            tokens.iter().map(|token| token.text).collect::<Vec<_>>(),
+            ["let", "x", "=", "10;"],
+        );
+    }
+
+    #[test]
+    fn overaligned_values() {
+        #[repr(align(32))]
+        struct Aligned<'a>(#[allow(dead_code)] &'a u8);
+
+        let byte = 0;
+        let mut heap = ScopedBumpHeap::default();
+        assert_eq!(
+            unsafe { heap.try_alloc(Aligned(&byte)) }.err(),
+            Some(AllocError::UnsupportedAlignment(32))
+        );
+    }
+}
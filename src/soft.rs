@@ -0,0 +1,161 @@
+use crate::{
+    handle::Handle,
+    memory::AllocId,
+    rooted::{Rooted, RootedInner},
+};
+use core::{any::Any, marker::PhantomData, mem};
+
+/// A handle that keeps its object alive until the heap comes under memory pressure
+///
+/// Soft handles root their objects through normal collections, but a
+/// [major collection](crate::BumpHeap::major) that starts while the heap's
+/// [memory pressure](crate::BumpHeap::memory_pressure) is `High` or `Critical`
+/// clears every soft handle first and reclaims their objects, which makes them
+/// a good fit for caches whose entries can be recreated
+#[derive(Debug)]
+pub struct Soft<T: Sized + Any> {
+    static_inner: *mut RootedInner,
+    __type: PhantomData<T>,
+}
+
+impl<T: Sized + Any> Soft<T> {
+    /// Turns a handle into a soft one
+    pub fn new(mut rooted: Rooted<T>) -> Self {
+        unsafe { rooted.inner_mut().soft = true };
+
+        let static_inner = unsafe { rooted.inner_mut() as *mut RootedInner };
+        mem::forget(rooted);
+
+        Self {
+            static_inner,
+            __type: PhantomData,
+        }
+    }
+
+    /// Whether the heap has cleared the handle, or has been dropped
+    pub fn is_cleared(&self) -> bool {
+        unsafe { (*self.static_inner).is_null() }
+    }
+
+    /// The object's identity, or `None` if the handle has been cleared
+    pub fn id(&self) -> Option<AllocId> {
+        if self.is_cleared() {
+            None
+        } else {
+            Some(unsafe { (*self.static_inner).id })
+        }
+    }
+
+    /// Turns the handle back into a normal one, returning `None` if it's been
+    /// cleared
+    ///
+    /// The object comes back as a [`Handle`] so that borrowing its value borrows
+    /// the heap, the same as for a freshly allocated object
+    pub fn get(self) -> Option<Handle<T>> {
+        if self.is_cleared() {
+            return None;
+        }
+
+        let static_inner = self.static_inner;
+        mem::forget(self);
+
+        unsafe { (*static_inner).soft = false };
+        Some(Handle::new(Rooted::new(static_inner)))
+    }
+}
+
+impl<T: Sized + Any> Drop for Soft<T> {
+    fn drop(&mut self) {
+        if !self.is_cleared() {
+            unsafe { (*self.static_inner).soft = false };
+        }
+
+        // Dropping the handle unroots the object or frees its cleared metadata
+        drop(Rooted::<T>::new(self.static_inner));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BumpHeap, BumpOptions, PressureThresholds};
+
+    #[test]
+    fn survives_normal_collections() {
+        let mut bump = BumpHeap::default();
+        let soft = Soft::new(unsafe { bump.alloc_unchecked(10usize) });
+        let id = soft.id().unwrap();
+
+        bump.scavenge();
+        bump.major();
+        assert!(bump.locate(id).is_some());
+
+        let handle = soft.get().unwrap();
+        assert_eq!(*handle.get(&bump), 10);
+    }
+
+    #[test]
+    fn cleared_under_pressure() {
+        let options = BumpOptions::default().pressure_thresholds(PressureThresholds {
+            medium: 0,
+            high: 0,
+            critical: 100,
+        });
+        let mut bump = BumpHeap::new(options);
+
+        let soft = Soft::new(unsafe { bump.alloc_unchecked(10usize) });
+        let strong: Rooted<usize> = unsafe { bump.alloc_unchecked(20) };
+        let id = soft.id().unwrap();
+
+        bump.scavenge();
+        bump.major();
+
+        assert!(soft.is_cleared());
+        assert!(bump.locate(id).is_none());
+        assert!(soft.get().is_none());
+        assert_eq!(*strong, 20);
+    }
+
+    #[test]
+    fn cleared_values_are_dropped() {
+        use alloc::rc::Rc;
+
+        let options = BumpOptions::default().pressure_thresholds(PressureThresholds {
+            medium: 0,
+            high: 0,
+            critical: 100,
+        });
+        let mut bump = BumpHeap::new(options);
+
+        let value = Rc::new(());
+        let soft = Soft::new(unsafe { bump.alloc_unchecked(Rc::clone(&value)) });
+        bump.scavenge();
+        assert_eq!(Rc::strong_count(&value), 2);
+
+        // The object is still rooted by its soft handle when it's reclaimed
+        bump.major();
+        assert!(soft.is_cleared());
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn dropped_soft_handles_unroot() {
+        let mut bump = BumpHeap::default();
+        let soft = Soft::new(unsafe { bump.alloc_unchecked(10usize) });
+        let id = soft.id().unwrap();
+
+        drop(soft);
+        bump.scavenge();
+        assert!(bump.locate(id).is_none());
+    }
+
+    #[test]
+    fn outlives_heap() {
+        let mut bump = BumpHeap::default();
+        let soft = Soft::new(unsafe { bump.alloc_unchecked(10usize) });
+
+        drop(bump);
+        assert!(soft.is_cleared());
+        assert!(soft.get().is_none());
+    }
+}
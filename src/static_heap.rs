@@ -0,0 +1,170 @@
+use crate::{
+    bump_heap::{BumpHeap, BumpOptions, ScavengeOutcome},
+    counters::GcCounters,
+    error::AllocError,
+    handle::Handle,
+    memory::HeapPointer,
+    rooted::Rooted,
+    sweep_heap::CompactStats,
+};
+use core::{any::Any, marker::PhantomPinned, pin::Pin};
+
+#[repr(C, align(16))]
+struct Storage<const YOUNG: usize, const OLD: usize> {
+    young: [u8; YOUNG],
+    old: [u8; OLD],
+}
+
+/// A [`BumpHeap`] whose generations live inline in the struct instead of in a
+/// separate allocation, making it usable on the stack or in a `static`
+///
+/// Since the heap points into itself it has to be pinned before it's used, and
+/// it never frees its storage since there's nothing to free. Only the objects
+/// themselves are stored inline, their metadata and the heap's own bookkeeping
+/// are allocated through the global allocator the same as for any other heap.
+///
+/// The most used parts of the heap's API are forwarded, the rest can be reached
+/// through [`StaticBumpHeap::heap`] and [`StaticBumpHeap::heap_mut`]
+pub struct StaticBumpHeap<const YOUNG: usize, const OLD: usize> {
+    storage: Storage<YOUNG, OLD>,
+    heap: Option<BumpHeap>,
+    __pinned: PhantomPinned,
+}
+
+impl<const YOUNG: usize, const OLD: usize> StaticBumpHeap<YOUNG, OLD> {
+    pub const fn new() -> Self {
+        Self {
+            storage: Storage {
+                young: [0; YOUNG],
+                old: [0; OLD],
+            },
+            heap: None,
+            __pinned: PhantomPinned,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn alloc<T: Sized + Any + 'static>(self: Pin<&mut Self>, value: T) -> Rooted<T> {
+        self.heap_mut().alloc_unchecked(value)
+    }
+
+    /// # Safety
+    ///
+    /// See [`BumpHeap::try_alloc_unchecked`]
+    pub unsafe fn try_alloc<T: Sized + Any + 'static>(
+        self: Pin<&mut Self>,
+        value: T,
+    ) -> Result<Rooted<T>, AllocError> {
+        self.heap_mut().try_alloc_unchecked(value)
+    }
+
+    /// See [`BumpHeap::alloc`]
+    pub fn alloc_handle<T: Sized + Any + 'static>(self: Pin<&mut Self>, value: T) -> Handle<T> {
+        unsafe { self.heap_mut().alloc(value) }
+    }
+
+    /// See [`BumpHeap::try_alloc`]
+    pub fn try_alloc_handle<T: Sized + Any + 'static>(
+        self: Pin<&mut Self>,
+        value: T,
+    ) -> Result<Handle<T>, AllocError> {
+        unsafe { self.heap_mut().try_alloc(value) }
+    }
+
+    pub fn scavenge(self: Pin<&mut Self>) -> ScavengeOutcome {
+        unsafe { self.heap_mut().scavenge() }
+    }
+
+    pub fn major(self: Pin<&mut Self>) {
+        unsafe { self.heap_mut().major() }
+    }
+
+    /// See [`BumpHeap::compact_now`]
+    pub fn compact_now(self: Pin<&mut Self>) -> CompactStats {
+        unsafe { self.heap_mut().compact_now() }
+    }
+
+    /// See [`BumpHeap::collect_mark_sweep_only`]
+    pub fn collect_mark_sweep_only(self: Pin<&mut Self>) {
+        unsafe { self.heap_mut().collect_mark_sweep_only() }
+    }
+
+    pub fn counters(&self) -> Option<&GcCounters> {
+        self.heap().map(BumpHeap::counters)
+    }
+
+    /// The heap, or `None` if nothing has been done with it yet
+    pub fn heap(&self) -> Option<&BumpHeap> {
+        self.heap.as_ref()
+    }
+
+    /// The heap, which is built the first time this is called once `self` is
+    /// pinned in its final location
+    ///
+    /// # Safety
+    ///
+    /// The returned heap points into `self`, so it must not be moved out of it
+    /// (with [`mem::swap`](core::mem::swap) or the like), and nothing else may be
+    /// moved into its place
+    pub unsafe fn heap_mut(self: Pin<&mut Self>) -> &mut BumpHeap {
+        let this = self.get_unchecked_mut();
+
+        if this.heap.is_none() {
+            let start = HeapPointer::from(&mut this.storage as *mut Storage<YOUNG, OLD>);
+            let options = BumpOptions::new()
+                .young_heap_size(YOUNG)
+                .old_heap_size(OLD)
+                .allow_undersized_old_gen(true);
+
+            this.heap = Some(BumpHeap::from_region(start, options));
+        }
+
+        this.heap.as_mut().unwrap()
+    }
+}
+
+impl<const YOUNG: usize, const OLD: usize> Default for StaticBumpHeap<YOUNG, OLD> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+
+    #[test]
+    fn allocate_and_scavenge() {
+        let mut heap = pin!(StaticBumpHeap::<1024, 1024>::new());
+
+        let rooted: Rooted<usize> = unsafe { heap.as_mut().alloc(100) };
+        assert_eq!(*rooted, 100);
+
+        heap.as_mut().scavenge();
+        assert_eq!(*rooted, 100);
+        assert_eq!(heap.counters().unwrap().scavenge_count, 1);
+
+        let handle = heap.as_mut().alloc_handle(200usize);
+        heap.as_mut().scavenge();
+        heap.as_mut().collect_mark_sweep_only();
+        assert_eq!(*handle.get(heap.heap().unwrap()), 200);
+        assert_eq!(heap.heap().unwrap().live_count(), 2);
+    }
+
+    #[test]
+    fn capacity_is_enforced() {
+        let mut heap = pin!(StaticBumpHeap::<64, 1024>::new());
+
+        let result = unsafe { heap.as_mut().try_alloc([0u8; 128]) };
+        assert_eq!(
+            result.err(),
+            Some(AllocError::TooLarge {
+                size: 128,
+                capacity: 64
+            })
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    buffer::HeapBuffer,
+    bump_heap::{BumpHeap, BumpOptions},
+    rooted::Rooted,
+};
+use alloc::vec::Vec;
+
+/// The most objects the tester keeps alive at once, so that the tenured
+/// generation never fills up with live objects
+const MAX_LIVE: usize = 200;
+
+/// The longest a buffer is grown to
+const MAX_BUFFER_LEN: usize = 96;
+
+/// Runs randomized sequences of allocations, drops and collections against a
+/// heap, checking after every step that every live handle still reads what was
+/// written to it
+///
+/// Everything is derived from the seed, so a failing sequence can be reproduced
+/// by running the same seed again
+pub struct StressTester {
+    seed: u64,
+    rng: SplitMix64,
+    steps: usize,
+    live: Vec<Live>,
+    heap: BumpHeap,
+}
+
+enum Live {
+    Word(Rooted<u64>, u64),
+    Quad(Rooted<[u64; 4]>, u64),
+    Line(Rooted<[u64; 16]>, u64),
+    Buffer(Rooted<HeapBuffer>, Vec<u8>),
+}
+
+impl StressTester {
+    pub fn new(seed: u64) -> Self {
+        let options = BumpOptions::new()
+            .young_heap_size(1024 * 16)
+            .old_heap_size(1024 * 128);
+
+        Self::with_options(seed, options)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a heap can't be created with `options`
+    pub fn with_options(seed: u64, options: BumpOptions) -> Self {
+        Self {
+            seed,
+            rng: SplitMix64(seed),
+            steps: 0,
+            live: Vec::new(),
+            heap: BumpHeap::new(options),
+        }
+    }
+
+    pub fn heap(&self) -> &BumpHeap {
+        &self.heap
+    }
+
+    pub const fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Runs `steps` random steps, verifying the heap after each one
+    ///
+    /// # Panics
+    ///
+    /// Panics if verification fails, see [`StressTester::verify`]
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+            self.verify();
+        }
+    }
+
+    /// Runs a single random step
+    pub fn step(&mut self) {
+        self.steps += 1;
+
+        let roll = self.rng.below(100);
+        match roll {
+            0..=44 if self.live.len() < MAX_LIVE => self.allocate(),
+            0..=69 if !self.live.is_empty() => {
+                let index = self.rng.below(self.live.len() as u64) as usize;
+                self.live.swap_remove(index);
+            }
+            70..=79 => self.extend_buffer(),
+            80..=89 => {
+                self.heap.scavenge();
+            }
+            90..=95 => self.heap.major(),
+            96..=99 => {
+                self.heap.compact_now();
+            }
+            _ => self.allocate(),
+        }
+    }
+
+    fn allocate(&mut self) {
+        if self.live.len() >= MAX_LIVE {
+            return;
+        }
+
+        let value = self.rng.next();
+        let live = unsafe {
+            match self.rng.below(4) {
+                0 => Live::Word(self.heap.alloc_unchecked(value), value),
+                1 => Live::Quad(self.heap.alloc_unchecked(pattern(value)), value),
+                2 => Live::Line(self.heap.alloc_unchecked(pattern(value)), value),
+                _ => {
+                    let bytes = self.bytes();
+                    let buffer = self.heap.alloc_buffer(&bytes).unwrap();
+                    Live::Buffer(buffer, bytes)
+                }
+            }
+        };
+
+        self.live.push(live);
+    }
+
+    fn extend_buffer(&mut self) {
+        let bytes = self.bytes();
+        let buffer = self.live.iter_mut().find_map(|live| match live {
+            Live::Buffer(buffer, expected) if expected.len() + bytes.len() <= MAX_BUFFER_LEN => {
+                Some((buffer, expected))
+            }
+            _ => None,
+        });
+
+        if let Some((buffer, expected)) = buffer {
+            unsafe { self.heap.extend_buffer(buffer, &bytes).unwrap() };
+            expected.extend_from_slice(&bytes);
+        }
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = self.rng.below(16) as usize;
+        (0..len).map(|_| self.rng.next() as u8).collect()
+    }
+
+    /// Checks that every live handle reads its expected value and that the heap
+    /// agrees with each handle about where its object is
+    ///
+    /// # Panics
+    ///
+    /// Panics with the seed and step if anything is wrong
+    pub fn verify(&self) {
+        for live in &self.live {
+            let (intact, id, inner) = match live {
+                Live::Word(rooted, value) => {
+                    (**rooted == *value, rooted.id(), unsafe { rooted.inner() })
+                }
+                Live::Quad(rooted, value) => (**rooted == pattern(*value), rooted.id(), unsafe {
+                    rooted.inner()
+                }),
+                Live::Line(rooted, value) => (**rooted == pattern(*value), rooted.id(), unsafe {
+                    rooted.inner()
+                }),
+                Live::Buffer(rooted, bytes) => {
+                    (rooted.as_slice() == &bytes[..], rooted.id(), unsafe {
+                        rooted.inner()
+                    })
+                }
+            };
+
+            assert!(
+                intact,
+                "Stress test (seed {}, step {}): {} was corrupted",
+                self.seed, self.steps, id,
+            );
+            assert_eq!(
+                self.heap.locate(id),
+                Some(inner.data_ptr()),
+                "Stress test (seed {}, step {}): the heap lost track of {}",
+                self.seed,
+                self.steps,
+                id,
+            );
+
+            let bounds = self.heap.bounds_of(inner.containing_heap());
+            let address = inner.data_ptr();
+            assert!(
+                address >= bounds.0 && address + inner.size() <= bounds.1,
+                "Stress test (seed {}, step {}): {} is outside of its generation",
+                self.seed,
+                self.steps,
+                id,
+            );
+        }
+    }
+}
+
+fn pattern<const N: usize>(value: u64) -> [u64; N] {
+    let mut words = [0; N];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = value.wrapping_add(i as u64);
+    }
+
+    words
+}
+
+/// A small, deterministic generator, see <https://prng.di.unimi.it/splitmix64.c>
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: usize = if cfg!(miri) { 50 } else { 2000 };
+
+    #[test]
+    fn random_sequences() {
+        for seed in 0..8 {
+            let mut tester = StressTester::new(seed);
+            tester.run(STEPS);
+
+            assert_eq!(tester.steps(), STEPS);
+            assert!(tester.heap().counters().scavenge_count > 0);
+        }
+    }
+
+    #[test]
+    fn reproducible() {
+        let mut first = StressTester::new(0xBA11A57);
+        let mut second = StressTester::new(0xBA11A57);
+        first.run(STEPS / 4);
+        second.run(STEPS / 4);
+
+        assert_eq!(first.heap().counters(), second.heap().counters());
+    }
+}
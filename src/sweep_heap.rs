@@ -1,97 +1,349 @@
 use crate::{
-    free_list::{FreeList, PocketSize},
+    counters::GcCounters,
+    error::HeapCreateError,
+    free_list::{FreeBlock, FreeList, PocketSizes},
     memory::{self, HeapPointer},
-    rooted::{ContainingHeap, RootedInner},
+    rooted::{self, ContainingHeap, HeapValue, RootedInner},
 };
 use alloc::{boxed::Box, vec::Vec};
-use core::{mem, pin::Pin, raw};
+use core::{mem, pin::Pin, ptr};
 
+/// A generation of size-segregated pockets that objects are swept from in
+/// place, and compacted once it gets too fragmented
+///
+/// A [`BumpHeap`](crate::BumpHeap) carves its tenured generations out of its own
+/// region, but one built on its own allocates and frees its own memory, and can
+/// be handed to [`BumpHeap::with_intermediate`](crate::BumpHeap::with_intermediate)
+/// to be used as a heap's first tenured generation
 #[derive(Debug)]
-pub(crate) struct SweepHeap {
+pub struct SweepHeap {
     start: HeapPointer,
     size: usize,
     free_list: FreeList,
+    /// Whether the heap allocated its memory and frees it when dropped, instead
+    /// of being laid out over someone else's region
+    owns_memory: bool,
 }
 
 impl SweepHeap {
+    /// # Panics
+    ///
+    /// Panics if the heap can't be created, see [`SweepHeap::try_new`]
     pub fn new(size: usize) -> Self {
-        let layout = alloc::alloc::Layout::from_size_align(size, memory::page_size())
-            .expect("Failed to create heap layout");
+        match Self::try_new(size) {
+            Ok(heap) => heap,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_new(size: usize) -> Result<Self, HeapCreateError> {
+        Self::try_with_pockets(size, PocketSizes::default())
+    }
+
+    /// Allocates a `size` byte heap that sorts objects into `pocket_sizes`
+    pub fn try_with_pockets(
+        size: usize,
+        pocket_sizes: PocketSizes,
+    ) -> Result<Self, HeapCreateError> {
+        let align = memory::page_size();
+        let layout = alloc::alloc::Layout::from_size_align(size, align)
+            .map_err(|_| HeapCreateError::InvalidLayout { size, align })?;
 
-        // Safety: With a valid Layout, the allocation should be successful.
-        // Additionally, the pointer is checked for `null`, so the resulting pointer
-        // should also be to valid memory.
+        // Safety: The layout is valid and the pointer is checked for `null`, so
+        // the resulting pointer is to valid memory
         // TODO: Is it worth it to use `alloc::alloc` over `alloc::alloc_zeroed`?
-        let start = HeapPointer::new(unsafe { alloc::alloc::alloc_zeroed(layout) } as usize);
-        assert!(!start.is_null(), "The pointer to allocated memory is null");
+        let start = unsafe { alloc::alloc::alloc_zeroed(layout) };
+        if start.is_null() {
+            return Err(HeapCreateError::AllocationFailed { size });
+        }
+        let start = HeapPointer::new(start as usize);
 
-        Self {
+        Ok(Self {
             start,
             size,
-            free_list: FreeList::new(start, size),
-        }
+            free_list: FreeList::with_pockets(start, size, pocket_sizes),
+            owns_memory: true,
+        })
     }
 
-    pub const fn from_region(start: HeapPointer, size: usize) -> Self {
+    /// Lays a heap out over the `size` bytes at `start`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start + size` wraps around the address space
+    pub(crate) fn from_region(start: HeapPointer, size: usize, pocket_sizes: PocketSizes) -> Self {
         Self {
             start,
             size,
-            free_list: FreeList::new(start, size),
+            free_list: FreeList::with_pockets(start, size, pocket_sizes),
+            owns_memory: false,
         }
     }
 
-    pub fn alloc(&mut self, size: usize) -> Option<(HeapPointer, usize)> {
+    pub(crate) fn alloc(&mut self, size: usize) -> Option<(HeapPointer, usize)> {
         self.free_list.alloc(size)
     }
 
-    pub fn collect(&mut self, roots: &mut Vec<Pin<Box<RootedInner>>>) {
-        self.sweep(roots);
+    /// See [`FreeList::alloc_aligned`]
+    pub(crate) fn alloc_aligned(
+        &mut self,
+        size: usize,
+        align: usize,
+    ) -> Option<(HeapPointer, usize)> {
+        self.free_list.alloc_aligned(size, align)
+    }
+
+    /// Allocates a block for a `T`, returning it and its size
+    ///
+    /// `T`s too large for every pocket get a block of their own, use
+    /// [`assert_fits_pocket`](crate::assert_fits_pocket) to make sure that a type
+    /// always fits in one
+    pub(crate) fn alloc_typed<T: 'static>(&mut self) -> Option<(HeapPointer, usize)> {
+        self.alloc(mem::size_of::<HeapValue<T>>())
+    }
+
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The pocket sizes the heap sorts objects into
+    pub const fn pocket_sizes(&self) -> PocketSizes {
+        self.free_list.pocket_sizes
+    }
+
+    /// The heap's `[start, end)` address range
+    pub fn bounds(&self) -> (HeapPointer, HeapPointer) {
+        (self.start, self.start + self.size)
+    }
+
+    pub fn used(&self) -> usize {
+        self.free_list.used()
+    }
+
+    /// The number of bytes between the start of the heap and its bump frontier
+    pub fn span(&self) -> usize {
+        self.free_list.current.offset_from(self.free_list.start)
+    }
+
+    /// Resizes a block, in place if possible and otherwise by moving it to a new
+    /// block and freeing the old one, returning `None` if there's no room for it
+    pub(crate) fn realloc(
+        &mut self,
+        ptr: HeapPointer,
+        old_size: usize,
+        new_size: usize,
+    ) -> Option<HeapPointer> {
+        if self.free_list.resize_in_place(ptr, old_size, new_size) {
+            return Some(ptr);
+        }
+
+        let (new_ptr, _) = self.free_list.alloc(new_size)?;
+        unsafe {
+            ptr::copy_nonoverlapping(
+                ptr.as_ptr::<u8>(),
+                new_ptr.as_mut_ptr::<u8>(),
+                old_size.min(new_size),
+            );
+        }
+        self.free_list.dealloc(ptr, old_size);
+
+        Some(new_ptr)
+    }
+
+    /// Copies the heap's contents to `to`, pointing the heap and every object in
+    /// `roots` at the copy
+    ///
+    /// # Safety
+    ///
+    /// `to` must have room for the heap and not overlap it, the old memory isn't freed
+    pub(crate) unsafe fn move_to(&mut self, to: HeapPointer, roots: &mut [Pin<Box<RootedInner>>]) {
+        ptr::copy_nonoverlapping(
+            self.start.as_ptr::<u8>(),
+            to.as_mut_ptr::<u8>(),
+            self.span(),
+        );
 
-        if dbg!(self.fragmentation()) > 0.50 {
-            self.compact(roots);
+        for root in roots.iter_mut() {
+            let offset = root.data_ptr().offset_from(self.start);
+            root.as_mut().get_unchecked_mut().relocate(to + offset);
         }
+
+        self.free_list.rebase(to);
+        self.start = to;
+    }
+
+    /// Frees every block and zeroes everything that was allocated, leaving the
+    /// heap as it was when it was created
+    ///
+    /// # Safety
+    ///
+    /// Nothing in the heap may be used afterwards
+    pub(crate) unsafe fn clear(&mut self) {
+        memory::zero(self.start, self.span());
+        self.free_list.reset();
+    }
+
+    /// Every free block in the heap, sorted by address
+    pub fn free_blocks(&self) -> impl Iterator<Item = FreeBlock> {
+        self.free_list.free_blocks()
+    }
+
+    /// The size of the block an object of `size` bytes is given
+    pub fn block_size(&self, size: usize) -> usize {
+        self.free_list.block_size(size)
+    }
+
+    /// The size of the largest object the heap could currently fit
+    pub fn largest_free_block(&self) -> usize {
+        self.free_list.largest_free_block()
+    }
+
+    /// Returns an object's pocket to the free list
+    pub(crate) fn free(&mut self, ptr: HeapPointer, pocket_size: usize) {
+        self.free_list.dealloc(ptr, pocket_size);
+    }
+
+    pub(crate) fn collect(
+        &mut self,
+        roots: &mut Vec<Pin<Box<RootedInner>>>,
+        counters: &mut GcCounters,
+        on_reclaim: impl FnMut(&RootedInner),
+        on_relocate: impl FnMut(&RootedInner, HeapPointer),
+    ) {
+        counters.bytes_reclaimed += self.sweep(roots, on_reclaim);
+
+        if self.fragmentation() > 0.50 {
+            counters.compaction_count += 1;
+            self.compact(roots, on_relocate);
+        }
+    }
+
+    /// Reclaims all unrooted objects in the heap, returning the number of bytes freed
+    pub(crate) fn sweep(
+        &mut self,
+        roots: &mut Vec<Pin<Box<RootedInner>>>,
+        on_reclaim: impl FnMut(&RootedInner),
+    ) -> usize {
+        self.sweep_where(roots, |root| !root.is_rooted(), on_reclaim)
     }
 
-    pub fn sweep(&mut self, roots: &mut Vec<Pin<Box<RootedInner>>>) {
-        roots.retain(|root| {
-            if let ContainingHeap::Intermediate(pocket_size) = &root.heap {
-                if !root.is_rooted() {
-                    let raw_root: raw::TraitObject = unsafe { mem::transmute(root.value_ptr()) };
+    /// Frees every object `is_dead` picks out, returning the number of bytes reclaimed
+    ///
+    /// Like every other reclamation this counts the objects' own sizes rather
+    /// than their blocks', so that it balances the bytes they were allocated with
+    pub(crate) fn sweep_where(
+        &mut self,
+        roots: &mut Vec<Pin<Box<RootedInner>>>,
+        mut is_dead: impl FnMut(&RootedInner) -> bool,
+        mut on_reclaim: impl FnMut(&RootedInner),
+    ) -> usize {
+        let mut reclaimed = 0;
 
-                    PocketSize::reclaim(
-                        *pocket_size,
-                        HeapPointer::new(raw_root.data as usize),
-                        &mut self.free_list,
-                    );
+        let swept = mem::replace(roots, Vec::with_capacity(roots.len()));
+        for root in swept {
+            match root.heap {
+                ContainingHeap::Generation(_, pocket_size) if is_dead(&root) => {
+                    self.free_list.dealloc(root.data_ptr(), pocket_size);
+                    reclaimed += root.size();
+                    on_reclaim(&root);
 
-                    return false;
+                    // Weak handles may still point at the metadata
+                    let _ = rooted::leave_to_handles(root);
                 }
+
+                _ => roots.push(root),
             }
+        }
 
-            true
-        });
+        reclaimed
     }
 
-    pub fn compact(&mut self, roots: &mut Vec<Pin<Box<RootedInner>>>) {
-        for root in roots {
-            if let ContainingHeap::Intermediate(pocket_size) = &root.heap {
-                // TODO: Sort by low to high?
+    /// Slides every object in the heap down to its start, leaving all free space
+    /// in one contiguous block after them
+    ///
+    /// Pinned objects stay where they are, the free space in front of each of
+    /// them is kept as a free block
+    ///
+    /// Expects `roots` to already be swept, since everything in it is kept.
+    /// `on_relocate` is called with every moved object and its old address
+    pub(crate) fn compact(
+        &mut self,
+        roots: &mut [Pin<Box<RootedInner>>],
+        mut on_relocate: impl FnMut(&RootedInner, HeapPointer),
+    ) -> CompactStats {
+        let frontier = self.free_list.current;
+        let mut stats = CompactStats::default();
+
+        // Objects are only ever moved to lower addresses, so visiting them from low
+        // to high means an object is never overwritten before it's been moved
+        roots.sort_by_key(|root| root.data_ptr());
+
+        // Everything is about to be slid down over the freed blocks
+        self.free_list.reset();
+
+        for root in roots.iter_mut() {
+            if let ContainingHeap::Generation(_, pocket_size) = root.heap {
+                let from = root.data_ptr();
+                if root.pinned {
+                    let current = self.free_list.current;
+                    if from > current {
+                        self.free_list
+                            .large
+                            .push((current, from.offset_from(current)));
+                    }
+                    self.free_list.current = from + pocket_size;
+
+                    continue;
+                }
+
+                let (to, _) = self
+                    .free_list
+                    .alloc_aligned(pocket_size, root.align)
+                    .expect("Compacted objects should always fit");
+                debug_assert!(to <= from);
+
+                if to != from {
+                    unsafe {
+                        ptr::copy(from.as_ptr::<u8>(), to.as_mut_ptr::<u8>(), root.size());
+                        root.as_mut().get_unchecked_mut().relocate(to);
+                    }
+                    on_relocate(root, from);
+
+                    stats.objects_moved += 1;
+                }
             }
         }
 
-        todo!("Compact")
+        stats.bytes_reclaimed = frontier.offset_from(self.free_list.current);
+        stats
     }
 
-    // TODO: Fragmentation's kinda wack
+    /// The fraction of the heap's used span that's sitting free in pockets
     #[inline]
     pub fn fragmentation(&self) -> f32 {
-        1.0 - ((self.free_list.current.as_usize() - self.free_list.start.as_usize()) as f32
-            / self.size as f32)
+        let span = self.span();
+        if span == 0 {
+            return 0.0;
+        }
+
+        1.0 - (self.free_list.used() as f32 / span as f32)
     }
 }
 
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CompactStats {
+    pub objects_moved: usize,
+    /// How far the heap's free region grew, counting both swept objects and
+    /// the fragmented space that was closed up
+    pub bytes_reclaimed: usize,
+}
+
 impl Drop for SweepHeap {
     fn drop(&mut self) {
+        if !self.owns_memory {
+            return;
+        }
+
         let layout = alloc::alloc::Layout::from_size_align(self.size, memory::page_size())
             .expect("Failed to create heap layout");
 
@@ -100,6 +352,86 @@ impl Drop for SweepHeap {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "wraps around the address space")]
+    fn wrapping_region() {
+        SweepHeap::from_region(
+            HeapPointer::new(usize::MAX - 1024),
+            1024 * 4,
+            PocketSizes::default(),
+        );
+    }
+
+    #[test]
+    fn try_new_failures() {
+        let align = memory::page_size();
+        assert_eq!(
+            SweepHeap::try_new(usize::MAX).err(),
+            Some(HeapCreateError::InvalidLayout {
+                size: usize::MAX,
+                align,
+            })
+        );
+
+        // A valid layout that's far too large for any allocator to provide
+        let size = (isize::MAX as usize + 1) - align;
+        assert_eq!(
+            SweepHeap::try_new(size).err(),
+            Some(HeapCreateError::AllocationFailed { size })
+        );
+    }
+
+    #[test]
+    fn alloc_typed() {
+        const HUGE_POCKET: usize = 1024 * 32;
+
+        let mut heap = SweepHeap::new(1024 * 128);
+        crate::assert_fits_pocket::<[u8; HUGE_POCKET]>();
+
+        let (_, under) = heap.alloc_typed::<[u8; HUGE_POCKET]>().unwrap();
+        assert_eq!(under, HUGE_POCKET);
+        assert!(heap.free_list.large.is_empty());
+
+        let (over_ptr, over) = heap.alloc_typed::<[u8; HUGE_POCKET + 1]>().unwrap();
+        assert_eq!(over, HUGE_POCKET + memory::MAX_ALIGN);
+        heap.free(over_ptr, HUGE_POCKET + 1);
+        assert_eq!(heap.free_list.large, vec![(over_ptr, over)]);
+    }
+
+    #[test]
+    fn realloc_grows_in_place() {
+        let mut heap = SweepHeap::new(1024 * 4);
+
+        let (ptr, _) = heap.alloc(16).unwrap();
+        unsafe { ptr.as_mut_ptr::<u64>().write(0xBA11A57) };
+
+        let grown = heap.realloc(ptr, 16, 100).unwrap();
+        assert_eq!(grown, ptr);
+        assert_eq!(heap.used(), 128);
+        assert_eq!(unsafe { grown.as_ptr::<u64>().read() }, 0xBA11A57);
+    }
+
+    #[test]
+    fn realloc_relocates() {
+        let mut heap = SweepHeap::new(1024 * 4);
+
+        let (ptr, _) = heap.alloc(16).unwrap();
+        unsafe { ptr.as_mut_ptr::<u64>().write(0xBA11A57) };
+        let _blocker = heap.alloc(16).unwrap();
+
+        let moved = heap.realloc(ptr, 16, 100).unwrap();
+        assert_ne!(moved, ptr);
+        assert_eq!(unsafe { moved.as_ptr::<u64>().read() }, 0xBA11A57);
+
+        // The old block went back to the free list
+        assert_eq!(heap.alloc(16).map(|(ptr, _)| ptr), Some(ptr));
+    }
+}
+
 /*
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,172 @@
+use crate::{memory::AllocId, rooted::Rooted};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::any::Any;
+
+/// Reports the heap objects a value holds references to
+///
+/// Every `Rooted` inside of a value is an edge of the object graph, and `trace`
+/// must hand each of them to the [`Tracer`]
+///
+/// Each reported edge must be a handle the value owns. An object has exactly one
+/// handle, and collections that mark through `Trace` take an object reported as
+/// an edge to be held only by the object reporting it, so it's reclaimed along
+/// with that object even if it's rooted. Reporting a handle the value doesn't
+/// own, one it borrows or shares through an `Rc`, lets the object it points to
+/// be freed while the program still holds its handle
+pub trait Trace {
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+/// Collects the edges reported by [`Trace`] implementations
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+    edges: Vec<AllocId>,
+    owner: Option<AllocId>,
+}
+
+impl Tracer {
+    pub const fn new() -> Self {
+        Self {
+            edges: Vec::new(),
+            owner: None,
+        }
+    }
+
+    /// The object being traced, if the heap is the one tracing it
+    pub(crate) const fn owner(&self) -> Option<AllocId> {
+        self.owner
+    }
+
+    pub(crate) fn set_owner(&mut self, owner: AllocId) {
+        self.owner = Some(owner);
+    }
+
+    pub fn edge<T: ?Sized + Any>(&mut self, rooted: &Rooted<T>) {
+        self.edges.push(rooted.id());
+    }
+
+    pub fn edges(&self) -> &[AllocId] {
+        &self.edges
+    }
+
+    pub fn clear(&mut self) {
+        self.edges.clear();
+    }
+}
+
+impl<T: ?Sized + Any> Trace for Rooted<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(self);
+    }
+}
+
+impl<T: Trace> Trace for Option<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(value) = self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl<T: Trace> Trace for [T] {
+    fn trace(&self, tracer: &mut Tracer) {
+        for value in self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl<T: Trace, const N: usize> Trace for [T; N] {
+    fn trace(&self, tracer: &mut Tracer) {
+        self[..].trace(tracer);
+    }
+}
+
+impl<T: Trace> Trace for Vec<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        self[..].trace(tracer);
+    }
+}
+
+impl<T: ?Sized + Trace> Trace for Box<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        (**self).trace(tracer);
+    }
+}
+
+macro_rules! trace_tuples {
+    ($( ($($name:ident),+) ),* $(,)?) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($name: Trace),+> Trace for ($($name,)+) {
+                fn trace(&self, tracer: &mut Tracer) {
+                    let ($($name,)+) = self;
+                    $( $name.trace(tracer); )+
+                }
+            }
+        )*
+    };
+}
+
+trace_tuples! {
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+}
+
+macro_rules! trace_leaves {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Trace for $ty {
+                #[inline]
+                fn trace(&self, _tracer: &mut Tracer) {}
+            }
+        )*
+    };
+}
+
+trace_leaves! {
+    (), bool, char, str, String,
+    u8, u16, u32, u64, u128, usize,
+    i8, i16, i32, i64, i128, isize,
+    f32, f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BumpHeap;
+
+    #[test]
+    fn containers() {
+        let mut bump = BumpHeap::default();
+        let (a, b): (Rooted<usize>, Rooted<usize>) =
+            unsafe { (bump.alloc_unchecked(1), bump.alloc_unchecked(2)) };
+        let mut tracer = Tracer::new();
+
+        None::<Rooted<usize>>.trace(&mut tracer);
+        assert!(tracer.edges().is_empty());
+
+        let a = Box::new(a);
+        a.trace(&mut tracer);
+        let a = *a;
+        assert_eq!(tracer.edges(), &[a.id()]);
+        tracer.clear();
+
+        let vec = vec![a, b];
+        let ids = [vec[0].id(), vec[1].id()];
+        vec.trace(&mut tracer);
+        assert_eq!(tracer.edges(), &ids);
+        tracer.clear();
+
+        vec[..1].trace(&mut tracer);
+        assert_eq!(tracer.edges(), &ids[..1]);
+        tracer.clear();
+
+        (1u32, Some(vec), String::new()).trace(&mut tracer);
+        assert_eq!(tracer.edges(), &ids);
+    }
+}
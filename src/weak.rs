@@ -0,0 +1,163 @@
+use crate::{
+    bump_heap::BumpHeap,
+    memory::AllocId,
+    rooted::{Rooted, RootedInner},
+};
+use alloc::boxed::Box;
+use core::{any::Any, marker::PhantomData};
+
+/// A handle that doesn't keep its object alive, and is cleared once the object
+/// is reclaimed
+///
+/// Unrooted objects are only reclaimed by the collection that finds them, so a
+/// weak handle can still see its object between the last strong handle being
+/// dropped and the next collection
+#[derive(Debug)]
+pub struct Weak<T: Sized + Any> {
+    static_inner: *mut RootedInner,
+    __type: PhantomData<T>,
+}
+
+impl<T: Sized + Any> Weak<T> {
+    pub fn new(rooted: &Rooted<T>) -> Self {
+        let static_inner = unsafe {
+            let inner = rooted.inner() as *const RootedInner as *mut RootedInner;
+            (*inner).weak_handles += 1;
+
+            inner
+        };
+
+        Self {
+            static_inner,
+            __type: PhantomData,
+        }
+    }
+
+    /// Whether the object has been reclaimed, or its heap has been dropped
+    pub fn is_cleared(&self) -> bool {
+        unsafe { (*self.static_inner).is_null() }
+    }
+
+    /// The object's identity, or `None` if the handle has been cleared
+    pub fn id(&self) -> Option<AllocId> {
+        if self.is_cleared() {
+            None
+        } else {
+            Some(unsafe { (*self.static_inner).id })
+        }
+    }
+
+    /// Borrows the object's value for as long as `heap` is borrowed, or returns
+    /// `None` if the handle has been cleared
+    ///
+    /// The same as with [`Handle::get`](crate::Handle::get), borrowing the heap
+    /// keeps anything that can collect from moving the object out from under
+    /// the reference
+    ///
+    /// # Safety
+    ///
+    /// The object's [`Rooted`] can still be written to while the heap is borrowed,
+    /// so the reference must not be held across a write through it, see
+    /// [`BumpHeap::try_alloc_unchecked`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object isn't in `heap`
+    pub unsafe fn get<'a>(&'a self, heap: &'a BumpHeap) -> Option<&'a T> {
+        let inner = unsafe { &*self.static_inner };
+        let value = inner.downcast_ref()?;
+        assert!(
+            heap.contains(inner.data_ptr()),
+            "Accessed a Weak through a heap it isn't in",
+        );
+
+        Some(value)
+    }
+}
+
+impl<T: Sized + Any> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        unsafe { (*self.static_inner).weak_handles += 1 };
+
+        Self {
+            static_inner: self.static_inner,
+            __type: PhantomData,
+        }
+    }
+}
+
+impl<T: Sized + Any> Drop for Weak<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let inner = &mut *self.static_inner;
+            inner.weak_handles -= 1;
+
+            // The object is gone and left the metadata for its last handle to free
+            if inner.is_null() && inner.weak_handles == 0 && !inner.is_rooted() {
+                drop(Box::from_raw(self.static_inner));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BumpHeap;
+
+    #[test]
+    fn cleared_once_reclaimed() {
+        let mut bump = BumpHeap::default();
+
+        let tenured: Rooted<usize> = unsafe { bump.alloc_unchecked(2) };
+        let weak_tenured = Weak::new(&tenured);
+        let cloned = weak_tenured.clone();
+        bump.scavenge();
+        assert_eq!(unsafe { weak_tenured.get(&bump) }, Some(&2));
+
+        let young: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let weak_young = Weak::new(&young);
+
+        drop(young);
+        bump.scavenge();
+        assert!(weak_young.is_cleared());
+        assert_eq!(unsafe { weak_young.get(&bump) }, None);
+
+        drop(tenured);
+        bump.major();
+        assert!(weak_tenured.is_cleared() && cloned.is_cleared());
+        assert_eq!(weak_tenured.id(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Accessed a Weak through a heap it isn't in")]
+    fn get_through_other_heap() {
+        let (mut bump, other) = (BumpHeap::default(), BumpHeap::default());
+        let rooted: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+
+        unsafe { Weak::new(&rooted).get(&other) };
+    }
+
+    #[test]
+    fn outlives_heap_and_handle() {
+        let mut bump = BumpHeap::default();
+        let rooted: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let weak = Weak::new(&rooted);
+
+        drop(bump);
+        assert!(weak.is_cleared());
+        drop(rooted);
+        assert!(weak.is_cleared());
+    }
+
+    #[test]
+    fn outlived_by_handle() {
+        let mut bump = BumpHeap::default();
+        let rooted: Rooted<usize> = unsafe { bump.alloc_unchecked(1) };
+        let weak = Weak::new(&rooted);
+
+        drop(bump);
+        drop(weak);
+        assert_eq!(rooted.try_deref(), None);
+    }
+}
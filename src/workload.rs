@@ -0,0 +1,211 @@
+use crate::{bump_heap::BumpHeap, handle::Handle, stress::SplitMix64};
+use alloc::collections::VecDeque;
+use core::time::Duration;
+use std::time::Instant;
+
+/// The most survivors a churn workload keeps alive at once, the oldest are
+/// dropped to make room for new ones
+const MAX_CHURN_LIVE: usize = 200;
+
+/// An allocation pattern for [`BumpHeap::run_workload`]
+///
+/// Everything random is derived from the seed, so running the same spec against
+/// the same heap options gives the same collections
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WorkloadSpec {
+    pub shape: WorkloadShape,
+    /// The number of objects to allocate
+    pub allocations: usize,
+    /// The sizes objects are drawn from, uniformly
+    pub sizes: &'static [ObjectSize],
+    /// The percentage of objects that are kept alive after they're allocated
+    pub survival_percent: u8,
+    /// Scavenges after every this many allocations, on top of the scavenges
+    /// the heap runs when it fills up
+    pub scavenge_every: Option<usize>,
+    /// Runs a major collection after every this many allocations
+    pub major_every: Option<usize>,
+    pub seed: u64,
+}
+
+impl WorkloadSpec {
+    /// A churn of `allocations` word sized objects that all die young
+    pub const fn churn(allocations: usize) -> Self {
+        Self {
+            shape: WorkloadShape::Churn,
+            allocations,
+            sizes: &[ObjectSize::Word],
+            survival_percent: 0,
+            scavenge_every: None,
+            major_every: None,
+            seed: 0,
+        }
+    }
+}
+
+/// How a workload's surviving objects are kept alive
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WorkloadShape {
+    /// Survivors are kept until the churn has outlived a few hundred newer ones
+    Churn,
+    /// `live` objects are allocated up front and kept alive, with every
+    /// survivor replacing a random one of them
+    SteadyState { live: usize },
+    /// Survivors are kept until `burst` objects have been allocated, then all
+    /// dropped at once
+    Bursty { burst: usize },
+}
+
+/// The size of a workload's objects
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ObjectSize {
+    /// A single `u64`
+    Word,
+    /// Four `u64`s
+    Quad,
+    /// Sixteen `u64`s
+    Line,
+}
+
+/// What running a [`WorkloadSpec`] took
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WorkloadStats {
+    pub elapsed: Duration,
+    pub allocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_promoted: usize,
+    pub scavenges: usize,
+    pub majors: usize,
+    pub compactions: usize,
+}
+
+// The handles are only held to keep their objects alive
+#[allow(dead_code)]
+enum Object {
+    Word(Handle<u64>),
+    Quad(Handle<[u64; 4]>),
+    Line(Handle<[u64; 16]>),
+}
+
+pub(crate) fn run(heap: &mut BumpHeap, spec: WorkloadSpec) -> WorkloadStats {
+    assert!(!spec.sizes.is_empty(), "Workloads need at least one size");
+
+    let mut rng = SplitMix64(spec.seed);
+    let mut live = VecDeque::new();
+    let before = *heap.counters();
+    let start = Instant::now();
+
+    if let WorkloadShape::SteadyState { live: count } = spec.shape {
+        live.extend((0..count).map(|_| allocate(heap, &mut rng, spec.sizes)));
+    }
+
+    for i in 1..=spec.allocations {
+        let object = allocate(heap, &mut rng, spec.sizes);
+
+        if rng.below(100) < u64::from(spec.survival_percent) {
+            match spec.shape {
+                WorkloadShape::Churn => {
+                    if live.len() >= MAX_CHURN_LIVE {
+                        live.pop_front();
+                    }
+                    live.push_back(object);
+                }
+                WorkloadShape::SteadyState { .. } if !live.is_empty() => {
+                    let index = rng.below(live.len() as u64) as usize;
+                    live[index] = object;
+                }
+                WorkloadShape::SteadyState { .. } => drop(object),
+                WorkloadShape::Bursty { .. } => live.push_back(object),
+            }
+        }
+
+        if let WorkloadShape::Bursty { burst } = spec.shape {
+            if burst != 0 && i % burst == 0 {
+                live.clear();
+            }
+        }
+
+        if spec.scavenge_every.is_some_and(|every| i % every == 0) {
+            heap.scavenge();
+        }
+        if spec.major_every.is_some_and(|every| i % every == 0) {
+            heap.major();
+        }
+    }
+
+    let elapsed = start.elapsed();
+    drop(live);
+
+    let after = heap.counters();
+    WorkloadStats {
+        elapsed,
+        allocations: after.total_allocations - before.total_allocations,
+        bytes_allocated: after.bytes_allocated - before.bytes_allocated,
+        bytes_promoted: after.bytes_promoted - before.bytes_promoted,
+        scavenges: after.scavenge_count - before.scavenge_count,
+        majors: after.major_count - before.major_count,
+        compactions: after.compaction_count - before.compaction_count,
+    }
+}
+
+fn allocate(heap: &mut BumpHeap, rng: &mut SplitMix64, sizes: &[ObjectSize]) -> Object {
+    let value = rng.next();
+
+    match sizes[rng.below(sizes.len() as u64) as usize] {
+        ObjectSize::Word => Object::Word(heap.alloc(value)),
+        ObjectSize::Quad => Object::Quad(heap.alloc([value; 4])),
+        ObjectSize::Line => Object::Line(heap.alloc([value; 16])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bump_heap::BumpOptions, rooted::HeapValue};
+    use core::mem;
+
+    #[test]
+    fn churn_scavenges() {
+        let mut bump = BumpHeap::new(BumpOptions::new().young_heap_size(1024 * 4));
+        let per_young = 1024 * 4 / mem::size_of::<HeapValue<u64>>();
+
+        let stats = bump.run_workload(WorkloadSpec::churn(1000));
+        assert_eq!(stats.allocations, 1000);
+        assert_eq!(stats.scavenges, (1000 - 1) / per_young);
+        assert_eq!(stats.bytes_promoted, 0);
+        assert_eq!(stats.majors, 0);
+    }
+
+    #[test]
+    fn shapes() {
+        let sizes = &[ObjectSize::Word, ObjectSize::Quad, ObjectSize::Line];
+        let shapes = [
+            WorkloadShape::Churn,
+            WorkloadShape::SteadyState { live: 50 },
+            WorkloadShape::Bursty { burst: 64 },
+        ];
+
+        for &shape in &shapes {
+            let spec = WorkloadSpec {
+                shape,
+                sizes,
+                survival_percent: 25,
+                scavenge_every: Some(100),
+                major_every: Some(500),
+                ..WorkloadSpec::churn(2000)
+            };
+            let options = BumpOptions::new()
+                .young_heap_size(1024 * 16)
+                .old_heap_size(1024 * 128);
+
+            let first = BumpHeap::new(options).run_workload(spec);
+            let second = BumpHeap::new(options).run_workload(spec);
+            assert_eq!(first.majors, 4);
+            assert!(first.bytes_promoted > 0);
+            assert_eq!(
+                (first.scavenges, first.bytes_promoted),
+                (second.scavenges, second.bytes_promoted),
+            );
+        }
+    }
+}